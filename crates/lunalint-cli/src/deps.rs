@@ -0,0 +1,94 @@
+use std::{
+    collections::{BTreeSet, HashSet, VecDeque},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use lunalint_core::workspace::Workspace;
+
+/// Module graph of the files built from `require` calls with string literals
+#[derive(Default)]
+pub struct Graph {
+    /// Files and modules by their displayed paths
+    nodes: BTreeSet<String>,
+    /// Edges from files to required modules. Modules which are not found are named by `require`.
+    edges: BTreeSet<(String, String, bool)>,
+}
+
+impl Graph {
+    /// Build the graph of the files and modules required by them, transitively.
+    pub fn build(files: &[(PathBuf, Arc<Workspace>)]) -> Self {
+        let mut graph = Self::default();
+        let mut visited = HashSet::new();
+        let mut queue = files.iter().cloned().collect::<VecDeque<_>>();
+        while let Some((path, workspace)) = queue.pop_front() {
+            if !visited.insert(absolute(&path)) {
+                continue;
+            }
+            let from = display(&path);
+            graph.nodes.insert(from.clone());
+            let Some(module) = workspace.module(&path) else {
+                continue;
+            };
+            let library = workspace.config().library();
+            for require in module.requires() {
+                match require.path() {
+                    Some(dep) => {
+                        graph.edges.insert((from.clone(), display(dep), true));
+                        queue.push_back((dep.to_path_buf(), Arc::clone(&workspace)));
+                    }
+                    // Modules of the library are not files
+                    None if library.global(require.name()).is_some() => {}
+                    None => {
+                        graph
+                            .edges
+                            .insert((from.clone(), require.name().to_owned(), false));
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    /// Write the graph in Graphviz format. Modules which are not found are dashed.
+    pub fn write_dot<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+        writeln!(w, "digraph modules {{")?;
+        for node in self.nodes.iter() {
+            writeln!(w, "    {};", quote(node))?;
+        }
+        for (from, to, found) in self.edges.iter() {
+            let style = if *found { "" } else { " [style=dashed]" };
+            writeln!(w, "    {} -> {}{};", quote(from), quote(to), style)?;
+        }
+        writeln!(w, "}}")
+    }
+
+    /// Write the edges of the graph, one per line.
+    pub fn write_text<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+        for (from, to, found) in self.edges.iter() {
+            let suffix = if *found { "" } else { " (not found)" };
+            writeln!(w, "{from} -> {to}{suffix}")?;
+        }
+        Ok(())
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Path relative to the current directory if it is under the directory.
+fn display(path: &Path) -> String {
+    let path = absolute(path);
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok().map(Path::to_path_buf))
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Quote an ID of Graphviz.
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+
+/// Collect Lua files from files, directories and glob patterns.
+/// Directories are searched recursively for `*.lua` files honoring `.gitignore`.
+/// Files matching any of `excludes` are skipped. The result is sorted and deduplicated.
+pub fn collect_files(inputs: &[String], excludes: &[String]) -> Result<Vec<PathBuf>, String> {
+    let excludes = build_excludes(excludes)?;
+    let mut files = BTreeSet::new();
+
+    for input in inputs {
+        let path = Path::new(input);
+        if path.exists() {
+            collect_path(path, &excludes, &mut files);
+            continue;
+        }
+        if !is_glob(input) {
+            return Err(format!("no such file or directory: {}", input));
+        }
+        let paths = glob::glob(input).map_err(|e| format!("invalid pattern `{}`: {}", input, e))?;
+        let mut matched = false;
+        for path in paths.flatten() {
+            matched = true;
+            collect_path(&path, &excludes, &mut files);
+        }
+        if !matched {
+            return Err(format!("pattern `{}` did not match any files", input));
+        }
+    }
+
+    Ok(files.into_iter().collect())
+}
+
+fn collect_path(path: &Path, excludes: &GlobSet, files: &mut BTreeSet<PathBuf>) {
+    if excludes.is_match(normalize(path)) {
+        return;
+    }
+    if !path.is_dir() {
+        // Explicitly specified files are linted regardless of the extension
+        files.insert(normalize(path));
+        return;
+    }
+    let excludes = excludes.clone();
+    let walker = WalkBuilder::new(path)
+        // Respect .gitignore even outside of git repositories
+        .require_git(false)
+        .filter_entry(move |entry| !excludes.is_match(normalize(entry.path())))
+        .build();
+    for entry in walker.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_some_and(|t| t.is_file()) && is_lua_file(path) {
+            files.insert(normalize(path));
+        }
+    }
+}
+
+/// Build exclude patterns. As in `.gitignore`, patterns without `/` match at any depth, and
+/// files under a matched directory are excluded as well.
+fn build_excludes(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_end_matches('/');
+        let mut globs = vec![pattern.to_owned(), format!("{}/**", pattern)];
+        if !pattern.contains('/') {
+            globs.push(format!("**/{}", pattern));
+            globs.push(format!("**/{}/**", pattern));
+        }
+        for glob in globs {
+            let glob = Glob::new(&glob)
+                .map_err(|e| format!("invalid exclude pattern `{}`: {}", pattern, e))?;
+            builder.add(glob);
+        }
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+fn is_lua_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "lua")
+}
+
+/// Strip `./` so that the same file is not linted twice.
+fn normalize(path: &Path) -> PathBuf {
+    path.strip_prefix(".").unwrap_or(path).to_path_buf()
+}
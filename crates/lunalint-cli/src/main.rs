@@ -1,68 +1,448 @@
-use std::{fs::OpenOptions, io::Read, path::PathBuf, sync::Arc};
+mod deps;
+mod files;
+mod rename;
 
-use clap::Parser;
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use clap::{Args, Parser, Subcommand};
 use lunalint_core::{
     ariadne::{Color, Fmt},
+    diagnostics::{format::OutputFormat, sort_reports, LintReport},
     env_logger, eprint_report,
+    fix::{apply_fixes, Applicability},
     location::SourceInfo,
-    log, parse, pass, Context,
+    log, parse, pass,
+    workspace::Workspace,
+    Config, Context,
 };
+use rayon::prelude::*;
+
+/// Stack size of worker threads. The parser is recursive and needs more than the default.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Maximum number of times to re-lint a file after applying fixes.
+const MAX_FIX_ITERATIONS: usize = 10;
 
 #[derive(Parser)]
-pub struct Args {
-    /// Sets a custom config file
-    #[arg(value_name = "FILE")]
-    input_file: PathBuf,
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// Lua files, directories or glob patterns to lint
+    #[arg(value_name = "PATH", required = true)]
+    inputs: Vec<String>,
+
+    /// Use the given configuration file instead of searching `lunalint.toml` or `.luarc.json`
+    #[arg(long, value_name = "CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Skip files and directories matching the glob pattern
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Output format: text, json, sarif, checkstyle or junit. Reports other than text are written
+    /// to stdout
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    format: OutputFormat,
+
+    /// Number of threads. Defaults to the number of CPUs
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Apply safe fixes and write the files
+    #[arg(long)]
+    fix: bool,
+
+    /// Print fixes as a unified diff instead of writing the files
+    #[arg(long, alias = "fix-dry-run", conflicts_with = "fix")]
+    diff: bool,
+
+    /// Apply unsafe fixes as well, which may change the behavior of the program
+    #[arg(long)]
+    unsafe_fixes: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lint files and optionally fix them
+    Check(CheckArgs),
+    /// Rename a variable and all its references
+    Rename {
+        /// Position of the variable, 1-indexed
+        #[arg(value_name = "FILE:LINE:COL")]
+        target: String,
+
+        /// New name of the variable
+        #[arg(value_name = "NEW")]
+        new_name: String,
+
+        /// Use the given configuration file instead of searching `lunalint.toml` or `.luarc.json`
+        #[arg(long, value_name = "CONFIG")]
+        config: Option<PathBuf>,
+
+        /// Print the rename as a unified diff instead of writing the file
+        #[arg(long)]
+        diff: bool,
+    },
+    /// Print modules required by the files
+    Deps {
+        /// Lua files, directories or glob patterns
+        #[arg(value_name = "PATH", required = true)]
+        inputs: Vec<String>,
+
+        /// Use the given configuration file instead of searching `lunalint.toml` or `.luarc.json`
+        #[arg(long, value_name = "CONFIG")]
+        config: Option<PathBuf>,
+
+        /// Skip files and directories matching the glob pattern
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Print the graph in Graphviz format
+        #[arg(long)]
+        dot: bool,
+    },
+}
+
+impl CheckArgs {
+    /// Most unsafe fixes to apply, if fixing is requested.
+    fn fix_applicability(&self) -> Option<Applicability> {
+        if !self.fix && !self.diff {
+            return None;
+        }
+        Some(if self.unsafe_fixes {
+            Applicability::Unsafe
+        } else {
+            Applicability::Safe
+        })
+    }
+}
+
+/// Result of linting a file
+struct FileResult {
+    /// Reports of the file. Reports after applying fixes if `--fix` is specified.
+    reports: Vec<Arc<LintReport>>,
+    /// Unified diff of fixes if `--diff` is specified
+    diff: Option<String>,
 }
 
 fn main() {
     env_logger::init();
 
-    let args = Args::parse();
+    match Cli::parse().command {
+        Command::Check(args) => run_check(&args),
+        Command::Rename {
+            target,
+            new_name,
+            config,
+            diff,
+        } => run_rename(&target, &new_name, config, diff),
+        Command::Deps {
+            inputs,
+            config,
+            exclude,
+            dot,
+        } => run_deps(&inputs, &exclude, config, dot),
+    }
+}
+
+/// Lint the files given by `args`, then print reports or apply fixes.
+fn run_check(args: &CheckArgs) {
+    let files = match files::collect_files(&args.inputs, &args.exclude) {
+        Ok(files) => files,
+        Err(e) => {
+            error(e);
+            std::process::exit(1);
+        }
+    };
+    log::debug!("input files: {:?}", files);
+
+    if let Err(e) = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.unwrap_or(0))
+        .stack_size(STACK_SIZE)
+        .build_global()
+    {
+        log::warn!("failed to configure thread pool: {}", e);
+    }
+
+    let configs = ConfigCache::new(args.config.clone());
+    // Files are added to workspaces before linting any of them, so that globals of all files are
+    // visible. Files are summarized in parallel as well.
+    let workspaces = files
+        .iter()
+        .map(|path| configs.get(path))
+        .collect::<Vec<_>>();
+    files
+        .par_iter()
+        .zip(workspaces.par_iter())
+        .for_each(|(path, workspace)| {
+            workspace.module(path);
+        });
+    // The order of results is the same as `files`, which is sorted
+    let results = files
+        .par_iter()
+        .zip(workspaces.par_iter())
+        .map(|(path, workspace)| lint_file(path, workspace, args))
+        .collect::<Vec<_>>();
+
+    let mut saw_error = false;
+    let mut reports = configs.into_reports();
+    let mut diffs = Vec::new();
+    for result in results {
+        match result {
+            Ok(result) => {
+                reports.extend(result.reports);
+                diffs.extend(result.diff);
+            }
+            Err(msg) => {
+                error(msg);
+                saw_error = true;
+            }
+        }
+    }
+
+    if args.diff {
+        let mut stdout = std::io::stdout().lock();
+        for diff in diffs.iter() {
+            let _ = write!(stdout, "{}", diff);
+        }
+        std::process::exit(if saw_error || !diffs.is_empty() { 1 } else { 0 });
+    }
 
-    log::debug!("input file: {:?}", args.input_file);
+    saw_error |= !reports.is_empty();
 
-    let Ok(mut file) = OpenOptions::new()
+    if args.format == OutputFormat::Text {
+        for report in reports.iter() {
+            eprint_report(report);
+        }
+    } else {
+        let files = files
+            .iter()
+            .map(|f| f.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        let mut stdout = std::io::stdout().lock();
+        if let Err(e) = args
+            .format
+            .formatter()
+            .format(&files, &reports, &mut stdout)
+        {
+            error(format!("failed to write reports: {}", e));
+            std::process::exit(1);
+        }
+        // Keep stdout machine-readable
+        if saw_error {
+            std::process::exit(1);
+        }
+    }
+
+    if saw_error {
+        exit_with_error();
+    }
+}
+
+/// Lint a file and apply fixes if requested.
+fn lint_file(
+    path: &Path,
+    workspace: &Arc<Workspace>,
+    args: &CheckArgs,
+) -> Result<FileResult, String> {
+    let mut file = OpenOptions::new()
         .read(true)
-        .open(&args.input_file)
-        .map_err(|e| error(format!("failed to open file: {}", e)))
-    else {
-        std::process::exit(1);
-    };
+        .open(path)
+        .map_err(|e| format!("failed to open file `{}`: {}", path.display(), e))?;
 
     let mut code = String::new();
-    let Ok(_) = file.read_to_string(&mut code).map_err(|e| {
-        error(format!("failed to read file: {}", e));
-    }) else {
-        std::process::exit(1);
+    file.read_to_string(&mut code)
+        .map_err(|e| format!("failed to read file `{}`: {}", path.display(), e))?;
+
+    let path_str = path.to_string_lossy().into_owned();
+    let lint = |code: &str| {
+        let src = Arc::new(SourceInfo::new(path_str.clone(), code.to_owned()));
+        lint_source(src, Arc::clone(workspace))
+    };
+
+    let Some(applicability) = args.fix_applicability() else {
+        return Ok(FileResult {
+            reports: lint(&code),
+            diff: None,
+        });
+    };
+
+    // Apply fixes until no more fixes are available
+    let mut fixed = code.clone();
+    let mut reports = lint(&fixed);
+    for _ in 0..MAX_FIX_ITERATIONS {
+        let fixes = reports
+            .iter()
+            .flat_map(|r| r.fixes())
+            .filter(|f| f.applicability() <= applicability)
+            .collect::<Vec<_>>();
+        let (new, applied) = apply_fixes(&fixed, &fixes);
+        if applied == 0 || new == fixed {
+            break;
+        }
+        log::debug!("applied {} fixes to {:?}", applied, path);
+        fixed = new;
+        reports = lint(&fixed);
+    }
+
+    if fixed == code {
+        return Ok(FileResult {
+            reports,
+            diff: None,
+        });
+    }
+    if args.diff {
+        let diff = similar::TextDiff::from_lines(&code, &fixed)
+            .unified_diff()
+            .header(&format!("a/{}", path_str), &format!("b/{}", path_str))
+            .to_string();
+        return Ok(FileResult {
+            reports,
+            diff: Some(diff),
+        });
+    }
+    std::fs::write(path, &fixed)
+        .map_err(|e| format!("failed to write file `{}`: {}", path.display(), e))?;
+    Ok(FileResult {
+        reports,
+        diff: None,
+    })
+}
+
+fn run_rename(target: &str, new_name: &str, config: Option<PathBuf>, diff: bool) {
+    let (path, renamed) = match rename::rename_at(target, new_name, config) {
+        Ok(result) => result,
+        Err(e) => {
+            error(e);
+            std::process::exit(1);
+        }
+    };
+    let result = if diff {
+        std::fs::read_to_string(&path).map(|code| {
+            let path_str = path.to_string_lossy();
+            print!(
+                "{}",
+                similar::TextDiff::from_lines(&code, &renamed)
+                    .unified_diff()
+                    .header(&format!("a/{}", path_str), &format!("b/{}", path_str))
+            );
+        })
+    } else {
+        std::fs::write(&path, &renamed)
     };
+    if let Err(e) = result {
+        error(format!(
+            "failed to rewrite file `{}`: {}",
+            path.display(),
+            e
+        ));
+        std::process::exit(1);
+    }
+}
 
-    let src = Arc::new(SourceInfo::new(
-        args.input_file.to_str().unwrap().to_string(),
-        code,
-    ));
+fn run_deps(inputs: &[String], exclude: &[String], config: Option<PathBuf>, dot: bool) {
+    let files = match files::collect_files(inputs, exclude) {
+        Ok(files) => files,
+        Err(e) => {
+            error(e);
+            std::process::exit(1);
+        }
+    };
+    let configs = ConfigCache::new(config);
+    let files = files
+        .into_iter()
+        .map(|path| {
+            let workspace = configs.get(&path);
+            (path, workspace)
+        })
+        .collect::<Vec<_>>();
 
-    let Ok(ast) = parse(Arc::clone(&src)).map_err(|e| {
-        eprint_report(&e);
-    }) else {
+    let graph = deps::Graph::build(&files);
+    let stdout = std::io::stdout().lock();
+    let result = if dot {
+        graph.write_dot(stdout)
+    } else {
+        graph.write_text(stdout)
+    };
+    if let Err(e) = result {
+        error(format!("failed to write the graph: {}", e));
         std::process::exit(1);
+    }
+}
+
+/// Lint a source with its own context. Reports are sorted by their locations.
+fn lint_source(src: Arc<SourceInfo>, workspace: Arc<Workspace>) -> Vec<Arc<LintReport>> {
+    let ast = match parse(Arc::clone(&src)) {
+        Ok(ast) => ast,
+        Err(e) => return vec![Arc::new(e)],
     };
 
-    log::debug!("successfully parsed file");
+    log::debug!("successfully parsed file: {:?}", src.path());
 
-    let mut ctx = Context::new(src);
-    ctx.resolver_mut().go(&ast);
+    let mut ctx = Context::with_workspace(src, workspace);
+    ctx.analyze(&ast);
 
     let ctx = Arc::new(ctx);
     let mut pass_manager = pass::PassManager::with_all_passes(Arc::clone(&ctx));
     pass_manager.run(&ast);
 
-    for report in ctx.reports().iter() {
-        eprint_report(report);
+    let mut reports = ctx.reports().clone();
+    sort_reports(&mut reports);
+    reports
+}
+
+/// Configurations and workspaces shared by files in the same project. Each configuration file is
+/// loaded once.
+struct ConfigCache {
+    /// Configuration file given by `--config`
+    explicit: Option<PathBuf>,
+    workspaces: Mutex<HashMap<Option<PathBuf>, Arc<Workspace>>>,
+    reports: Mutex<Vec<Arc<LintReport>>>,
+}
+
+impl ConfigCache {
+    fn new(explicit: Option<PathBuf>) -> Self {
+        Self {
+            explicit,
+            workspaces: Mutex::new(HashMap::new()),
+            reports: Mutex::new(Vec::new()),
+        }
     }
 
-    if ctx.saw_error() {
-        exit_with_error();
+    /// Workspace of the configuration of the file. The file is added to the workspace.
+    fn get(&self, file: &Path) -> Arc<Workspace> {
+        let path = self.explicit.clone().or_else(|| Config::find(file));
+        let mut workspaces = self.workspaces.lock().unwrap();
+        let workspace = workspaces.entry(path).or_insert_with_key(|path| {
+            let (config, reports) = match path {
+                Some(path) => Config::load(path),
+                None => (Config::default(), Vec::new()),
+            };
+            self.reports
+                .lock()
+                .unwrap()
+                .extend(reports.into_iter().map(Arc::new));
+            Arc::new(Workspace::new(Arc::new(config)))
+        });
+        workspace.add_file(file);
+        Arc::clone(workspace)
+    }
+
+    /// Errors in configuration files sorted by their locations.
+    fn into_reports(self) -> Vec<Arc<LintReport>> {
+        let mut reports = self.reports.into_inner().unwrap();
+        sort_reports(&mut reports);
+        reports
     }
 }
 
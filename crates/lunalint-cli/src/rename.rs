@@ -0,0 +1,69 @@
+use std::{path::PathBuf, sync::Arc};
+
+use lunalint_core::{
+    eprint_report,
+    fix::{apply_fixes, Applicability, Fix},
+    location::SourceInfo,
+    rename, Config,
+};
+
+/// Rename the variable at `target` (`FILE:LINE:COL`, 1-indexed) and all its references.
+/// Returns the renamed file and its new content. Errors in the configuration file are printed and
+/// fail the rename, since builtin names depend on the configuration.
+pub fn rename_at(
+    target: &str,
+    new_name: &str,
+    config: Option<PathBuf>,
+) -> Result<(PathBuf, String), String> {
+    let (path, line, column) = parse_target(target)?;
+    let code = std::fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read file `{}`: {}", path.display(), e))?;
+    let (config, reports) = match config {
+        Some(config) => Config::load(&config),
+        None => Config::discover(&path),
+    };
+    if !reports.is_empty() {
+        for report in reports.iter() {
+            eprint_report(report);
+        }
+        return Err("exited with 1 due to errors in the configuration file".to_owned());
+    }
+
+    let src = Arc::new(SourceInfo::new(path.to_string_lossy().into_owned(), code));
+    let offset = src.offset(line, column);
+    let edits = rename::rename(&src, &config, offset, new_name)
+        .map_err(|e| format!("cannot rename `{}`: {}", target, e))?;
+    let fix = Fix::new(String::new(), Applicability::Safe, edits);
+    let (renamed, _) = apply_fixes(src.content(), &[&fix]);
+    Ok((path, renamed))
+}
+
+/// Split `FILE:LINE:COL`. The file name may contain colons.
+fn parse_target(target: &str) -> Result<(PathBuf, usize, usize), String> {
+    let invalid = || format!("invalid position `{}`, expected FILE:LINE:COL", target);
+    let mut parts = target.rsplitn(3, ':');
+    let column = parts
+        .next()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(invalid)?;
+    let line = parts
+        .next()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(invalid)?;
+    let path = parts.next().filter(|s| !s.is_empty()).ok_or_else(invalid)?;
+    Ok((PathBuf::from(path), line, column))
+}
+
+#[test]
+fn test_parse_target() {
+    assert_eq!(
+        parse_target("src/a.lua:3:7"),
+        Ok((PathBuf::from("src/a.lua"), 3, 7))
+    );
+    assert_eq!(
+        parse_target("C:/a.lua:1:2"),
+        Ok((PathBuf::from("C:/a.lua"), 1, 2))
+    );
+    assert!(parse_target("a.lua:3").is_err());
+    assert!(parse_target(":1:2").is_err());
+}
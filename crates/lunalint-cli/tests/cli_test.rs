@@ -0,0 +1,51 @@
+use std::process::Command;
+
+use insta::Settings;
+
+/// Run lunalint and snapshot its stdout.
+macro_rules! cli_test {
+    ($name:ident, [$($arg:expr),* $(,)?]) => {
+        #[test]
+        fn $name() {
+            let output = Command::new(env!("CARGO_BIN_EXE_lunalint"))
+                .args([$($arg),*])
+                .output()
+                .unwrap();
+            let out = String::from_utf8(output.stdout).unwrap();
+            let mut settings = Settings::new();
+            settings.set_prepend_module_to_snapshot(false);
+            settings.set_omit_expression(true);
+            settings.bind(|| insta::assert_snapshot!(out));
+        }
+    };
+}
+
+cli_test!(fix_chain, ["check", "--diff", "tests/lua/fix-chain.lua"]);
+cli_test!(
+    fix_iteration_limit,
+    ["check", "--diff", "tests/lua/fix-iteration-limit.lua"]
+);
+
+#[test]
+fn rename_global() {
+    // `Counter` is used by other.lua as well
+    let output = Command::new(env!("CARGO_BIN_EXE_lunalint"))
+        .args([
+            "rename",
+            "--diff",
+            "tests/lua/rename-global/main.lua:1:1",
+            "Count",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("`Counter` is a global, which other files may use"),
+        "{stderr}"
+    );
+}
+
+cli_test!(deps_text, ["deps", "tests/lua/deps-cycle/main.lua"]);
+cli_test!(deps_dot, ["deps", "--dot", "tests/lua/deps-cycle/main.lua"]);
@@ -0,0 +1,3 @@
+local a = require("a")
+
+return { a = a }
@@ -0,0 +1,5 @@
+local a = require("a")
+local json = require("json")
+local string = require("string")
+
+print(a, json, string)
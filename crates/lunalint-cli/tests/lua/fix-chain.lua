@@ -0,0 +1,10 @@
+-- Removing `unused` makes `helper` unused, which is removed in the next round.
+local function helper()
+  return 1
+end
+
+local function unused()
+  return helper()
+end
+
+print("done")
@@ -0,0 +1,15 @@
+-- Each removal makes the previous function unused. Fixing stops after 10 rounds, before f2 and f1
+-- are removed.
+local function f1() return 1 end
+local function f2() return f1() end
+local function f3() return f2() end
+local function f4() return f3() end
+local function f5() return f4() end
+local function f6() return f5() end
+local function f7() return f6() end
+local function f8() return f7() end
+local function f9() return f8() end
+local function f10() return f9() end
+local function f11() return f10() end
+local function f12() return f11() end
+print("done")
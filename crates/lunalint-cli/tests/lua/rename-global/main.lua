@@ -0,0 +1,6 @@
+Counter = 0
+
+local function increment()
+    Counter = Counter + 1
+end
+increment()
@@ -0,0 +1,2 @@
+-- Uses the global defined in main.lua
+print(Counter)
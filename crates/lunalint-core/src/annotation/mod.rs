@@ -0,0 +1,465 @@
+//! LuaCATS annotations written in `---` comments.
+//! See https://luals.github.io/wiki/annotations/
+//!
+//! Consecutive `---` lines form a block. A block is attached to the statement which immediately
+//! follows it, and blocks separated from statements by blank lines (e.g. standalone `@class`
+//! declarations) are kept unattached.
+
+mod parser;
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use full_moon::{
+    ast,
+    node::Node,
+    tokenizer::{Token, TokenType},
+    visitors::{Visit, Visitor},
+};
+
+use crate::{location::Location, location::SourceInfo, resolver::NodeId};
+
+/// Type written in an annotation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// Named type such as `string`, `MyClass` or `table<string, integer>`
+    Name { name: String, args: Vec<Type> },
+    /// `"left"`, `1` or `true`
+    Literal(Literal),
+    /// `T[]`
+    Array(Box<Type>),
+    /// `[string, integer]`
+    Tuple(Vec<Type>),
+    /// `{ x: number, [string]: boolean }`
+    Table(Vec<TableField>),
+    /// `fun(a: string): boolean`
+    Function(FunctionType),
+    /// `A | B`
+    Union(Vec<Type>),
+    /// `T?`, which also accepts `nil`
+    Optional(Box<Type>),
+}
+
+impl Type {
+    pub fn any() -> Self {
+        Type::Name {
+            name: "any".to_owned(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Whether the type needs parentheses before `[]` or `?`.
+    fn is_compound(&self) -> bool {
+        matches!(self, Type::Union(_) | Type::Function(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    /// Number as written in the annotation
+    Number(String),
+    Boolean(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableField {
+    pub key: FieldKey,
+    pub ty: Type,
+    /// `name?: T`
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldKey {
+    Name(String),
+    /// `[K]`
+    Type(Type),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionType {
+    pub params: Vec<Param>,
+    pub returns: Vec<Type>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// Name of the parameter, or `...` for variadic parameters
+    pub name: String,
+    pub ty: Type,
+    /// `name?`
+    pub optional: bool,
+}
+
+/// Type parameter declared by `@generic`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generic {
+    pub name: String,
+    /// `T: parent`
+    pub constraint: Option<Type>,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, types: &[Type], sep: &str| {
+            for (i, ty) in types.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(sep)?;
+                }
+                write!(f, "{ty}")?;
+            }
+            Ok(())
+        };
+        match self {
+            Type::Name { name, args } => {
+                f.write_str(name)?;
+                if !args.is_empty() {
+                    f.write_str("<")?;
+                    join(f, args, ", ")?;
+                    f.write_str(">")?;
+                }
+                Ok(())
+            }
+            Type::Literal(Literal::String(s)) => write!(f, "\"{s}\""),
+            Type::Literal(Literal::Number(n)) => f.write_str(n),
+            Type::Literal(Literal::Boolean(b)) => write!(f, "{b}"),
+            Type::Array(ty) if ty.is_compound() => write!(f, "({ty})[]"),
+            Type::Array(ty) => write!(f, "{ty}[]"),
+            Type::Tuple(types) => {
+                f.write_str("[")?;
+                join(f, types, ", ")?;
+                f.write_str("]")
+            }
+            Type::Table(fields) => {
+                if fields.is_empty() {
+                    return f.write_str("{}");
+                }
+                f.write_str("{ ")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    match &field.key {
+                        FieldKey::Name(name) => f.write_str(name)?,
+                        FieldKey::Type(key) => write!(f, "[{key}]")?,
+                    }
+                    let optional = if field.optional { "?" } else { "" };
+                    write!(f, "{optional}: {}", field.ty)?;
+                }
+                f.write_str(" }")
+            }
+            Type::Function(function) => {
+                f.write_str("fun(")?;
+                for (i, param) in function.params.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    let optional = if param.optional { "?" } else { "" };
+                    write!(f, "{}{optional}: {}", param.name, param.ty)?;
+                }
+                f.write_str(")")?;
+                if !function.returns.is_empty() {
+                    f.write_str(": ")?;
+                    join(f, &function.returns, ", ")?;
+                }
+                Ok(())
+            }
+            Type::Union(types) => join(f, types, "|"),
+            Type::Optional(ty) if ty.is_compound() => write!(f, "({ty})?"),
+            Type::Optional(ty) => write!(f, "{ty}?"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    /// `@class (exact) Name<T>: Parent`
+    Class {
+        name: String,
+        generics: Vec<String>,
+        parents: Vec<Type>,
+        exact: bool,
+    },
+    /// `@field name? type`
+    Field(TableField),
+    /// `@alias Name<T> type`, followed by variants in `---|` lines
+    Alias {
+        name: String,
+        generics: Vec<String>,
+        ty: Type,
+    },
+    /// `@param name? type` or `@vararg type`
+    Param(Param),
+    /// `@return type name, type name`
+    Return(Vec<Type>),
+    /// `@type type, type`
+    Type(Vec<Type>),
+    /// `@generic T: parent, U`
+    Generic(Vec<Generic>),
+    /// `@overload fun(...)`
+    Overload(FunctionType),
+    /// `@enum Name`
+    Enum { name: String },
+    /// Annotations without types (e.g. `@deprecated`) or unknown to lunalint
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    tag: Tag,
+    loc: Location,
+}
+
+impl Annotation {
+    pub fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+}
+
+/// Consecutive `---` lines
+#[derive(Debug)]
+pub struct DocBlock {
+    annotations: Vec<Annotation>,
+    /// Lines without annotations
+    description: String,
+    /// Statement which follows the block
+    statement: Option<NodeId>,
+}
+
+impl DocBlock {
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn statement(&self) -> Option<NodeId> {
+        self.statement
+    }
+}
+
+/// Annotation which cannot be parsed
+#[derive(Debug)]
+pub struct DocError {
+    loc: Location,
+    msg: String,
+    label: String,
+}
+
+impl DocError {
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// Annotations in a file
+#[derive(Debug, Default)]
+pub struct Annotations {
+    blocks: Vec<DocBlock>,
+    /// Index of the block attached to each statement
+    statements: HashMap<NodeId, usize>,
+    errors: Vec<DocError>,
+}
+
+impl Annotations {
+    /// Collect annotations from the trivia of the AST.
+    pub(crate) fn collect(src: &Arc<SourceInfo>, ast: &ast::Ast) -> Self {
+        let mut starts = StatementStarts::default();
+        ast.nodes().visit(&mut starts);
+
+        let mut annotations = Annotations::default();
+        for token in ast.nodes().tokens().chain(std::iter::once(ast.eof())) {
+            let mut lines = Vec::new();
+            // Newlines since the last line of the block. Blank lines end the block.
+            let mut newlines = 0;
+            for trivia in token.leading_trivia() {
+                match trivia.token_type() {
+                    TokenType::SingleLineComment { comment } if comment.starts_with('-') => {
+                        if newlines > 1 {
+                            annotations.add_block(src, &std::mem::take(&mut lines), None);
+                        }
+                        lines.push(trivia);
+                        newlines = 0;
+                    }
+                    TokenType::Whitespace { characters } => {
+                        newlines += characters.matches('\n').count();
+                    }
+                    // Other comments end the block
+                    _ => {
+                        annotations.add_block(src, &std::mem::take(&mut lines), None);
+                        newlines = 0;
+                    }
+                }
+            }
+            let statement = starts
+                .0
+                .get(&token.token().start_position().bytes())
+                .filter(|_| newlines < 2)
+                .copied();
+            annotations.add_block(src, &lines, statement);
+        }
+        annotations
+    }
+
+    fn add_block(&mut self, src: &Arc<SourceInfo>, lines: &[&Token], statement: Option<NodeId>) {
+        if lines.is_empty() {
+            return;
+        }
+        let mut annotations: Vec<Annotation> = Vec::new();
+        let mut description = Vec::new();
+        for line in lines {
+            let TokenType::SingleLineComment { comment } = line.token_type() else {
+                continue;
+            };
+            // Skip `---`
+            let text = &comment[1..];
+            let trimmed = text.trim();
+            let offset = line.start_position().bytes() + 3 + (text.len() - text.trim_start().len());
+            let loc = Location::from_range(Arc::clone(src), offset..offset + trimmed.len());
+            if let Some(tag) = trimmed.strip_prefix('@') {
+                // `@diagnostic` is handled by suppressions
+                let name = tag.split(|c: char| !c.is_ascii_alphanumeric()).next();
+                if name == Some("diagnostic") {
+                    continue;
+                }
+                match parser::parse_tag(tag) {
+                    Ok(tag) => annotations.push(Annotation { tag, loc }),
+                    Err(e) => self.errors.push(DocError {
+                        loc: Location::from_range(
+                            Arc::clone(src),
+                            offset + 1 + e.range.start..offset + 1 + e.range.end,
+                        ),
+                        msg: format!("Invalid annotation `@{}`", name.unwrap_or_default()),
+                        label: e.label,
+                    }),
+                }
+            } else if let Some(variant) = trimmed.strip_prefix('|') {
+                let Some(Annotation {
+                    tag: Tag::Alias { ty, .. },
+                    ..
+                }) = annotations.last_mut()
+                else {
+                    continue;
+                };
+                match parser::parse_variant(variant) {
+                    Ok(variant) => match ty {
+                        Type::Union(variants) => variants.push(variant),
+                        ty => *ty = Type::Union(vec![ty.clone(), variant]),
+                    },
+                    Err(e) => self.errors.push(DocError {
+                        loc: Location::from_range(
+                            Arc::clone(src),
+                            offset + 1 + e.range.start..offset + 1 + e.range.end,
+                        ),
+                        msg: "Invalid variant of `@alias`".to_owned(),
+                        label: e.label,
+                    }),
+                }
+            } else {
+                description.push(trimmed);
+            }
+        }
+
+        // Aliases without any variant
+        for annotation in annotations.iter() {
+            if matches!(&annotation.tag, Tag::Alias { ty: Type::Union(variants), .. } if variants.is_empty())
+            {
+                let end = annotation.loc.end().bytes();
+                self.errors.push(DocError {
+                    loc: Location::from_range(Arc::clone(src), end..end),
+                    msg: "Invalid annotation `@alias`".to_owned(),
+                    label: "Expected a type".to_owned(),
+                });
+            }
+        }
+
+        if let Some(statement) = statement {
+            self.statements.insert(statement, self.blocks.len());
+        }
+        self.blocks.push(DocBlock {
+            annotations,
+            description: description.join("\n"),
+            statement,
+        });
+    }
+
+    /// Blocks in the order of appearance
+    pub fn blocks(&self) -> &[DocBlock] {
+        &self.blocks
+    }
+
+    /// Block attached to the statement
+    pub fn for_statement(&self, statement: &dyn Node) -> Option<&DocBlock> {
+        let index = self.statements.get(&NodeId::from(statement))?;
+        Some(&self.blocks[*index])
+    }
+
+    pub fn errors(&self) -> &[DocError] {
+        &self.errors
+    }
+}
+
+/// Statements by the byte offsets of their first tokens
+#[derive(Default)]
+struct StatementStarts(HashMap<usize, NodeId>);
+
+impl StatementStarts {
+    fn insert(&mut self, node: &dyn Node) {
+        if let Some((start, _)) = node.range() {
+            self.0.insert(start.bytes(), NodeId::from(node));
+        }
+    }
+}
+
+impl Visitor for StatementStarts {
+    fn visit_stmt(&mut self, node: &ast::Stmt) {
+        self.insert(node);
+    }
+
+    fn visit_last_stmt(&mut self, node: &ast::LastStmt) {
+        self.insert(node);
+    }
+}
+
+#[test]
+fn test_collect() {
+    let src = "---@class Point\n---@field x number\n\n--- Create a point\n---@param x number\n---@return Point\nlocal function new(x) end\n\n---@alias Dir\n---| \"up\"\n---| \"down\" # description\nlocal d = 'up'\n";
+    let src = Arc::new(SourceInfo::new("test.lua".to_owned(), src.to_owned()));
+    let ast = full_moon::parse(src.content()).unwrap();
+    let annotations = Annotations::collect(&src, &ast);
+    assert!(annotations.errors().is_empty());
+
+    let blocks = annotations.blocks();
+    assert_eq!(blocks.len(), 3);
+    assert!(blocks[0].statement().is_none());
+    assert_eq!(blocks[0].annotations().len(), 2);
+
+    let stmts = ast.nodes().stmts().collect::<Vec<_>>();
+    let new = annotations.for_statement(stmts[0]).unwrap();
+    assert_eq!(new.description(), "Create a point");
+    assert!(matches!(
+        new.annotations()[0].tag(),
+        Tag::Param(Param { name, .. }) if name == "x"
+    ));
+    assert_eq!(new.annotations()[1].loc().start().line(), 6);
+
+    let d = annotations.for_statement(stmts[1]).unwrap();
+    let Tag::Alias { ty, .. } = d.annotations()[0].tag() else {
+        panic!("expected an alias");
+    };
+    assert_eq!(ty.to_string(), "\"up\"|\"down\"");
+}
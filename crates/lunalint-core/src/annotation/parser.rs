@@ -0,0 +1,541 @@
+//! Parser of a single line of LuaCATS annotations.
+
+use std::ops::Range;
+
+use super::{FieldKey, FunctionType, Generic, Literal, Param, TableField, Tag, Type};
+
+/// Error in an annotation. The range is relative to the parsed text.
+#[derive(Debug)]
+pub(super) struct ParseError {
+    pub(super) range: Range<usize>,
+    pub(super) label: String,
+}
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Name(&'a str),
+    String(&'a str),
+    Number(&'a str),
+    Ellipsis,
+    Symbol(char),
+    End,
+}
+
+const VISIBILITIES: [&str; 4] = ["public", "protected", "private", "package"];
+
+/// Parse an annotation after `@` (e.g. `param name string? description`). Text following the
+/// annotation is a description and ignored.
+pub(super) fn parse_tag(text: &str) -> Result<Tag> {
+    Parser::new(text).tag()
+}
+
+/// Parse a variant of an alias after `|` (e.g. `"left" # description`).
+pub(super) fn parse_variant(text: &str) -> Result<Type> {
+    let mut parser = Parser::new(text);
+    // `---|>` marks the default value and `---|+` an extensible variant
+    if !parser.eat('>') {
+        parser.eat('+');
+    }
+    parser.postfix(false)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn tag(&mut self) -> Result<Tag> {
+        let (name, _) = self.name("Expected an annotation name")?;
+        let tag = match name {
+            "class" => {
+                let exact = self.peek()? == Token::Symbol('(');
+                if exact {
+                    self.next()?;
+                    self.keyword("exact")?;
+                    self.expect(')')?;
+                }
+                let (name, _) = self.name("Expected a class name")?;
+                let generics = self.generic_names()?;
+                let mut parents = Vec::new();
+                if self.eat(':') {
+                    parents.push(self.postfix(false)?);
+                    while self.eat(',') {
+                        parents.push(self.postfix(false)?);
+                    }
+                }
+                Tag::Class {
+                    name: name.to_owned(),
+                    generics,
+                    parents,
+                    exact,
+                }
+            }
+            "field" => {
+                if let Token::Name(name) = self.peek()? {
+                    // `@field private number` declares a field named `private`
+                    let visibility = match self.peek_nth(1)? {
+                        Token::Name(_) => self.peek_nth(2)? != Token::End,
+                        next => next == Token::Symbol('['),
+                    };
+                    if VISIBILITIES.contains(&name) && visibility {
+                        self.next()?;
+                    }
+                }
+                let key = if self.eat('[') {
+                    let key = self.union(true)?;
+                    self.expect(']')?;
+                    FieldKey::Type(key)
+                } else {
+                    FieldKey::Name(self.name("Expected a field name")?.0.to_owned())
+                };
+                let optional = self.eat('?');
+                Tag::Field(TableField {
+                    key,
+                    ty: self.ty()?,
+                    optional,
+                })
+            }
+            "alias" => {
+                let (name, _) = self.name("Expected an alias name")?;
+                let generics = self.generic_names()?;
+                // Variants may follow in `---|` lines
+                let ty = if self.peek()? == Token::End {
+                    Type::Union(Vec::new())
+                } else {
+                    self.ty()?
+                };
+                Tag::Alias {
+                    name: name.to_owned(),
+                    generics,
+                    ty,
+                }
+            }
+            "param" => Tag::Param(self.param(false)?),
+            "vararg" => Tag::Param(Param {
+                name: "...".to_owned(),
+                ty: self.ty()?,
+                optional: false,
+            }),
+            "return" => {
+                let mut types = vec![self.ty()?];
+                loop {
+                    // Names of return values are optional
+                    if let Token::Name(_) = self.peek()? {
+                        self.next()?;
+                    }
+                    if !self.eat(',') {
+                        break;
+                    }
+                    types.push(self.ty()?);
+                }
+                Tag::Return(types)
+            }
+            "type" => {
+                let mut types = vec![self.ty()?];
+                while self.eat(',') {
+                    types.push(self.ty()?);
+                }
+                Tag::Type(types)
+            }
+            "generic" => {
+                let mut generics = Vec::new();
+                loop {
+                    let (name, _) = self.name("Expected a type parameter")?;
+                    let constraint = if self.eat(':') {
+                        Some(self.postfix(false)?)
+                    } else {
+                        None
+                    };
+                    generics.push(Generic {
+                        name: name.to_owned(),
+                        constraint,
+                    });
+                    if !self.eat(',') {
+                        break;
+                    }
+                }
+                Tag::Generic(generics)
+            }
+            "overload" => {
+                let start = self.skip_whitespace();
+                match self.ty()? {
+                    Type::Function(function) => Tag::Overload(function),
+                    _ => {
+                        return Err(ParseError {
+                            range: start..self.pos,
+                            label: "Expected a function type".to_owned(),
+                        })
+                    }
+                }
+            }
+            "enum" => {
+                let (name, _) = self.name("Expected an enum name")?;
+                Tag::Enum {
+                    name: name.to_owned(),
+                }
+            }
+            _ => Tag::Other(name.to_owned()),
+        };
+        Ok(tag)
+    }
+
+    /// Type parameters of a class or an alias (e.g. `<K, V>`)
+    fn generic_names(&mut self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        if !self.eat('<') {
+            return Ok(names);
+        }
+        loop {
+            names.push(self.name("Expected a type parameter")?.0.to_owned());
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect('>')?;
+        Ok(names)
+    }
+
+    /// `name?: type` in a function type, or `name? type` in `@param`
+    fn param(&mut self, in_function: bool) -> Result<Param> {
+        let (token, range) = self.next()?;
+        let name = match token {
+            Token::Name(name) => name,
+            Token::Ellipsis => "...",
+            _ => return Err(error(range, "Expected a parameter name")),
+        };
+        let optional = self.eat('?');
+        let ty = if !in_function {
+            self.ty()?
+        } else if self.eat(':') {
+            self.union(true)?
+        } else {
+            Type::any()
+        };
+        Ok(Param {
+            name: name.to_owned(),
+            ty,
+            optional,
+        })
+    }
+
+    /// Type at the top level of an annotation. A function type may return several values.
+    fn ty(&mut self) -> Result<Type> {
+        self.union(false)
+    }
+
+    /// `a | b`. If `nested` is true, the type is enclosed in brackets and a function type returns
+    /// a single value since commas separate types.
+    fn union(&mut self, nested: bool) -> Result<Type> {
+        self.eat('|');
+        let mut types = vec![self.postfix(nested)?];
+        while self.eat('|') {
+            types.push(self.postfix(nested)?);
+        }
+        Ok(if types.len() == 1 {
+            types.pop().unwrap()
+        } else {
+            Type::Union(types)
+        })
+    }
+
+    /// `type[]` or `type?`
+    fn postfix(&mut self, nested: bool) -> Result<Type> {
+        let mut ty = self.primary(nested)?;
+        loop {
+            if self.peek()? == Token::Symbol('[') && self.peek_nth(1)? == Token::Symbol(']') {
+                self.next()?;
+                self.next()?;
+                ty = Type::Array(Box::new(ty));
+            } else if self.eat('?') {
+                ty = Type::Optional(Box::new(ty));
+            } else {
+                return Ok(ty);
+            }
+        }
+    }
+
+    fn primary(&mut self, nested: bool) -> Result<Type> {
+        let (token, range) = self.next()?;
+        let ty = match token {
+            Token::Name("fun") if self.peek()? == Token::Symbol('(') => self.function(nested)?,
+            Token::Name("true") => Type::Literal(Literal::Boolean(true)),
+            Token::Name("false") => Type::Literal(Literal::Boolean(false)),
+            Token::Name(name) => {
+                let mut args = Vec::new();
+                if self.eat('<') {
+                    args.push(self.union(true)?);
+                    while self.eat(',') {
+                        args.push(self.union(true)?);
+                    }
+                    self.expect('>')?;
+                }
+                Type::Name {
+                    name: name.to_owned(),
+                    args,
+                }
+            }
+            Token::String(s) => Type::Literal(Literal::String(s.to_owned())),
+            Token::Number(n) => Type::Literal(Literal::Number(n.to_owned())),
+            Token::Symbol('(') => {
+                let ty = self.union(true)?;
+                self.expect(')')?;
+                ty
+            }
+            Token::Symbol('{') => {
+                let mut fields = Vec::new();
+                while !self.eat('}') {
+                    let key = if self.eat('[') {
+                        let key = self.union(true)?;
+                        self.expect(']')?;
+                        FieldKey::Type(key)
+                    } else {
+                        FieldKey::Name(self.name("Expected a field name")?.0.to_owned())
+                    };
+                    let optional = self.eat('?');
+                    self.expect(':')?;
+                    fields.push(TableField {
+                        key,
+                        ty: self.union(true)?,
+                        optional,
+                    });
+                    if !self.eat(',') {
+                        self.expect('}')?;
+                        break;
+                    }
+                }
+                Type::Table(fields)
+            }
+            Token::Symbol('[') => {
+                let mut types = vec![self.union(true)?];
+                while self.eat(',') {
+                    types.push(self.union(true)?);
+                }
+                self.expect(']')?;
+                Type::Tuple(types)
+            }
+            _ => return Err(error(range, "Expected a type")),
+        };
+        Ok(ty)
+    }
+
+    /// `fun(a: T, b?: U): R` after `fun`
+    fn function(&mut self, nested: bool) -> Result<Type> {
+        self.expect('(')?;
+        let mut params = Vec::new();
+        if !self.eat(')') {
+            loop {
+                params.push(self.param(true)?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect(')')?;
+        }
+        let mut returns = Vec::new();
+        if self.eat(':') {
+            returns.push(self.union(true)?);
+            while !nested && self.eat(',') {
+                returns.push(self.union(true)?);
+            }
+        }
+        Ok(Type::Function(FunctionType { params, returns }))
+    }
+
+    fn name(&mut self, label: &str) -> Result<(&'a str, Range<usize>)> {
+        match self.next()? {
+            (Token::Name(name), range) => Ok((name, range)),
+            (_, range) => Err(error(range, label)),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<()> {
+        match self.next()? {
+            (Token::Name(name), _) if name == keyword => Ok(()),
+            (_, range) => Err(error(range, &format!("Expected `{keyword}`"))),
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<()> {
+        match self.next()? {
+            (Token::Symbol(s), _) if s == symbol => Ok(()),
+            (_, range) => Err(error(range, &format!("Expected `{symbol}`"))),
+        }
+    }
+
+    /// Consume the symbol if it is the next token.
+    fn eat(&mut self, symbol: char) -> bool {
+        if self
+            .peek()
+            .is_ok_and(|token| token == Token::Symbol(symbol))
+        {
+            self.next().unwrap();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&mut self) -> Result<Token<'a>> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Result<Token<'a>> {
+        let pos = self.pos;
+        let mut token = Token::End;
+        for _ in 0..=n {
+            token = self.next()?.0;
+        }
+        self.pos = pos;
+        Ok(token)
+    }
+
+    fn skip_whitespace(&mut self) -> usize {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.pos
+    }
+
+    fn next(&mut self) -> Result<(Token<'a>, Range<usize>)> {
+        let start = self.skip_whitespace();
+        let rest = &self.text[start..];
+        let Some(c) = rest.chars().next() else {
+            return Ok((Token::End, start..start));
+        };
+        // `-` is a sign only if a digit follows
+        let is_number =
+            c.is_ascii_digit() || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit()));
+        let len = if c.is_ascii_alphabetic() || c == '_' {
+            rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '.')
+                .unwrap_or(rest.len())
+        } else if is_number {
+            1 + rest[1..]
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '.')
+                .unwrap_or(rest.len() - 1)
+        } else if c == '"' || c == '\'' {
+            let mut escaped = false;
+            let end = rest[1..].find(|ch: char| {
+                let found = !escaped && ch == c;
+                escaped = !escaped && ch == '\\';
+                found
+            });
+            match end {
+                Some(end) => end + 2,
+                None => return Err(error(start..self.text.len(), "Unclosed string literal")),
+            }
+        } else if rest.starts_with("...") {
+            3
+        } else {
+            c.len_utf8()
+        };
+        self.pos = start + len;
+        let token_text = &rest[..len];
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            Token::Name(token_text)
+        } else if is_number {
+            Token::Number(token_text)
+        } else if c == '"' || c == '\'' {
+            Token::String(&token_text[1..len - 1])
+        } else if token_text == "..." {
+            Token::Ellipsis
+        } else {
+            Token::Symbol(c)
+        };
+        Ok((token, start..self.pos))
+    }
+}
+
+fn error(range: Range<usize>, label: &str) -> ParseError {
+    ParseError {
+        range,
+        label: label.to_owned(),
+    }
+}
+
+#[test]
+fn test_parse_type() {
+    let parse = |text: &str| match parse_tag(&format!("type {text}")) {
+        Ok(Tag::Type(types)) => types
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Ok(tag) => panic!("unexpected tag {tag:?}"),
+        Err(e) => format!("error at {:?}: {}", e.range, e.label),
+    };
+    assert_eq!(parse("string"), "string");
+    assert_eq!(parse("string | nil description"), "string|nil");
+    assert_eq!(parse("(string|number)[]?"), "(string|number)[]?");
+    assert_eq!(
+        parse("table<string, integer[]>"),
+        "table<string, integer[]>"
+    );
+    assert_eq!(
+        parse("\"left\"|'right'|1|-2|true"),
+        "\"left\"|\"right\"|1|-2|true"
+    );
+    assert_eq!(
+        parse("fun(a: string, b?: number, ...: any): boolean, string"),
+        "fun(a: string, b?: number, ...: any): boolean, string"
+    );
+    assert_eq!(
+        parse("fun(cb: fun(): string, n)"),
+        "fun(cb: fun(): string, n: any)"
+    );
+    assert_eq!(
+        parse("{ x: number, [string]: boolean, name?: string }"),
+        "{ x: number, [string]: boolean, name?: string }"
+    );
+    assert_eq!(
+        parse("[string, integer], vim.api.Buffer"),
+        "[string, integer], vim.api.Buffer"
+    );
+    assert_eq!(parse(""), "error at 5..5: Expected a type");
+    assert_eq!(parse("table<string"), "error at 17..17: Expected `>`");
+    assert_eq!(parse("fun(a: )"), "error at 12..13: Expected a type");
+    assert_eq!(parse("\"open"), "error at 5..10: Unclosed string literal");
+    assert_eq!(parse("-"), "error at 5..6: Expected a type");
+    assert_eq!(parse("- 1"), "error at 5..6: Expected a type");
+}
+
+#[test]
+fn test_parse_tag() {
+    let parse = |text: &str| format!("{:?}", parse_tag(text).ok().unwrap());
+    assert_eq!(
+        parse("class (exact) Point: Shape, Serializable description"),
+        r#"Class { name: "Point", generics: [], parents: [Name { name: "Shape", args: [] }, Name { name: "Serializable", args: [] }], exact: true }"#
+    );
+    assert_eq!(
+        parse("field private x? number"),
+        r#"Field(TableField { key: Name("x"), ty: Name { name: "number", args: [] }, optional: true })"#
+    );
+    assert_eq!(
+        parse("field private number"),
+        r#"Field(TableField { key: Name("private"), ty: Name { name: "number", args: [] }, optional: false })"#
+    );
+    assert_eq!(
+        parse("param ... string"),
+        r#"Param(Param { name: "...", ty: Name { name: "string", args: [] }, optional: false })"#
+    );
+    assert_eq!(
+        parse("return boolean ok, string? err # description"),
+        r#"Return([Name { name: "boolean", args: [] }, Optional(Name { name: "string", args: [] })])"#
+    );
+    assert_eq!(
+        parse("generic T: table, K"),
+        r#"Generic([Generic { name: "T", constraint: Some(Name { name: "table", args: [] }) }, Generic { name: "K", constraint: None }])"#
+    );
+    assert_eq!(
+        parse("alias Mode"),
+        r#"Alias { name: "Mode", generics: [], ty: Union([]) }"#
+    );
+    assert_eq!(parse("deprecated"), r#"Other("deprecated")"#);
+    assert!(parse_tag("overload string").is_err());
+    assert!(parse_tag("class (strict) A").is_err());
+}
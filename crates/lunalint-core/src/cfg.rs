@@ -1,101 +1,616 @@
-use full_moon::ast;
+//! Control-flow graphs of functions.
+//!
+//! A graph is built for the main chunk and for each function body. Nested functions are not part
+//! of the enclosing graph; a function declaration is an ordinary statement there.
 
-// build cfg from ast
-fn build_cfg(ast: ast::Ast) {
-    let builder = Builder;
-}
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use full_moon::{
+    ast,
+    node::Node,
+    tokenizer::{Symbol, TokenType},
+    visitors::{Visit, Visitor},
+};
 
-use full_moon::visitors::Visitor;
+use crate::{
+    location::{Location, SourceInfo},
+    resolver::{NodeId, Resolver},
+    utils,
+};
 
-struct Builder;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(usize);
+
+/// How control leaves a basic block other than falling through or branching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminatorKind {
+    Return,
+    Break,
+    Goto,
+    /// Call to `error`, which never returns
+    Error,
+}
 
-trait CfgVisit<V: Visitor> {
-    fn walk(&self, v: &mut V);
+#[derive(Debug, Clone)]
+pub struct Terminator {
+    kind: TerminatorKind,
+    stmt: NodeId,
+    loc: Location,
 }
 
-impl<V: Visitor> CfgVisit<V> for ast::Ast {
-    fn walk(&self, v: &mut V) {
-        self.nodes().walk(v);
+impl Terminator {
+    pub fn kind(&self) -> TerminatorKind {
+        self.kind
     }
+
+    /// The statement which terminates the block
+    pub fn stmt(&self) -> NodeId {
+        self.stmt
+    }
+
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+}
+
+/// Condition evaluated at the end of a block, which selects the successor.
+#[derive(Debug, Clone, Copy)]
+pub struct Branch {
+    condition: NodeId,
+    on_true: Option<BlockId>,
+    on_false: Option<BlockId>,
 }
 
-// ast::Block
-impl<V: Visitor> CfgVisit<V> for ast::Block {
-    fn walk(&self, v: &mut V) {
-        for stmt in self.stmts() {
-            stmt.walk(v);
+impl Branch {
+    /// The condition expression
+    pub fn condition(&self) -> NodeId {
+        self.condition
+    }
+
+    /// The successor taken if the condition is truthy (`value` is true) or falsy. `None` if the
+    /// condition is a constant which never has the value (e.g. `while true do`).
+    pub fn target(&self, value: bool) -> Option<BlockId> {
+        if value {
+            self.on_true
+        } else {
+            self.on_false
         }
     }
 }
 
-// ast::Stmt
-impl<V: Visitor> CfgVisit<V> for ast::Stmt {
-    fn walk(&self, v: &mut V) {
-        match self {
-            ast::Stmt::Do(d) => d.walk(v),
-            ast::Stmt::Assignment(a) => a.walk(v),
-            ast::Stmt::FunctionCall(c) => c.walk(v),
-            ast::Stmt::FunctionDeclaration(decl) => decl.walk(v),
-            ast::Stmt::GenericFor(f) => f.walk(v),
-            ast::Stmt::Goto(g) => todo!(),
-            ast::Stmt::Label(l) => todo!(),
-            _ => todo!(),
+/// A sequence of statements executed without jumps.
+/// Compound statements (e.g. `if`, `while`) belong to the block where their condition is evaluated.
+#[derive(Debug, Default)]
+pub struct BasicBlock {
+    stmts: Vec<NodeId>,
+    terminator: Option<Terminator>,
+    branch: Option<Branch>,
+    preds: Vec<BlockId>,
+    succs: Vec<BlockId>,
+}
+
+impl BasicBlock {
+    pub fn stmts(&self) -> &[NodeId] {
+        &self.stmts
+    }
+
+    pub fn terminator(&self) -> Option<&Terminator> {
+        self.terminator.as_ref()
+    }
+
+    /// Condition of `if`, `elseif`, `while` or `until` evaluated after the statements
+    pub fn branch(&self) -> Option<&Branch> {
+        self.branch.as_ref()
+    }
+}
+
+#[derive(Debug)]
+pub struct Cfg {
+    blocks: Vec<BasicBlock>,
+    entry: BlockId,
+    exit: BlockId,
+    stmt_to_block: HashMap<NodeId, BlockId>,
+    reachable: Vec<bool>,
+}
+
+impl Cfg {
+    pub fn entry(&self) -> BlockId {
+        self.entry
+    }
+
+    /// The block reached by returning from the function or falling off its end
+    pub fn exit(&self) -> BlockId {
+        self.exit
+    }
+
+    pub fn block(&self, id: BlockId) -> &BasicBlock {
+        &self.blocks[id.0]
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = (BlockId, &BasicBlock)> {
+        self.blocks.iter().enumerate().map(|(i, b)| (BlockId(i), b))
+    }
+
+    pub fn predecessors(&self, id: BlockId) -> &[BlockId] {
+        &self.blocks[id.0].preds
+    }
+
+    pub fn successors(&self, id: BlockId) -> &[BlockId] {
+        &self.blocks[id.0].succs
+    }
+
+    /// Whether the block can be reached from the entry.
+    pub fn is_reachable(&self, id: BlockId) -> bool {
+        self.reachable[id.0]
+    }
+
+    /// The block containing the statement.
+    pub fn block_of(&self, stmt: NodeId) -> Option<BlockId> {
+        self.stmt_to_block.get(&stmt).copied()
+    }
+
+    /// Whether the end of the function can be reached without `return`.
+    pub fn falls_through(&self) -> bool {
+        self.predecessors(self.exit).iter().any(|pred| {
+            self.is_reachable(*pred)
+                && self
+                    .block(*pred)
+                    .terminator()
+                    .is_none_or(|t| t.kind() != TerminatorKind::Return)
+        })
+    }
+
+    fn compute_reachability(&mut self) {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut queue = VecDeque::from([self.entry]);
+        reachable[self.entry.0] = true;
+        while let Some(id) = queue.pop_front() {
+            for succ in self.blocks[id.0].succs.iter() {
+                if !reachable[succ.0] {
+                    reachable[succ.0] = true;
+                    queue.push_back(*succ);
+                }
+            }
         }
+        self.reachable = reachable;
     }
 }
 
-// ast::FunctionDeclaration
-impl<V: Visitor> CfgVisit<V> for ast::FunctionDeclaration {
-    fn walk(&self, v: &mut V) {
-        self.body().walk(v);
+/// Control-flow graphs of the main chunk and all functions in a file.
+#[derive(Debug, Default)]
+pub struct ControlFlow {
+    /// Graphs keyed by the function body, or the AST for the main chunk
+    graphs: HashMap<NodeId, Cfg>,
+    /// Statement to the function which contains it
+    stmt_to_graph: HashMap<NodeId, NodeId>,
+}
+
+impl ControlFlow {
+    pub fn build(src: &Arc<SourceInfo>, ast: &ast::Ast, resolver: &Resolver) -> Self {
+        let mut collector = Collector {
+            src,
+            resolver,
+            cf: ControlFlow::default(),
+        };
+        collector.visit_ast(ast);
+        collector.cf
+    }
+
+    /// Graph of the function body, or the main chunk if the AST is given.
+    pub fn graph(&self, func: NodeId) -> Option<&Cfg> {
+        self.graphs.get(&func)
+    }
+
+    /// Graph of the function which contains the statement.
+    pub fn graph_of(&self, stmt: NodeId) -> Option<&Cfg> {
+        self.stmt_to_graph
+            .get(&stmt)
+            .and_then(|func| self.graphs.get(func))
+    }
+
+    /// Whether the statement can be executed. Unknown statements are considered reachable.
+    pub fn is_reachable(&self, stmt: NodeId) -> bool {
+        let Some(cfg) = self.graph_of(stmt) else {
+            return true;
+        };
+        cfg.block_of(stmt).is_none_or(|b| cfg.is_reachable(b))
     }
 }
 
-// ast::FunctionBody
-impl<V: Visitor> CfgVisit<V> for ast::FunctionBody {
-    fn walk(&self, v: &mut V) {
-        self.block().walk(v);
+/// Visit all functions and build their graphs.
+struct Collector<'a> {
+    src: &'a Arc<SourceInfo>,
+    resolver: &'a Resolver,
+    cf: ControlFlow,
+}
+
+impl Collector<'_> {
+    fn insert(&mut self, func: NodeId, cfg: Cfg) {
+        for stmt in cfg.stmt_to_block.keys() {
+            self.cf.stmt_to_graph.insert(*stmt, func);
+        }
+        self.cf.graphs.insert(func, cfg);
     }
 }
 
-// ast::Do
-impl<V: Visitor> CfgVisit<V> for ast::Do {
-    fn walk(&self, v: &mut V) {
-        self.block().walk(v);
+impl Visitor for Collector<'_> {
+    fn visit_ast(&mut self, ast: &ast::Ast) {
+        let cfg = Builder::new(self.src, self.resolver).build(ast.nodes());
+        self.insert(NodeId::from(ast), cfg);
+
+        ast.nodes().visit(self);
+        ast.eof().visit(self);
+    }
+
+    fn visit_function_body(&mut self, node: &ast::FunctionBody) {
+        let cfg = Builder::new(self.src, self.resolver).build(node.block());
+        self.insert(NodeId::from(node), cfg);
     }
 }
 
-// ast::Assignment
-impl<V: Visitor> CfgVisit<V> for ast::Assignment {
-    fn walk(&self, v: &mut V) {
-        for expr in self.expressions() {
-            expr.walk(v);
+/// Build a graph of a single function.
+struct Builder<'a> {
+    src: &'a Arc<SourceInfo>,
+    resolver: &'a Resolver,
+    cfg: Cfg,
+    current: BlockId,
+    /// Blocks following enclosing loops, which `break` jumps to
+    loop_exits: Vec<BlockId>,
+    /// Labels visible in enclosing blocks
+    labels: Vec<HashMap<String, BlockId>>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(src: &'a Arc<SourceInfo>, resolver: &'a Resolver) -> Self {
+        let mut cfg = Cfg {
+            blocks: Vec::new(),
+            entry: BlockId(0),
+            exit: BlockId(0),
+            stmt_to_block: HashMap::new(),
+            reachable: Vec::new(),
+        };
+        cfg.blocks.push(BasicBlock::default());
+        cfg.blocks.push(BasicBlock::default());
+        cfg.exit = BlockId(1);
+        Builder {
+            src,
+            resolver,
+            cfg,
+            current: BlockId(0),
+            loop_exits: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    fn build(mut self, block: &ast::Block) -> Cfg {
+        self.walk_block(block);
+        self.add_edge(self.current, self.cfg.exit);
+        self.cfg.compute_reachability();
+        self.cfg
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.cfg.blocks.push(BasicBlock::default());
+        BlockId(self.cfg.blocks.len() - 1)
+    }
+
+    fn add_edge(&mut self, from: BlockId, to: BlockId) {
+        self.cfg.blocks[from.0].succs.push(to);
+        self.cfg.blocks[to.0].preds.push(from);
+    }
+
+    fn set_branch(
+        &mut self,
+        block: BlockId,
+        condition: &ast::Expression,
+        on_true: Option<BlockId>,
+        on_false: Option<BlockId>,
+    ) {
+        self.cfg.blocks[block.0].branch = Some(Branch {
+            condition: NodeId::from(condition),
+            on_true,
+            on_false,
+        });
+    }
+
+    fn push_stmt(&mut self, stmt: NodeId) {
+        self.cfg.blocks[self.current.0].stmts.push(stmt);
+        self.cfg.stmt_to_block.insert(stmt, self.current);
+    }
+
+    /// End the current block with a jump. Statements that follow go to a new block without
+    /// predecessors.
+    fn terminate(&mut self, kind: TerminatorKind, stmt: &dyn Node, target: Option<BlockId>) {
+        self.cfg.blocks[self.current.0].terminator = Some(Terminator {
+            kind,
+            stmt: NodeId::from(stmt),
+            loc: Location::from((self.src, stmt.tokens())),
+        });
+        if let Some(target) = target {
+            self.add_edge(self.current, target);
+        }
+        self.current = self.new_block();
+    }
+
+    fn walk_block(&mut self, block: &ast::Block) {
+        // Labels are visible in the whole block, so `goto` can jump forward
+        let mut labels = HashMap::new();
+        for stmt in block.stmts() {
+            if let ast::Stmt::Label(label) = stmt {
+                let name = utils::ident_as_str(label.name()).to_owned();
+                let id = self.new_block();
+                labels.insert(name, id);
+            }
         }
+        self.labels.push(labels);
+
+        for stmt in block.stmts() {
+            self.walk_stmt(stmt);
+        }
+        if let Some(last_stmt) = block.last_stmt() {
+            self.walk_last_stmt(last_stmt);
+        }
+
+        self.labels.pop();
+    }
+
+    fn walk_stmt(&mut self, stmt: &ast::Stmt) {
+        let stmt_id = NodeId::from(stmt);
+        match stmt {
+            ast::Stmt::Do(d) => {
+                self.push_stmt(stmt_id);
+                self.walk_block(d.block());
+            }
+            ast::Stmt::FunctionCall(call) => {
+                self.push_stmt(stmt_id);
+                if self.is_error_call(call) {
+                    self.terminate(TerminatorKind::Error, stmt, None);
+                }
+            }
+            ast::Stmt::If(node) => {
+                self.push_stmt(stmt_id);
+                let after = self.new_block();
+                let conds = std::iter::once((node.condition(), node.block())).chain(
+                    node.else_if()
+                        .into_iter()
+                        .flatten()
+                        .map(|e| (e.condition(), e.block())),
+                );
+                for (condition, block) in conds {
+                    // Each condition is evaluated in `self.current`
+                    let cond = self.current;
+                    let then = self.new_block();
+                    self.current = then;
+                    self.add_edge(cond, then);
+                    self.walk_block(block);
+                    self.add_edge(self.current, after);
+                    self.current = self.new_block();
+                    self.add_edge(cond, self.current);
+                    self.set_branch(cond, condition, Some(then), Some(self.current));
+                }
+                if let Some(block) = node.else_block() {
+                    self.walk_block(block);
+                }
+                self.add_edge(self.current, after);
+                self.current = after;
+            }
+            ast::Stmt::While(node) => {
+                let header = self.new_block();
+                self.add_edge(self.current, header);
+                self.current = header;
+                self.push_stmt(stmt_id);
+                self.walk_loop(node.block(), header, Some(node.condition()));
+            }
+            ast::Stmt::Repeat(node) => {
+                self.push_stmt(stmt_id);
+                let body = self.new_block();
+                let after = self.new_block();
+                self.add_edge(self.current, body);
+                self.current = body;
+                self.loop_exits.push(after);
+                self.walk_block(node.block());
+                self.loop_exits.pop();
+                // `until` is evaluated at the end of the body
+                let exits = !is_falsy(node.until());
+                if exits {
+                    self.add_edge(self.current, after);
+                }
+                self.add_edge(self.current, body);
+                let on_true = exits.then_some(after);
+                self.set_branch(self.current, node.until(), on_true, Some(body));
+                self.current = after;
+            }
+            ast::Stmt::NumericFor(node) => {
+                let header = self.new_block();
+                self.add_edge(self.current, header);
+                self.current = header;
+                self.push_stmt(stmt_id);
+                self.walk_loop(node.block(), header, None);
+            }
+            ast::Stmt::GenericFor(node) => {
+                let header = self.new_block();
+                self.add_edge(self.current, header);
+                self.current = header;
+                self.push_stmt(stmt_id);
+                self.walk_loop(node.block(), header, None);
+            }
+            ast::Stmt::Goto(node) => {
+                self.push_stmt(stmt_id);
+                let name = utils::ident_as_str(node.label_name());
+                let target = self
+                    .labels
+                    .iter()
+                    .rev()
+                    .find_map(|labels| labels.get(name).copied());
+                self.terminate(TerminatorKind::Goto, stmt, target);
+            }
+            ast::Stmt::Label(node) => {
+                let name = utils::ident_as_str(node.name());
+                let label = self
+                    .labels
+                    .last()
+                    .and_then(|labels| labels.get(name).copied())
+                    .unwrap_or_else(|| self.new_block());
+                self.add_edge(self.current, label);
+                self.current = label;
+                self.push_stmt(stmt_id);
+            }
+            _ => self.push_stmt(stmt_id),
+        }
+    }
+
+    /// Walk the body of a loop whose condition is evaluated in `header`. `for` loops have no
+    /// condition and may exit at any iteration.
+    fn walk_loop(
+        &mut self,
+        block: &ast::Block,
+        header: BlockId,
+        condition: Option<&ast::Expression>,
+    ) {
+        let after = self.new_block();
+        let may_exit = condition.is_none_or(|condition| !is_truthy(condition));
+        if may_exit {
+            self.add_edge(header, after);
+        }
+        let body = self.new_block();
+        self.current = body;
+        self.add_edge(header, body);
+        if let Some(condition) = condition {
+            self.set_branch(header, condition, Some(body), may_exit.then_some(after));
+        }
+        self.loop_exits.push(after);
+        self.walk_block(block);
+        self.loop_exits.pop();
+        self.add_edge(self.current, header);
+        self.current = after;
+    }
+
+    fn walk_last_stmt(&mut self, last_stmt: &ast::LastStmt) {
+        let stmt_id = NodeId::from(last_stmt);
+        self.push_stmt(stmt_id);
+        match last_stmt {
+            ast::LastStmt::Return(_) => {
+                let exit = self.cfg.exit;
+                self.terminate(TerminatorKind::Return, last_stmt, Some(exit));
+            }
+            ast::LastStmt::Break(_) => {
+                let target = self.loop_exits.last().copied();
+                self.terminate(TerminatorKind::Break, last_stmt, target);
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the call is `error(...)` of the standard library
+    fn is_error_call(&self, call: &ast::FunctionCall) -> bool {
+        let prefix = call.prefix();
+        let ast::Prefix::Name(name) = prefix else {
+            return false;
+        };
+        if utils::ident_as_str(name) != "error" || call.suffixes().count() != 1 {
+            return false;
+        }
+        self.resolver
+            .lookup_definiton(NodeId::from(prefix))
+            .is_some_and(|def| self.resolver.is_builtin(def))
     }
 }
 
-// ast::FunctionCall
-impl<V: Visitor> CfgVisit<V> for ast::FunctionCall {
-    fn walk(&self, v: &mut V) {
-        self.prefix().walk(v);
+/// Whether the expression is a constant which is always true (e.g. `while true do`)
+fn is_truthy(e: &ast::Expression) -> bool {
+    match e {
+        ast::Expression::Number(_) | ast::Expression::String(_) => true,
+        ast::Expression::Symbol(s) => matches!(
+            s.token_type(),
+            TokenType::Symbol {
+                symbol: Symbol::True
+            }
+        ),
+        ast::Expression::Parentheses { expression, .. } => is_truthy(expression),
+        _ => false,
     }
 }
 
-// ast::Prefix
-impl<V: Visitor> CfgVisit<V> for ast::Prefix {
-    fn walk(&self, v: &mut V) {
-        match self {
-            &ast::Prefix::Name(_) => {}
-            &ast::Prefix::Expression(e) => e.walk(v),
-        }
+/// Whether the expression is `false` or `nil`
+fn is_falsy(e: &ast::Expression) -> bool {
+    match e {
+        ast::Expression::Symbol(s) => matches!(
+            s.token_type(),
+            TokenType::Symbol {
+                symbol: Symbol::False | Symbol::Nil
+            }
+        ),
+        ast::Expression::Parentheses { expression, .. } => is_falsy(expression),
+        _ => false,
     }
 }
 
-// ast::Expression
-impl<V: Visitor> CfgVisit<V> for ast::Expression {
-    fn walk(&self, v: &mut V) {
-        match self {
-            _ => {}
+#[cfg(test)]
+fn unreachable_stmts(code: &str) -> Vec<String> {
+    let src = Arc::new(SourceInfo::new("test.lua".to_owned(), code.to_owned()));
+    let Ok(ast) = crate::parse(Arc::clone(&src)) else {
+        panic!("failed to parse: {code}");
+    };
+    let mut resolver = Resolver::new(Arc::clone(&src), &crate::config::Config::default());
+    resolver.go(&ast);
+    let cf = ControlFlow::build(&src, &ast, &resolver);
+
+    struct Stmts<'a>(&'a ControlFlow, Vec<String>);
+    impl Visitor for Stmts<'_> {
+        fn visit_stmt(&mut self, node: &ast::Stmt) {
+            if !self.0.is_reachable(NodeId::from(node)) {
+                self.1.push(node.to_string().trim().to_owned());
+            }
+        }
+        fn visit_last_stmt(&mut self, node: &ast::LastStmt) {
+            if !self.0.is_reachable(NodeId::from(node)) {
+                self.1.push(node.to_string().trim().to_owned());
+            }
         }
     }
+    let mut v = Stmts(&cf, Vec::new());
+    v.visit_ast(&ast);
+    v.1
+}
+
+#[test]
+fn test_reachability() {
+    assert!(unreachable_stmts("local a = 1\nif a then return end\nprint(a)").is_empty());
+    assert_eq!(
+        unreachable_stmts("do return end\nprint(1)"),
+        vec!["print(1)"]
+    );
+    assert_eq!(
+        unreachable_stmts("if x then return 1 else error('e') end\nprint(1)"),
+        vec!["print(1)"]
+    );
+    assert_eq!(
+        unreachable_stmts("while true do print(1) end\nprint(2)"),
+        vec!["print(2)"]
+    );
+    assert!(unreachable_stmts("while true do break end\nprint(2)").is_empty());
+    assert_eq!(
+        unreachable_stmts("repeat local a = 1 until false\nprint(2)"),
+        vec!["print(2)"]
+    );
+    assert_eq!(
+        unreachable_stmts("for i = 1, 2 do do break end print(i) end"),
+        vec!["print(i)"]
+    );
+    // `goto` jumps over statements to a visible label
+    assert_eq!(
+        unreachable_stmts("goto skip\nprint(1)\n::skip::\nprint(2)"),
+        vec!["print(1)"]
+    );
+    // Nested functions have their own graphs
+    assert!(unreachable_stmts("local function f() return end\nf()").is_empty());
+    assert_eq!(
+        unreachable_stmts("local function f() error('e') print(1) end\nf()"),
+        vec!["print(1)"]
+    );
+    // Shadowed `error` may return
+    assert!(unreachable_stmts("local function error() end\nerror()\nprint(1)").is_empty());
 }
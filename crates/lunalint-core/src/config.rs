@@ -0,0 +1,532 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    diagnostics::{LintLabel, LintLevel, LintReport},
+    library::Library,
+    location::{Location, SourceInfo},
+};
+
+/// Configuration file compatible with lua-language-server.
+/// See https://luals.github.io/wiki/configuration/#luarcjson-file
+pub const LUARC_FILE_NAME: &str = ".luarc.json";
+/// Configuration file specific to lunalint.
+pub const TOML_FILE_NAME: &str = "lunalint.toml";
+/// Templates of paths of modules used if `runtime.path` is not configured, as in
+/// lua-language-server.
+const DEFAULT_RUNTIME_PATH: [&str; 2] = ["?.lua", "?/init.lua"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuntimeVersion {
+    Lua51,
+    Lua52,
+    Lua53,
+    #[default]
+    Lua54,
+    LuaJIT,
+}
+
+impl RuntimeVersion {
+    pub const ALL: [RuntimeVersion; 5] = [
+        Self::Lua51,
+        Self::Lua52,
+        Self::Lua53,
+        Self::Lua54,
+        Self::LuaJIT,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lua51 => "Lua 5.1",
+            Self::Lua52 => "Lua 5.2",
+            Self::Lua53 => "Lua 5.3",
+            Self::Lua54 => "Lua 5.4",
+            Self::LuaJIT => "LuaJIT",
+        }
+    }
+
+    /// `goto` statements and labels (`::name::`), introduced in Lua 5.2 and supported by LuaJIT.
+    pub fn has_goto(&self) -> bool {
+        !matches!(self, Self::Lua51)
+    }
+
+    /// Floor division (`//`) and bitwise operators, introduced in Lua 5.3.
+    pub fn has_integer_operators(&self) -> bool {
+        matches!(self, Self::Lua53 | Self::Lua54)
+    }
+
+    /// `<const>` and `<close>` attributes of local variables, introduced in Lua 5.4.
+    pub fn has_attributes(&self) -> bool {
+        matches!(self, Self::Lua54)
+    }
+}
+
+impl std::str::FromStr for RuntimeVersion {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|v| v.as_str().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    path: Option<PathBuf>,
+    disabled: HashSet<String>,
+    severity: HashMap<String, LintLevel>,
+    globals: Vec<String>,
+    runtime_version: RuntimeVersion,
+    runtime_path: Option<Vec<String>>,
+    /// Presets and definition files given by the user, in the order of precedence
+    definitions: Vec<Library>,
+    /// Standard library combined with `definitions`
+    library: OnceLock<Library>,
+}
+
+impl Config {
+    /// Find a configuration file by walking up from the directory of the given file.
+    /// `lunalint.toml` takes precedence over `.luarc.json` in the same directory.
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        let path = std::path::absolute(path).ok()?;
+        let cwd = std::env::current_dir().ok();
+        for dir in path.ancestors().skip(1) {
+            for name in [TOML_FILE_NAME, LUARC_FILE_NAME] {
+                let candidate = dir.join(name);
+                if !candidate.is_file() {
+                    continue;
+                }
+                // Keep paths under the working directory relative for readable reports
+                let relative = cwd
+                    .as_ref()
+                    .and_then(|cwd| candidate.strip_prefix(cwd).ok())
+                    .map(Path::to_path_buf);
+                return Some(relative.unwrap_or(candidate));
+            }
+        }
+        None
+    }
+
+    /// Find and load the configuration file for the given file. The default configuration is used
+    /// if no configuration file is found.
+    pub fn discover(path: &Path) -> (Config, Vec<LintReport>) {
+        match Config::find(path) {
+            Some(config_path) => Config::load(&config_path),
+            None => (Config::default(), Vec::new()),
+        }
+    }
+
+    /// Load a configuration file. The format is determined by the file name.
+    /// Invalid entries are reported and ignored.
+    pub fn load(path: &Path) -> (Config, Vec<LintReport>) {
+        log::debug!("loading config file: {:?}", path);
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                let report = LintReport::new_config_error(
+                    Location::dummy(),
+                    format!("Failed to read config file `{}`: {}", path.display(), e),
+                );
+                return (Config::default(), vec![report]);
+            }
+        };
+        let src = Arc::new(SourceInfo::new(
+            path.to_string_lossy().into_owned(),
+            content,
+        ));
+        let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+        let (mut config, reports) = if is_toml {
+            Config::from_toml(src)
+        } else {
+            Config::from_luarc(src)
+        };
+        config.path = Some(path.to_path_buf());
+        (config, reports)
+    }
+
+    /// Parse the contents of `.luarc.json`.
+    pub fn from_luarc(src: Arc<SourceInfo>) -> (Config, Vec<LintReport>) {
+        match serde_json::from_str::<RawConfig>(src.content()) {
+            Ok(raw) => raw.into_config(&src),
+            Err(e) => {
+                let offset = src.offset(e.line(), e.column());
+                let loc = Location::from_range(Arc::clone(&src), offset..offset);
+                (Config::default(), vec![invalid_config(loc, e.to_string())])
+            }
+        }
+    }
+
+    /// Parse the contents of `lunalint.toml`.
+    pub fn from_toml(src: Arc<SourceInfo>) -> (Config, Vec<LintReport>) {
+        match toml::from_str::<RawConfig>(src.content()) {
+            Ok(raw) => raw.into_config(&src),
+            Err(e) => {
+                let range = e.span().unwrap_or(0..0);
+                let loc = Location::from_range(Arc::clone(&src), range);
+                (
+                    Config::default(),
+                    vec![invalid_config(loc, e.message().to_owned())],
+                )
+            }
+        }
+    }
+
+    /// Path of the loaded configuration file, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name)
+    }
+
+    /// Severity overridden by the configuration.
+    pub fn severity(&self, name: &str) -> Option<LintLevel> {
+        self.severity.get(name).copied()
+    }
+
+    /// Additional global variables defined by the user.
+    pub fn globals(&self) -> &[String] {
+        &self.globals
+    }
+
+    pub fn runtime_version(&self) -> RuntimeVersion {
+        self.runtime_version
+    }
+
+    /// Templates of paths of modules loaded by `require`, relative to the directory of the
+    /// configuration file. `?` is replaced by the module name whose `.` are replaced by `/`.
+    pub fn runtime_path(&self) -> Vec<&str> {
+        match &self.runtime_path {
+            Some(path) => path.iter().map(String::as_str).collect(),
+            None => DEFAULT_RUNTIME_PATH.to_vec(),
+        }
+    }
+
+    /// Global variables of the standard library, presets and definition files.
+    pub fn library(&self) -> &Library {
+        self.library.get_or_init(|| {
+            let mut library = Library::bundled(self.runtime_version).clone();
+            for definitions in self.definitions.iter() {
+                library.extend(definitions);
+            }
+            library
+        })
+    }
+}
+
+pub(crate) fn invalid_config(loc: Location, msg: String) -> LintReport {
+    LintReport::new_config_error(loc.clone(), "Invalid configuration".to_owned())
+        .with_label(LintLabel::new(loc, msg))
+}
+
+/// Locate a value in the configuration file by its key path. Each of `paths` is tried in turn since
+/// both nested and dotted keys (e.g. `"diagnostics.severity"`) are accepted. If the value is an
+/// array, the element equal to `value` is located.
+pub(crate) fn locate_value(src: &Arc<SourceInfo>, paths: &[&[&str]], value: &str) -> Location {
+    let is_toml = src.path().ends_with(".toml");
+    let range = paths
+        .iter()
+        .find_map(|path| {
+            if is_toml {
+                toml_span(src.content(), path)
+            } else {
+                json_span(src.content(), path)
+            }
+        })
+        .map(|span| {
+            let text = &src.content()[span.clone()];
+            if !text.starts_with('[') {
+                return span;
+            }
+            ["\"", "'"]
+                .iter()
+                .find_map(|quote| text.find(&format!("{quote}{value}{quote}")))
+                .map(|offset| span.start + offset..span.start + offset + value.len() + 2)
+                .unwrap_or(span)
+        })
+        .unwrap_or(0..0);
+    Location::from_range(Arc::clone(src), range)
+}
+
+/// Span of the value at `path` in a TOML document.
+pub(crate) fn toml_span(content: &str, path: &[&str]) -> Option<Range<usize>> {
+    let doc = toml_edit::ImDocument::parse(content).ok()?;
+    let mut item = doc.as_item();
+    for key in path {
+        item = item.get(*key)?;
+    }
+    item.span()
+}
+
+/// Span of the value at `path` in a JSON document. serde_json does not preserve spans, so the
+/// document is scanned again. The document is known to be valid when this is called.
+fn json_span(content: &str, path: &[&str]) -> Option<Range<usize>> {
+    JsonScanner {
+        content: content.as_bytes(),
+        pos: 0,
+    }
+    .find(path)
+}
+
+struct JsonScanner<'a> {
+    content: &'a [u8],
+    pos: usize,
+}
+
+impl JsonScanner<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .content
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.content.get(self.pos) == Some(&c)).then(|| self.pos += 1)
+    }
+
+    /// Find the value at `path` in the value at the current position.
+    fn find(&mut self, path: &[&str]) -> Option<Range<usize>> {
+        let Some((key, rest)) = path.split_first() else {
+            return self.value();
+        };
+        self.expect(b'{')?;
+        loop {
+            let name = self.string()?;
+            self.expect(b':')?;
+            if self.content[name.start + 1..name.end - 1] == *key.as_bytes() {
+                return self.find(rest);
+            }
+            self.value()?;
+            self.expect(b',')?;
+        }
+    }
+
+    /// Skip the value at the current position and return its span.
+    fn value(&mut self) -> Option<Range<usize>> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.content.get(self.pos)? {
+            b'"' => {
+                self.string()?;
+            }
+            open @ (b'{' | b'[') => {
+                let close = if *open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.content.get(self.pos) == Some(&close) {
+                    self.pos += 1;
+                    return Some(start..self.pos);
+                }
+                loop {
+                    if close == b'}' {
+                        self.string()?;
+                        self.expect(b':')?;
+                    }
+                    self.value()?;
+                    self.skip_whitespace();
+                    match self.content.get(self.pos)? {
+                        b',' => self.pos += 1,
+                        c if *c == close => {
+                            self.pos += 1;
+                            break;
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            // Numbers, booleans and null
+            _ => {
+                while self
+                    .content
+                    .get(self.pos)
+                    .is_some_and(|c| !matches!(c, b',' | b'}' | b']') && !c.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+        Some(start..self.pos)
+    }
+
+    /// Skip a string literal and return its span including the quotes.
+    fn string(&mut self) -> Option<Range<usize>> {
+        self.expect(b'"')?;
+        let start = self.pos - 1;
+        loop {
+            match self.content.get(self.pos)? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(start..self.pos);
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+}
+
+/// Schema shared by `.luarc.json` and `lunalint.toml`. Unknown keys are ignored since `.luarc.json`
+/// contains many settings which are irrelevant to lunalint.
+/// Dotted keys (e.g. `"diagnostics.disable"`) are accepted as in lua-language-server.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawConfig {
+    diagnostics: RawDiagnostics,
+    runtime: RawRuntime,
+    #[serde(rename = "diagnostics.disable")]
+    diagnostics_disable: Vec<String>,
+    #[serde(rename = "diagnostics.severity")]
+    diagnostics_severity: HashMap<String, String>,
+    #[serde(rename = "diagnostics.globals")]
+    diagnostics_globals: Vec<String>,
+    #[serde(rename = "runtime.version")]
+    runtime_version: Option<String>,
+    #[serde(rename = "runtime.definitions")]
+    runtime_definitions: Vec<String>,
+    #[serde(rename = "runtime.presets")]
+    runtime_presets: Vec<String>,
+    #[serde(rename = "runtime.path")]
+    runtime_path: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawDiagnostics {
+    disable: Vec<String>,
+    severity: HashMap<String, String>,
+    globals: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawRuntime {
+    version: Option<String>,
+    definitions: Vec<String>,
+    presets: Vec<String>,
+    path: Option<Vec<String>>,
+}
+
+impl RawConfig {
+    fn into_config(self, src: &Arc<SourceInfo>) -> (Config, Vec<LintReport>) {
+        let mut config = Config::default();
+        let mut reports = Vec::new();
+
+        config.disabled = self
+            .diagnostics
+            .disable
+            .into_iter()
+            .chain(self.diagnostics_disable)
+            .collect();
+
+        config.globals = self.diagnostics.globals;
+        config.globals.extend(self.diagnostics_globals);
+
+        // Sort entries so that reports are emitted in a stable order
+        let mut severity = self
+            .diagnostics
+            .severity
+            .into_iter()
+            .chain(self.diagnostics_severity)
+            .collect::<Vec<_>>();
+        severity.sort();
+        for (name, level) in severity {
+            match parse_level(&level) {
+                Some(level) => {
+                    config.severity.insert(name, level);
+                }
+                None => reports.push(invalid_config(
+                    locate_value(
+                        src,
+                        &[
+                            &["diagnostics", "severity", &name],
+                            &["diagnostics.severity", &name],
+                        ],
+                        &level,
+                    ),
+                    format!(
+                        "Unknown severity `{level}`. Expected one of `Error`, `Warning`, `Information` or `Hint`"
+                    ),
+                )),
+            }
+        }
+
+        if let Some(version) = self.runtime.version.or(self.runtime_version) {
+            match version.parse() {
+                Ok(version) => config.runtime_version = version,
+                Err(()) => reports.push(invalid_config(
+                    locate_value(
+                        src,
+                        &[&["runtime", "version"], &["runtime.version"]],
+                        &version,
+                    ),
+                    format!(
+                        "Unknown runtime version `{version}`. Expected one of `Lua 5.1`, `Lua 5.2`, `Lua 5.3`, `Lua 5.4` or `LuaJIT`"
+                    ),
+                )),
+            }
+        }
+
+        config.runtime_path = self.runtime.path.or(self.runtime_path);
+
+        for name in self.runtime.presets.into_iter().chain(self.runtime_presets) {
+            match Library::preset(&name, config.runtime_version) {
+                Some(library) => config.definitions.push(library),
+                None => {
+                    let names = Library::preset_names()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>();
+                    let (last, rest) = names.split_last().unwrap();
+                    reports.push(invalid_config(
+                        locate_value(src, &[&["runtime", "presets"], &["runtime.presets"]], &name),
+                        format!(
+                            "Unknown preset `{name}`. Expected one of {} or {last}",
+                            rest.join(", ")
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // Paths of definition files are relative to the configuration file
+        let dir = Path::new(src.path()).parent().unwrap_or(Path::new(""));
+        for path in self
+            .runtime
+            .definitions
+            .into_iter()
+            .chain(self.runtime_definitions)
+        {
+            match Library::load(&dir.join(&path), config.runtime_version) {
+                Ok(library) => config.definitions.push(library),
+                Err(report) => reports.push(report),
+            }
+        }
+
+        (config, reports)
+    }
+}
+
+/// Parse a severity of lua-language-server. The `!` suffix, which forces the severity regardless
+/// of groups, has the same meaning in lunalint.
+fn parse_level(s: &str) -> Option<LintLevel> {
+    match s.strip_suffix('!').unwrap_or(s) {
+        "Error" => Some(LintLevel::Error),
+        "Warning" => Some(LintLevel::Warning),
+        "Information" => Some(LintLevel::Information),
+        "Hint" => Some(LintLevel::Hint),
+        _ => None,
+    }
+}
@@ -2,27 +2,92 @@ use std::sync::Arc;
 
 use parking_lot::{Mutex, MutexGuard};
 
-use crate::{diagnostics::LintReport, location::SourceInfo, resolver::Resolver};
+use full_moon::ast;
+
+use crate::{
+    annotation::Annotations,
+    cfg::ControlFlow,
+    config::Config,
+    diagnostics::LintReport,
+    location::SourceInfo,
+    resolver::Resolver,
+    suppression::Suppressions,
+    types::Types,
+    workspace::{Imports, Workspace},
+};
 
 pub struct Context {
     resolver: Resolver,
     reports: Mutex<Vec<Arc<LintReport>>>,
     src: Arc<SourceInfo>,
+    config: Arc<Config>,
+    suppressions: Suppressions,
+    control_flow: ControlFlow,
+    annotations: Annotations,
+    types: Types,
+    workspace: Option<Arc<Workspace>>,
+    imports: Imports,
 }
 
 impl Context {
     pub fn new(src: Arc<SourceInfo>) -> Self {
+        Self::with_config(src, Arc::new(Config::default()))
+    }
+
+    pub fn with_config(src: Arc<SourceInfo>, config: Arc<Config>) -> Self {
         Self {
-            resolver: Resolver::new(Arc::clone(&src)),
+            resolver: Resolver::new(Arc::clone(&src), &config),
             reports: Mutex::new(Vec::new()),
             src,
+            config,
+            suppressions: Suppressions::default(),
+            control_flow: ControlFlow::default(),
+            annotations: Annotations::default(),
+            types: Types::default(),
+            workspace: None,
+            imports: Imports::default(),
+        }
+    }
+
+    /// Create a context of a file of the workspace, which sees globals defined by other files
+    /// and modules loaded by `require`.
+    pub fn with_workspace(src: Arc<SourceInfo>, workspace: Arc<Workspace>) -> Self {
+        let imports = Imports::new(&workspace, &src);
+        let mut ctx = Self::with_config(src, Arc::clone(workspace.config()));
+        ctx.resolver.add_globals(imports.globals().keys().cloned());
+        ctx.imports = imports;
+        ctx.workspace = Some(workspace);
+        ctx
+    }
+
+    /// Run analyses required by lint passes.
+    pub fn analyze(&mut self, ast: &ast::Ast) {
+        self.resolver.go(ast);
+        if let Some(workspace) = &self.workspace {
+            self.imports
+                .collect_requires(workspace, &self.src, ast, &self.resolver);
         }
+        self.suppressions = Suppressions::collect(&self.src, ast);
+        self.control_flow = ControlFlow::build(&self.src, ast, &self.resolver);
+        self.annotations = Annotations::collect(&self.src, ast);
+        self.types = Types::infer(
+            ast,
+            &self.resolver,
+            &self.control_flow,
+            &self.annotations,
+            &self.config,
+            &self.imports,
+        );
     }
 
     pub fn src(&self) -> &Arc<SourceInfo> {
         &self.src
     }
 
+    pub fn config(&self) -> &Arc<Config> {
+        &self.config
+    }
+
     pub fn resolver(&self) -> &Resolver {
         &self.resolver
     }
@@ -31,15 +96,52 @@ impl Context {
         &mut self.resolver
     }
 
+    pub fn suppressions(&self) -> &Suppressions {
+        &self.suppressions
+    }
+
+    /// Control-flow graphs of the main chunk and functions.
+    pub fn control_flow(&self) -> &ControlFlow {
+        &self.control_flow
+    }
+
+    /// LuaCATS annotations in `---` comments.
+    pub fn annotations(&self) -> &Annotations {
+        &self.annotations
+    }
+
+    /// Workspace of the file. `None` if the file is linted alone.
+    pub fn workspace(&self) -> Option<&Arc<Workspace>> {
+        self.workspace.as_ref()
+    }
+
+    /// Globals and modules of the workspace visible from the file.
+    pub fn imports(&self) -> &Imports {
+        &self.imports
+    }
+
+    /// Inferred types of expressions and variables.
+    pub fn types(&self) -> &Types {
+        &self.types
+    }
+
     pub fn saw_error(&self) -> bool {
         !self.reports.lock().is_empty()
     }
 
-    pub fn push_report(&self, report: LintReport) {
+    /// Store a report. Reports of disabled or suppressed diagnostics are discarded and the
+    /// severity is overridden by the configuration.
+    pub fn push_report(&self, mut report: LintReport) {
+        if !self.config.is_enabled(report.name()) || self.suppressions.suppress(&report) {
+            return;
+        }
+        if let Some(level) = self.config.severity(report.name()) {
+            report.set_level(level);
+        }
         self.reports.lock().push(Arc::new(report));
     }
 
-    pub fn reports(&self) -> MutexGuard<Vec<Arc<LintReport>>> {
+    pub fn reports(&self) -> MutexGuard<'_, Vec<Arc<LintReport>>> {
         self.reports.lock()
     }
 }
@@ -0,0 +1,515 @@
+//! Machine-readable output formats of lint reports.
+
+use std::{collections::BTreeMap, io::Write, path::Path, sync::Arc};
+
+use serde::Serialize;
+
+use super::{write_report, LintLevel, LintReport};
+use crate::location::{Location, Position};
+
+const TOOL_NAME: &str = "lunalint";
+const TOOL_URI: &str = "https://github.com/tamaroning/lunalint";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human-readable reports rendered by ariadne
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+    /// SARIF 2.1.0
+    Sarif,
+    /// Checkstyle XML
+    Checkstyle,
+    /// JUnit XML
+    Junit,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 5] = [
+        Self::Text,
+        Self::Json,
+        Self::Sarif,
+        Self::Checkstyle,
+        Self::Junit,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+            Self::Sarif => "sarif",
+            Self::Checkstyle => "checkstyle",
+            Self::Junit => "junit",
+        }
+    }
+
+    pub fn formatter(&self) -> Box<dyn Formatter> {
+        match self {
+            Self::Text => Box::new(TextFormatter),
+            Self::Json => Box::new(JsonFormatter),
+            Self::Sarif => Box::new(SarifFormatter),
+            Self::Checkstyle => Box::new(CheckstyleFormatter),
+            Self::Junit => Box::new(JunitFormatter),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.as_str() == s)
+            .ok_or_else(|| {
+                let formats = Self::ALL.map(|f| f.as_str()).join(", ");
+                format!("unknown format `{s}` (expected one of {formats})")
+            })
+    }
+}
+
+/// Serializer of lint reports.
+pub trait Formatter {
+    /// Write reports of the linted files. `files` contains all linted files including ones without
+    /// reports, since some formats list successful files as well.
+    fn format(
+        &self,
+        files: &[String],
+        reports: &[Arc<LintReport>],
+        w: &mut dyn Write,
+    ) -> std::io::Result<()>;
+}
+
+pub struct TextFormatter;
+
+impl Formatter for TextFormatter {
+    fn format(
+        &self,
+        _files: &[String],
+        reports: &[Arc<LintReport>],
+        w: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        for report in reports {
+            write_report(report, &mut *w);
+        }
+        Ok(())
+    }
+}
+
+pub struct JsonFormatter;
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    name: &'a str,
+    kind: &'a str,
+    level: &'a str,
+    message: &'a str,
+    location: JsonLocation<'a>,
+    labels: Vec<JsonLabel<'a>>,
+    help: Option<String>,
+    fixes: Vec<JsonFix<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFix<'a> {
+    message: &'a str,
+    applicability: &'a str,
+    edits: Vec<JsonEdit<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonEdit<'a> {
+    location: JsonLocation<'a>,
+    new_text: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonLabel<'a> {
+    message: &'a str,
+    location: JsonLocation<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonLocation<'a> {
+    path: &'a str,
+    start: JsonPosition,
+    end: JsonPosition,
+}
+
+#[derive(Serialize)]
+struct JsonPosition {
+    line: usize,
+    column: usize,
+    offset: usize,
+}
+
+impl<'a> JsonLocation<'a> {
+    fn new(loc: &'a Location) -> Self {
+        Self {
+            path: loc.src().path(),
+            start: JsonPosition::new(loc.start()),
+            end: JsonPosition::new(loc.end()),
+        }
+    }
+}
+
+impl JsonPosition {
+    fn new(pos: Position) -> Self {
+        Self {
+            line: pos.line(),
+            column: pos.character(),
+            offset: pos.bytes(),
+        }
+    }
+}
+
+impl Formatter for JsonFormatter {
+    fn format(
+        &self,
+        _files: &[String],
+        reports: &[Arc<LintReport>],
+        w: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        for report in reports {
+            let label_locs = report.labels().iter().map(|l| l.loc()).collect::<Vec<_>>();
+            let edit_locs = report
+                .fixes()
+                .iter()
+                .map(|f| f.edits().iter().map(|e| e.loc()).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let json = JsonReport {
+                name: report.name(),
+                kind: report.kind().as_str(),
+                level: report.level().as_str(),
+                message: report.msg(),
+                location: JsonLocation::new(&report.loc),
+                labels: report
+                    .labels()
+                    .iter()
+                    .zip(label_locs.iter())
+                    .map(|(label, loc)| JsonLabel {
+                        message: label.msg(),
+                        location: JsonLocation::new(loc),
+                    })
+                    .collect(),
+                help: report.help_url(),
+                fixes: report
+                    .fixes()
+                    .iter()
+                    .zip(edit_locs.iter())
+                    .map(|(fix, locs)| JsonFix {
+                        message: fix.msg(),
+                        applicability: fix.applicability().as_str(),
+                        edits: fix
+                            .edits()
+                            .iter()
+                            .zip(locs.iter())
+                            .map(|(edit, loc)| JsonEdit {
+                                location: JsonLocation::new(loc),
+                                new_text: edit.new_text(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            serde_json::to_writer(&mut *w, &json)?;
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
+
+/// SARIF 2.1.0 log.
+/// See https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+pub struct SarifFormatter;
+
+impl SarifFormatter {
+    fn level(level: LintLevel) -> &'static str {
+        match level {
+            LintLevel::Error => "error",
+            LintLevel::Warning => "warning",
+            LintLevel::Information | LintLevel::Hint => "note",
+        }
+    }
+
+    fn physical_location(loc: &Location) -> serde_json::Value {
+        // SARIF lines and columns are 1-indexed
+        serde_json::json!({
+            "artifactLocation": { "uri": artifact_uri(loc.src().path()) },
+            "region": {
+                "startLine": loc.start().line().max(1),
+                "startColumn": loc.start().character().max(1),
+                "endLine": loc.end().line().max(1),
+                "endColumn": loc.end().character().max(1),
+            },
+        })
+    }
+}
+
+impl Formatter for SarifFormatter {
+    fn format(
+        &self,
+        files: &[String],
+        reports: &[Arc<LintReport>],
+        w: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        // Rules are sorted by name and referenced by index from results
+        let rules = reports
+            .iter()
+            .map(|r| (r.name(), r.help_url()))
+            .collect::<BTreeMap<_, _>>();
+        let rule_index = |name: &str| rules.keys().position(|n| *n == name).unwrap();
+
+        let results = reports
+            .iter()
+            .map(|report| {
+                let related = report
+                    .labels()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, label)| {
+                        serde_json::json!({
+                            "id": i,
+                            "physicalLocation": Self::physical_location(&label.loc()),
+                            "message": { "text": label.msg() },
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "ruleId": report.name(),
+                    "ruleIndex": rule_index(report.name()),
+                    "level": Self::level(report.level()),
+                    "message": { "text": report.msg() },
+                    "locations": [{ "physicalLocation": Self::physical_location(&report.loc) }],
+                    "relatedLocations": related,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let rules = rules
+            .iter()
+            .map(|(name, url)| {
+                let mut rule = serde_json::json!({ "id": name });
+                if let Some(url) = url {
+                    rule["helpUri"] = url.as_str().into();
+                }
+                rule
+            })
+            .collect::<Vec<_>>();
+
+        let artifacts = files
+            .iter()
+            .map(|f| serde_json::json!({ "location": { "uri": artifact_uri(f) } }))
+            .collect::<Vec<_>>();
+
+        let log = serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": TOOL_NAME,
+                        "informationUri": TOOL_URI,
+                        "version": TOOL_VERSION,
+                        "rules": rules,
+                    },
+                },
+                "artifacts": artifacts,
+                "results": results,
+            }],
+        });
+        serde_json::to_writer_pretty(&mut *w, &log)?;
+        writeln!(w)
+    }
+}
+
+/// URI reference of a file, which SARIF requires instead of a path. Relative paths become relative
+/// references and absolute paths become `file` URIs.
+fn artifact_uri(path: &str) -> String {
+    let is_absolute = Path::new(path).is_absolute();
+    let mut uri = String::new();
+    for c in path.chars() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' | '/' => uri.push(c),
+            c if c == std::path::MAIN_SEPARATOR => uri.push('/'),
+            // A colon in the first segment of a relative reference would be taken as a scheme
+            ':' if is_absolute => uri.push(c),
+            c => {
+                for b in c.encode_utf8(&mut [0; 4]).bytes() {
+                    uri.push_str(&format!("%{b:02X}"));
+                }
+            }
+        }
+    }
+    if !is_absolute {
+        uri
+    } else if uri.starts_with('/') {
+        format!("file://{uri}")
+    } else {
+        // Windows paths with a drive letter
+        format!("file:///{uri}")
+    }
+}
+
+/// Checkstyle XML, which is understood by many CI tools.
+pub struct CheckstyleFormatter;
+
+impl Formatter for CheckstyleFormatter {
+    fn format(
+        &self,
+        files: &[String],
+        reports: &[Arc<LintReport>],
+        w: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, r#"<checkstyle version="4.3">"#)?;
+        for (file, reports) in group_by_file(files, reports) {
+            if reports.is_empty() {
+                writeln!(w, r#"  <file name="{}"/>"#, escape_xml(file))?;
+                continue;
+            }
+            writeln!(w, r#"  <file name="{}">"#, escape_xml(file))?;
+            for report in reports {
+                let loc = report.loc();
+                let severity = match report.level() {
+                    LintLevel::Error => "error",
+                    LintLevel::Warning => "warning",
+                    LintLevel::Information | LintLevel::Hint => "info",
+                };
+                writeln!(
+                    w,
+                    r#"    <error line="{}" column="{}" severity="{}" message="{}" source="{}.{}"/>"#,
+                    loc.start().line(),
+                    loc.start().character(),
+                    severity,
+                    escape_xml(report.msg()),
+                    TOOL_NAME,
+                    escape_xml(report.name()),
+                )?;
+            }
+            writeln!(w, "  </file>")?;
+        }
+        writeln!(w, "</checkstyle>")
+    }
+}
+
+/// JUnit XML. Each file is a test suite and each report is a failed test case. Files without
+/// reports have a single successful test case.
+pub struct JunitFormatter;
+
+impl Formatter for JunitFormatter {
+    fn format(
+        &self,
+        files: &[String],
+        reports: &[Arc<LintReport>],
+        w: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let groups = group_by_file(files, reports);
+        let tests = groups.values().map(|r| r.len().max(1)).sum::<usize>();
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<testsuites name="{}" tests="{}" failures="{}">"#,
+            TOOL_NAME,
+            tests,
+            reports.len()
+        )?;
+        for (file, reports) in groups {
+            let file = escape_xml(file);
+            writeln!(
+                w,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0">"#,
+                file,
+                reports.len().max(1),
+                reports.len()
+            )?;
+            if reports.is_empty() {
+                writeln!(
+                    w,
+                    r#"    <testcase name="{}" classname="{}"/>"#,
+                    TOOL_NAME, file
+                )?;
+            }
+            for report in reports {
+                let loc = report.loc();
+                let position = format!(
+                    "{}:{}:{}",
+                    file,
+                    loc.start().line(),
+                    loc.start().character()
+                );
+                writeln!(
+                    w,
+                    r#"    <testcase name="{}" classname="{}">"#,
+                    escape_xml(report.name()),
+                    position
+                )?;
+                writeln!(
+                    w,
+                    r#"      <failure message="{}" type="{}">{}: {} ({})</failure>"#,
+                    escape_xml(report.msg()),
+                    report.level().as_str(),
+                    position,
+                    escape_xml(report.msg()),
+                    escape_xml(report.name()),
+                )?;
+                writeln!(w, "    </testcase>")?;
+            }
+            writeln!(w, "  </testsuite>")?;
+        }
+        writeln!(w, "</testsuites>")
+    }
+}
+
+/// Group reports by file path. Files without reports are included.
+fn group_by_file<'a>(
+    files: &'a [String],
+    reports: &'a [Arc<LintReport>],
+) -> BTreeMap<&'a str, Vec<&'a Arc<LintReport>>> {
+    let mut groups = files
+        .iter()
+        .map(|f| (f.as_str(), Vec::new()))
+        .collect::<BTreeMap<_, _>>();
+    for report in reports {
+        groups
+            .entry(report.loc.src().path())
+            .or_default()
+            .push(report);
+    }
+    groups
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_artifact_uri() {
+    assert_eq!(artifact_uri("src/main.lua"), "src/main.lua");
+    assert_eq!(artifact_uri("my dir/100%.lua"), "my%20dir/100%25.lua");
+    assert_eq!(artifact_uri("a:b.lua"), "a%3Ab.lua");
+    assert_eq!(artifact_uri("ü.lua"), "%C3%BC.lua");
+    #[cfg(unix)]
+    assert_eq!(
+        artifact_uri("/home/user/my dir/main.lua"),
+        "file:///home/user/my%20dir/main.lua"
+    );
+    #[cfg(windows)]
+    assert_eq!(
+        artifact_uri("C:\\Users\\my dir\\main.lua"),
+        "file:///C:/Users/my%20dir/main.lua"
+    );
+}
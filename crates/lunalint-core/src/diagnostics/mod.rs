@@ -0,0 +1,292 @@
+pub mod format;
+
+use crate::{fix::Fix, location::Location, pass::Pass};
+use ariadne::{Color, Fmt, Label, Report, ReportKind};
+use std::sync::Arc;
+
+/// Diagnostics which are specific to lunalint and not documented in lua-language-server's wiki
+const LUNALINT_DIAGNOSTICS: &[&str] = &[
+    "doc-syntax",
+    "missing-module",
+    "require-cycle",
+    "unused-suppression",
+];
+
+#[derive(Clone)]
+pub struct LintReport {
+    name: String,
+    kind: LintKind,
+    level: LintLevel,
+    loc: Location,
+    msg: String,
+    labels: Vec<LintLabel>,
+    fixes: Vec<Fix>,
+    tags: Vec<LintTag>,
+}
+
+impl LintReport {
+    pub fn new(pass: &dyn Pass, loc: Location, msg: String) -> Self {
+        Self {
+            name: pass.name().to_string(),
+            kind: pass.kind(),
+            level: pass.level(),
+            loc,
+            msg,
+            labels: Vec::new(),
+            fixes: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn new_parse_error(loc: Location, msg: String) -> Self {
+        LintReport {
+            name: "parse-error".to_owned(),
+            kind: LintKind::ParseError,
+            level: LintLevel::Error,
+            loc,
+            msg,
+            labels: Vec::new(),
+            fixes: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn new_config_error(loc: Location, msg: String) -> Self {
+        LintReport {
+            name: "config-error".to_owned(),
+            kind: LintKind::ConfigError,
+            level: LintLevel::Error,
+            loc,
+            msg,
+            labels: Vec::new(),
+            fixes: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_label(mut self, label: LintLabel) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: LintTag) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    pub(crate) fn set_level(&mut self, level: LintLevel) {
+        self.level = level;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> LintKind {
+        self.kind
+    }
+
+    pub fn level(&self) -> LintLevel {
+        self.level
+    }
+
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    pub fn labels(&self) -> &[LintLabel] {
+        &self.labels
+    }
+
+    pub fn fixes(&self) -> &[Fix] {
+        &self.fixes
+    }
+
+    pub fn tags(&self) -> &[LintTag] {
+        &self.tags
+    }
+
+    /// URL of the documentation of the diagnostic.
+    pub fn help_url(&self) -> Option<String> {
+        if !matches!(self.kind, LintKind::Diagnostics | LintKind::SyntaxError)
+            || LUNALINT_DIAGNOSTICS.contains(&self.name.as_str())
+        {
+            return None;
+        }
+        Some(format!(
+            "https://luals.github.io/wiki/{}/#{}",
+            self.kind.as_str(),
+            self.name
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    Diagnostics,
+    SyntaxError,
+    ParseError,
+    ConfigError,
+}
+
+impl LintKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Diagnostics => "diagnostics",
+            Self::SyntaxError => "syntax-errors",
+            Self::ParseError => "parse-errors",
+            Self::ConfigError => "config-errors",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl LintLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Information => "information",
+            Self::Hint => "hint",
+        }
+    }
+}
+
+/// Additional metadata of a report which editors use to render the range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintTag {
+    /// Unused or unreachable code, which is rendered faded out
+    Unnecessary,
+}
+
+#[derive(Clone)]
+pub enum LintLabel {
+    Label { msg: String, loc: Location },
+}
+
+impl LintLabel {
+    pub fn new(loc: Location, msg: String) -> Self {
+        Self::Label { msg, loc }
+    }
+
+    pub fn loc(&self) -> Location {
+        match self {
+            Self::Label { loc, .. } => loc.clone(),
+        }
+    }
+
+    pub fn msg(&self) -> &str {
+        match self {
+            Self::Label { msg, .. } => msg,
+        }
+    }
+}
+
+/// Emit a lint report from lint passes, which is internally stored in the context.
+pub(crate) fn emit_report(pass: &dyn Pass, report: LintReport) {
+    pass.ctx().push_report(report)
+}
+
+/// Sort reports by file path and location so that the output does not depend on the order in
+/// which passes or threads emitted them.
+pub fn sort_reports(reports: &mut [Arc<LintReport>]) {
+    reports.sort_by(|a, b| {
+        let (a_loc, b_loc) = (a.loc(), b.loc());
+        (
+            a_loc.src().path(),
+            a_loc.range().start,
+            a_loc.range().end,
+            a.name(),
+        )
+            .cmp(&(
+                b_loc.src().path(),
+                b_loc.range().start,
+                b_loc.range().end,
+                b.name(),
+            ))
+    });
+}
+
+/// Print a lint report to stderr. Do not use this from lint passes.
+pub fn eprint_report(report: &LintReport) {
+    convert_report(report).eprint(sources(report)).unwrap();
+}
+
+/// Write a lint report to the given writer.
+pub fn write_report<W: std::io::Write>(report: &LintReport, w: W) {
+    convert_report(report).write(sources(report), w).unwrap();
+}
+
+/// Sources of the report and its labels, which may be in other files.
+fn sources(report: &LintReport) -> impl ariadne::Cache<String> {
+    let mut sources = vec![report.loc().src().clone()];
+    for label in report.labels() {
+        let src = label.loc().src().clone();
+        if !sources.iter().any(|s| s.path() == src.path()) {
+            sources.push(src);
+        }
+    }
+    ariadne::sources(
+        sources
+            .into_iter()
+            .map(|src| (src.path().to_owned(), src.content().to_owned())),
+    )
+}
+
+/// Convert a lint report to an ariadne's report.
+fn convert_report(report: &LintReport) -> Report<'static, (String, std::ops::Range<usize>)> {
+    let LintReport {
+        name,
+        kind: _,
+        level,
+        loc,
+        msg,
+        labels,
+        fixes: _,
+        tags: _,
+    } = report;
+
+    let level = match level {
+        LintLevel::Error => ReportKind::Error,
+        LintLevel::Warning => ReportKind::Warning,
+        LintLevel::Information => ReportKind::Advice,
+        LintLevel::Hint => ReportKind::Custom("Hint", Color::Cyan),
+    };
+    let path = loc.src().path().to_owned();
+    let mut builder = Report::build(level, path.clone(), loc.start().bytes()).with_message(
+        format!("{} {}", msg, format!("({})", name).fg(Color::BrightBlack)),
+    );
+
+    for label in labels {
+        // Labels without sources (e.g. in the library) are rendered at the location of the report
+        let label_loc = label.loc();
+        let span = if label_loc.src().content().is_empty() {
+            (path.clone(), loc.range())
+        } else {
+            (label_loc.src().path().to_owned(), label_loc.range())
+        };
+        builder = builder.with_label(Label::new(span).with_message(label.msg()));
+    }
+
+    if let Some(url) = report.help_url() {
+        builder = builder.with_help(format!("for further information visit {}", url));
+    }
+
+    builder.finish()
+}
@@ -0,0 +1,157 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::location::{Location, SourceInfo};
+
+/// Whether a fix can be applied without reviewing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Applicability {
+    /// The fix preserves the behavior of the program (e.g. removing an unused comment)
+    Safe,
+    /// The fix may change the behavior of the program (e.g. replacing a misspelled name)
+    Unsafe,
+}
+
+impl Applicability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Safe => "safe",
+            Self::Unsafe => "unsafe",
+        }
+    }
+}
+
+/// Replace the text at `loc` with `new_text`.
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    loc: Location,
+    new_text: String,
+}
+
+impl TextEdit {
+    pub fn new(loc: Location, new_text: String) -> Self {
+        Self { loc, new_text }
+    }
+
+    pub fn delete(loc: Location) -> Self {
+        Self::new(loc, String::new())
+    }
+
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.loc.range()
+    }
+
+    pub fn new_text(&self) -> &str {
+        &self.new_text
+    }
+}
+
+/// A set of edits which must be applied together.
+#[derive(Debug, Clone)]
+pub struct Fix {
+    msg: String,
+    applicability: Applicability,
+    edits: Vec<TextEdit>,
+}
+
+impl Fix {
+    pub fn new(msg: String, applicability: Applicability, edits: Vec<TextEdit>) -> Self {
+        Self {
+            msg,
+            applicability,
+            edits,
+        }
+    }
+
+    /// A fix which replaces a single range.
+    pub fn replace(
+        msg: String,
+        applicability: Applicability,
+        loc: Location,
+        new_text: String,
+    ) -> Self {
+        Self::new(msg, applicability, vec![TextEdit::new(loc, new_text)])
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    pub fn applicability(&self) -> Applicability {
+        self.applicability
+    }
+
+    pub fn edits(&self) -> &[TextEdit] {
+        &self.edits
+    }
+}
+
+/// Apply fixes to `content`. A fix whose edits overlap with an already accepted fix is skipped
+/// so that it can be retried after re-linting. Returns the new content and the number of applied
+/// fixes.
+pub fn apply_fixes(content: &str, fixes: &[&Fix]) -> (String, usize) {
+    let mut fixes = fixes.to_vec();
+    fixes.sort_by_key(|fix| fix.edits.iter().map(|e| e.range().start).min());
+
+    let mut accepted: Vec<&TextEdit> = Vec::new();
+    let mut applied = 0;
+    for fix in fixes {
+        let in_bounds = fix.edits.iter().all(|e| {
+            let range = e.range();
+            range.start <= range.end
+                && range.end <= content.len()
+                && content.is_char_boundary(range.start)
+                && content.is_char_boundary(range.end)
+        });
+        let overlaps = fix.edits.iter().any(|e| {
+            accepted
+                .iter()
+                .any(|a| ranges_overlap(&e.range(), &a.range()))
+        });
+        if fix.edits.is_empty() || !in_bounds || overlaps {
+            continue;
+        }
+        accepted.extend(fix.edits.iter());
+        applied += 1;
+    }
+
+    // Apply from the end so that earlier offsets remain valid
+    accepted.sort_by_key(|e| std::cmp::Reverse((e.range().start, e.range().end)));
+    let mut content = content.to_owned();
+    for edit in accepted {
+        content.replace_range(edit.range(), edit.new_text());
+    }
+    (content, applied)
+}
+
+/// Range to remove code. Code on its own lines is removed with the lines. Otherwise the code is
+/// removed with preceding whitespaces.
+pub(crate) fn removal_range(src: &Arc<SourceInfo>, loc: &Location) -> Location {
+    let content = src.content();
+    let range = loc.range();
+    let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[range.end..]
+        .find('\n')
+        .map_or(content.len(), |i| range.end + i + 1);
+    let before = &content[line_start..range.start];
+    let after = &content[range.end..line_end];
+    if before.trim().is_empty() && after.trim().is_empty() {
+        Location::from_range(Arc::clone(src), line_start..line_end)
+    } else {
+        let start = range.start - (before.len() - before.trim_end().len());
+        Location::from_range(Arc::clone(src), start..range.end)
+    }
+}
+
+/// Two edits overlap if they share any byte. Insertions at the same position also conflict since
+/// their order would be ambiguous.
+fn ranges_overlap(a: &Range<usize>, b: &Range<usize>) -> bool {
+    let intersects = a.start < b.end && b.start < a.end;
+    if a.is_empty() || b.is_empty() {
+        return intersects || a.start == b.start;
+    }
+    intersects
+}
@@ -1,14 +1,24 @@
+pub mod annotation;
+pub mod cfg;
+pub mod config;
 mod context;
 pub mod diagnostics;
+pub mod fix;
+pub mod library;
 pub mod location;
 mod parse;
 pub mod pass;
-mod resolver;
+pub mod rename;
+pub mod resolver;
+pub mod suppression;
+pub mod types;
 mod utils;
+pub mod workspace;
 
+pub use config::Config;
 pub use context::Context;
 pub use diagnostics::eprint_report;
-pub use parse::parse;
+pub use parse::{parse, parse_prefix};
 
 // reexports
 pub use ariadne;
@@ -0,0 +1,437 @@
+//! Definitions of global variables provided by the runtime or the host application.
+//!
+//! Definitions are written in TOML. Each key is a global variable. A member with `params` is a
+//! function, a member with `fields` is a table, and any other member is a value. Parameters ending
+//! with `?` are optional and `...` accepts any number of arguments. `versions` restricts a member
+//! to some runtime versions. Tables with `open = true` list only some of their fields, so accesses to
+//! other fields are not reported. Functions with `nilable = true` may return `nil`.
+//!
+//! ```toml
+//! [string.fields]
+//! byte = { params = ["s", "i?", "j?"] }
+//! match = { params = ["s", "pattern", "init?"], nilable = true }
+//! pack = { params = ["fmt", "..."], versions = ["Lua 5.3", "Lua 5.4"] }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    config::{invalid_config, locate_value, toml_span, RuntimeVersion},
+    diagnostics::LintReport,
+    location::{Location, SourceInfo},
+};
+
+/// Definitions of the standard library bundled with lunalint
+const BUNDLED: [(&str, &str); 12] = [
+    ("builtins.toml", include_str!("../library/builtins.toml")),
+    ("coroutine.toml", include_str!("../library/coroutine.toml")),
+    ("debug.toml", include_str!("../library/debug.toml")),
+    ("io.toml", include_str!("../library/io.toml")),
+    ("math.toml", include_str!("../library/math.toml")),
+    ("os.toml", include_str!("../library/os.toml")),
+    ("package.toml", include_str!("../library/package.toml")),
+    ("string.toml", include_str!("../library/string.toml")),
+    ("table.toml", include_str!("../library/table.toml")),
+    ("utf8.toml", include_str!("../library/utf8.toml")),
+    ("bit32.toml", include_str!("../library/bit32.toml")),
+    ("luajit.toml", include_str!("../library/luajit.toml")),
+];
+
+/// Definitions of frameworks and host applications, selected by `runtime.presets`
+const PRESETS: [(&str, &str); 5] = [
+    ("love", include_str!("../library/presets/love.toml")),
+    ("neovim", include_str!("../library/presets/neovim.toml")),
+    (
+        "openresty",
+        include_str!("../library/presets/openresty.toml"),
+    ),
+    ("redis", include_str!("../library/presets/redis.toml")),
+    ("roblox", include_str!("../library/presets/roblox.toml")),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Member {
+    Function {
+        min_args: usize,
+        /// `None` if the function accepts any number of arguments
+        max_args: Option<usize>,
+        /// Whether the function may return `nil` (e.g. `string.match` without a match)
+        nilable: bool,
+    },
+    Table {
+        fields: BTreeMap<String, Member>,
+        /// Whether the table may have fields other than `fields`
+        open: bool,
+    },
+    Value,
+}
+
+impl Member {
+    pub fn field(&self, name: &str) -> Option<&Member> {
+        match self {
+            Member::Table { fields, .. } => fields.get(name),
+            _ => None,
+        }
+    }
+
+    /// Fields of a table. Empty for other members.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &Member)> {
+        let fields = match self {
+            Member::Table { fields, .. } => Some(fields),
+            _ => None,
+        };
+        fields
+            .into_iter()
+            .flat_map(|fields| fields.iter().map(|(name, member)| (name.as_str(), member)))
+    }
+
+    /// Whether accesses to unknown fields of the member can be reported.
+    pub fn is_closed_table(&self) -> bool {
+        matches!(self, Member::Table { open: false, .. })
+    }
+
+    /// Merge `other` into this member. Fields of tables are merged recursively, and other members
+    /// are replaced.
+    fn merge(&mut self, other: &Member) {
+        match (self, other) {
+            (
+                Member::Table { fields, open },
+                Member::Table {
+                    fields: other,
+                    open: other_open,
+                },
+            ) => {
+                *open |= other_open;
+                for (name, member) in other {
+                    match fields.get_mut(name) {
+                        Some(field) => field.merge(member),
+                        None => {
+                            fields.insert(name.clone(), member.clone());
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other.clone(),
+        }
+    }
+}
+
+/// Global variables available in a runtime version.
+#[derive(Debug, Clone, Default)]
+pub struct Library {
+    globals: BTreeMap<String, Member>,
+}
+
+impl Library {
+    /// Standard library of the runtime version.
+    pub fn bundled(version: RuntimeVersion) -> &'static Library {
+        static BUNDLED_LIBRARIES: OnceLock<Vec<Library>> = OnceLock::new();
+        let libraries = BUNDLED_LIBRARIES.get_or_init(|| {
+            RuntimeVersion::ALL
+                .iter()
+                .map(|version| {
+                    let mut library = Library::default();
+                    for (name, content) in BUNDLED {
+                        let src = Arc::new(SourceInfo::new(name.to_owned(), content.to_owned()));
+                        match Library::parse(src, *version) {
+                            Ok(bundled) => library.extend(&bundled),
+                            Err(_) => panic!("invalid bundled definition file `{name}`"),
+                        }
+                    }
+                    library
+                })
+                .collect()
+        });
+        let index = RuntimeVersion::ALL
+            .iter()
+            .position(|v| *v == version)
+            .unwrap();
+        &libraries[index]
+    }
+
+    /// Definitions of a preset. `None` if there is no such preset.
+    pub fn preset(name: &str, version: RuntimeVersion) -> Option<Library> {
+        let (name, content) = PRESETS.iter().find(|(preset, _)| *preset == name)?;
+        let src = Arc::new(SourceInfo::new(
+            format!("{name}.toml"),
+            (*content).to_owned(),
+        ));
+        match Library::parse(src, version) {
+            Ok(library) => Some(library),
+            Err(_) => panic!("invalid preset `{name}`"),
+        }
+    }
+
+    /// Names of presets in alphabetical order.
+    pub fn preset_names() -> impl Iterator<Item = &'static str> {
+        PRESETS.iter().map(|(name, _)| *name)
+    }
+
+    /// Load a definition file. Members which are not available in the runtime version are
+    /// skipped.
+    #[allow(clippy::result_large_err)]
+    pub fn load(path: &Path, version: RuntimeVersion) -> Result<Library, LintReport> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            LintReport::new_config_error(
+                Location::dummy(),
+                format!("Failed to read definition file `{}`: {}", path.display(), e),
+            )
+        })?;
+        let src = Arc::new(SourceInfo::new(
+            path.to_string_lossy().into_owned(),
+            content,
+        ));
+        Library::parse(src, version)
+    }
+
+    /// Parse the contents of a definition file.
+    #[allow(clippy::result_large_err)]
+    pub fn parse(src: Arc<SourceInfo>, version: RuntimeVersion) -> Result<Library, LintReport> {
+        let raw = toml::from_str::<BTreeMap<String, RawMember>>(src.content()).map_err(|e| {
+            let range = e.span().unwrap_or(0..0);
+            invalid_config(
+                Location::from_range(Arc::clone(&src), range),
+                e.message().to_owned(),
+            )
+        })?;
+        let mut globals = BTreeMap::new();
+        for (name, member) in raw {
+            if let Some(member) = member.into_member(&[&name], &src, version)? {
+                globals.insert(name, member);
+            }
+        }
+        Ok(Library { globals })
+    }
+
+    /// Add definitions of `other`. Tables defined by both are merged.
+    pub fn extend(&mut self, other: &Library) {
+        for (name, member) in other.globals.iter() {
+            match self.globals.get_mut(name) {
+                Some(global) => global.merge(member),
+                None => {
+                    self.globals.insert(name.clone(), member.clone());
+                }
+            }
+        }
+    }
+
+    pub fn global(&self, name: &str) -> Option<&Member> {
+        self.globals.get(name)
+    }
+
+    /// Names of global variables in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.globals.keys().map(String::as_str)
+    }
+}
+
+/// Runtime versions whose standard library defines the global variable.
+pub(crate) fn versions_of(name: &str) -> Vec<RuntimeVersion> {
+    RuntimeVersion::ALL
+        .into_iter()
+        .filter(|version| Library::bundled(*version).global(name).is_some())
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMember {
+    params: Option<Vec<String>>,
+    fields: Option<BTreeMap<String, RawMember>>,
+    #[serde(default)]
+    open: bool,
+    #[serde(default)]
+    nilable: bool,
+    versions: Option<Vec<String>>,
+}
+
+impl RawMember {
+    /// Convert the member at the key path to a member. Returns `None` if the member is not
+    /// available in the version.
+    #[allow(clippy::result_large_err)]
+    fn into_member(
+        self,
+        path: &[&str],
+        src: &Arc<SourceInfo>,
+        version: RuntimeVersion,
+    ) -> Result<Option<Member>, LintReport> {
+        if let Some(versions) = self.versions {
+            let mut available = false;
+            for v in versions {
+                match v.parse::<RuntimeVersion>() {
+                    Ok(v) => available |= v == version,
+                    Err(()) => {
+                        return Err(invalid_config(
+                            locate_value(src, &[&[path, &["versions"]].concat()], &v),
+                            format!(
+                                "Unknown runtime version `{v}`. Expected one of `Lua 5.1`, `Lua 5.2`, `Lua 5.3`, `Lua 5.4` or `LuaJIT`"
+                            ),
+                        ))
+                    }
+                }
+            }
+            if !available {
+                return Ok(None);
+            }
+        }
+
+        let fields = match (self.fields, self.open) {
+            (None, true) => Some(BTreeMap::new()),
+            (fields, _) => fields,
+        };
+        let member = match (self.params, fields) {
+            (Some(params), None) => Member::Function {
+                min_args: params
+                    .iter()
+                    .filter(|p| *p != "..." && !p.ends_with('?'))
+                    .count(),
+                max_args: (!params.iter().any(|p| p == "...")).then_some(params.len()),
+                nilable: self.nilable,
+            },
+            (None, Some(raw_fields)) => {
+                let mut fields = BTreeMap::new();
+                for (field_name, field) in raw_fields {
+                    let field_path = [path, &["fields", &field_name]].concat();
+                    if let Some(field) = field.into_member(&field_path, src, version)? {
+                        fields.insert(field_name, field);
+                    }
+                }
+                Member::Table {
+                    fields,
+                    open: self.open,
+                }
+            }
+            (None, None) => Member::Value,
+            (Some(_), Some(_)) => {
+                // Skip `fields` between names
+                let name = path
+                    .iter()
+                    .step_by(2)
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(".");
+                let range = toml_span(src.content(), path).unwrap_or(0..0);
+                return Err(invalid_config(
+                    Location::from_range(Arc::clone(src), range),
+                    format!("`{name}` cannot be both a function and a table"),
+                ));
+            }
+        };
+        Ok(Some(member))
+    }
+}
+
+#[test]
+fn test_bundled() {
+    let lua54 = Library::bundled(RuntimeVersion::Lua54);
+    let string = lua54.global("string").unwrap();
+    assert_eq!(
+        string.field("format"),
+        Some(&Member::Function {
+            min_args: 1,
+            max_args: None,
+            nilable: false
+        })
+    );
+    assert_eq!(
+        string.field("sub"),
+        Some(&Member::Function {
+            min_args: 2,
+            max_args: Some(3),
+            nilable: false
+        })
+    );
+    assert!(lua54.global("setfenv").is_none());
+    assert!(lua54.global("utf8").is_some());
+
+    let lua51 = Library::bundled(RuntimeVersion::Lua51);
+    assert!(lua51.global("setfenv").is_some());
+    assert!(lua51.global("utf8").is_none());
+    assert!(lua51.global("table").unwrap().field("unpack").is_none());
+    assert_eq!(
+        versions_of("unpack"),
+        vec![RuntimeVersion::Lua51, RuntimeVersion::LuaJIT]
+    );
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn test_parse() {
+    let parse = |content: &str| {
+        let src = Arc::new(SourceInfo::new("host.toml".to_owned(), content.to_owned()));
+        Library::parse(src, RuntimeVersion::Lua54)
+    };
+    let Ok(host) = parse("[host.fields]\nlog = { params = [\"msg\"] }\nversion = {}\n") else {
+        panic!("failed to parse");
+    };
+    let mut library = Library::bundled(RuntimeVersion::Lua54).clone();
+    library.extend(&host);
+    assert!(library.global("host").unwrap().field("log").is_some());
+    assert!(library.global("print").is_some());
+
+    // Definitions add fields to existing tables
+    let Ok(string) = parse("[string.fields]\nsplit = { params = [\"s\", \"sep\"] }\n") else {
+        panic!("failed to parse");
+    };
+    library.extend(&string);
+    let string = library.global("string").unwrap();
+    assert!(string.field("split").is_some());
+    assert!(string.field("format").is_some());
+
+    assert!(parse("x = { versions = [\"Lua 6.0\"] }").is_err());
+    assert!(parse("x = { args = 1 }").is_err());
+}
+
+#[test]
+fn test_presets() {
+    for name in Library::preset_names() {
+        assert!(Library::preset(name, RuntimeVersion::LuaJIT).is_some());
+    }
+    assert!(Library::preset("unknown", RuntimeVersion::Lua54).is_none());
+
+    let neovim = Library::preset("neovim", RuntimeVersion::LuaJIT).unwrap();
+    let vim = neovim.global("vim").unwrap();
+    assert!(!vim.is_closed_table());
+    assert!(vim.field("keymap").unwrap().is_closed_table());
+
+    let mut library = Library::bundled(RuntimeVersion::Lua51).clone();
+    library.extend(&Library::preset("redis", RuntimeVersion::Lua51).unwrap());
+    assert!(library.global("redis").unwrap().field("call").is_some());
+    assert!(library.global("KEYS").is_some());
+    assert!(library.global("print").is_some());
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn test_parse_error_location() {
+    // Message and located text of the error
+    let locate = |content: &str| {
+        let src = Arc::new(SourceInfo::new("host.toml".to_owned(), content.to_owned()));
+        let Err(report) = Library::parse(src, RuntimeVersion::Lua54) else {
+            panic!("parsed invalid definitions");
+        };
+        let label = report.labels()[0].msg().to_owned();
+        (label, content[report.loc().range()].to_owned())
+    };
+    assert_eq!(
+        locate("[host.fields]\nlog = { params = [], fields = {} }\n"),
+        (
+            "`host.log` cannot be both a function and a table".to_owned(),
+            "{ params = [], fields = {} }".to_owned()
+        )
+    );
+    assert_eq!(
+        locate("[host.fields.log]\nparams = []\nopen = true\n").1,
+        "[host.fields.log]\nparams = []\nopen = true"
+    );
+    assert_eq!(
+        locate("[host.fields]\nlog = { versions = [\"Lua 5.4\", \"Lua 6.0\"] }\n").1,
+        "\"Lua 6.0\""
+    );
+}
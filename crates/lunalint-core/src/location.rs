@@ -23,6 +23,36 @@ impl SourceInfo {
     pub fn content(&self) -> &str {
         &self.content
     }
+
+    /// Compute the position of the given byte offset. Lines and characters are 1-indexed as in
+    /// full_moon.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.content.len());
+        let before = &self.content[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Position::new(
+            offset,
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+
+    /// Compute the byte offset of the given 1-indexed line and character.
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let line_start = self
+            .content
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum::<usize>();
+        let line_text = self.content[line_start..].split('\n').next().unwrap_or("");
+        let column = line_text
+            .char_indices()
+            .nth(character.saturating_sub(1))
+            .map(|(i, _)| i)
+            .unwrap_or(line_text.len());
+        line_start + column
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +75,12 @@ impl Location {
         }
     }
 
+    pub(crate) fn from_range(src: Arc<SourceInfo>, range: Range<usize>) -> Self {
+        let start = src.position(range.start);
+        let end = src.position(range.end);
+        Self { src, start, end }
+    }
+
     pub fn dummy() -> Self {
         Location {
             src: Arc::new(SourceInfo::new("<null>".to_string(), "".to_string())),
@@ -7,10 +7,35 @@ use crate::{
     location::{Location, Position, SourceInfo},
 };
 
+/// Maximum number of prefixes tried by [parse_prefix]
+const MAX_PREFIX_ATTEMPTS: usize = 32;
+
+#[allow(clippy::result_large_err)]
 pub fn parse(src: Arc<SourceInfo>) -> Result<full_moon::ast::Ast, LintReport> {
     full_moon::parse(src.content()).map_err(|e| convert_error(e, src))
 }
 
+/// Parse the longest prefix of the source before the parse error which consists of complete
+/// statements. Prefixes are cut at lines starting at the first column, which are likely to start
+/// top-level statements. Positions in the AST are the same as in the whole source.
+pub fn parse_prefix(src: &SourceInfo, error: &LintReport) -> Option<full_moon::ast::Ast> {
+    let content = src.content();
+    let error_offset = error.loc().range().start.min(content.len());
+    let line_starts = std::iter::once(0)
+        .chain(
+            content[..error_offset]
+                .match_indices('\n')
+                .map(|(i, _)| i + 1),
+        )
+        .filter(|&start| content[start..].starts_with(|c: char| !c.is_whitespace()))
+        .collect::<Vec<_>>();
+    line_starts
+        .into_iter()
+        .rev()
+        .take(MAX_PREFIX_ATTEMPTS)
+        .find_map(|cut| full_moon::parse(&content[..cut]).ok())
+}
+
 fn convert_error(e: full_moon::Error, src: Arc<SourceInfo>) -> LintReport {
     match e {
         full_moon::Error::TokenizerError(e) => {
@@ -31,7 +56,7 @@ fn convert_error(e: full_moon::Error, src: Arc<SourceInfo>) -> LintReport {
             );
             let msg = format!(
                 "Unexpected token `{}`{}",
-                token.to_string(),
+                token,
                 additional.map(|s| format!(", {}", s)).unwrap_or_default()
             );
             LintReport::new_parse_error(loc.clone(), "Error occured while parsing".to_owned())
@@ -39,3 +64,14 @@ fn convert_error(e: full_moon::Error, src: Arc<SourceInfo>) -> LintReport {
         }
     }
 }
+
+#[test]
+fn test_parse_prefix() {
+    let code = "local a = 1\nfunction f()\n  return 1\n  print(2)\nend\nlocal b = 2\n";
+    let src = Arc::new(SourceInfo::new("test.lua".to_owned(), code.to_owned()));
+    let Err(error) = parse(Arc::clone(&src)) else {
+        panic!("expected a parse error");
+    };
+    let ast = parse_prefix(&src, &error).unwrap();
+    assert_eq!(ast.nodes().to_string(), "local a = 1\n");
+}
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::resolver::NodeId;
+use crate::types::LuaType;
+use crate::utils;
+use crate::{context::Context, impl_lint_pass, location::Location};
+use full_moon::{ast, node::Node, visitors::Visitor};
+
+/// Report values whose types cannot be assigned to variables declared by `---@type` or
+/// `---@param`, or to fields declared by `---@field`. Assigning `nil` is allowed since variables
+/// are often cleared or initialized later.
+pub struct AssignTypeMismatch {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "assign-type-mismatch",
+    AssignTypeMismatch,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+impl AssignTypeMismatch {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    /// Check the value assigned to the variable defined by `def_id`.
+    fn check_variable(&self, def_id: NodeId, name: &str, value: &ast::Expression) {
+        let types = self.ctx.types();
+        let (Some(declaration), Some(target)) = (types.declaration(def_id), types.get(def_id))
+        else {
+            return;
+        };
+        let label = format!("`{name}` is declared as `{target}` here");
+        self.check(value, target, declaration, label);
+    }
+
+    /// Check the value assigned to `prefix.field`.
+    fn check_field(&self, prefix: &ast::Prefix, field: &str, value: &ast::Expression) {
+        let types = self.ctx.types();
+        let resolver = self.ctx.resolver();
+        let Some(def_id) = resolver.lookup_definiton(NodeId::from(prefix)) else {
+            return;
+        };
+        let Some(LuaType::Class(class)) = types.get(def_id) else {
+            return;
+        };
+        let declaration = types.field_declaration(class, field);
+        let target = types.class(class).and_then(|shape| shape.fields.get(field));
+        let (Some(declaration), Some(target)) = (declaration, target) else {
+            return;
+        };
+        let label = format!("Field `{field}` is declared as `{target}` here");
+        self.check(value, target, declaration, label);
+    }
+
+    fn check(
+        &self,
+        value: &ast::Expression,
+        target: &LuaType,
+        declaration: &Location,
+        label: String,
+    ) {
+        let types = self.ctx.types();
+        let Some(value_ty) = types.get(NodeId::from(value)) else {
+            return;
+        };
+        if *value_ty == LuaType::Nil || types.is_assignable(value_ty, target) {
+            return;
+        }
+        let loc = Location::from((self.ctx.src(), value.tokens()));
+        emit_report(
+            self,
+            LintReport::new(
+                self,
+                loc.clone(),
+                format!("Cannot assign `{value_ty}` to `{target}`"),
+            )
+            .with_label(LintLabel::new(loc, format!("This value is `{value_ty}`")))
+            .with_label(LintLabel::new(declaration.clone(), label)),
+        );
+    }
+}
+
+impl Visitor for AssignTypeMismatch {
+    fn visit_local_assignment(&mut self, node: &ast::LocalAssignment) {
+        for (name, value) in node.names().iter().zip(node.expressions().iter()) {
+            self.check_variable(NodeId::from(name), utils::ident_as_str(name), value);
+        }
+    }
+
+    fn visit_assignment(&mut self, node: &ast::Assignment) {
+        let resolver = self.ctx.resolver();
+        for (var, value) in node.variables().iter().zip(node.expressions().iter()) {
+            match var {
+                ast::Var::Name(name) => {
+                    if let Some(def_id) = resolver.lookup_definiton(NodeId::from(var)) {
+                        self.check_variable(def_id, utils::ident_as_str(name), value);
+                    }
+                }
+                ast::Var::Expression(var) => {
+                    let suffixes = var.suffixes().collect::<Vec<_>>();
+                    if let [ast::Suffix::Index(ast::Index::Dot { name, .. })] = suffixes.as_slice()
+                    {
+                        self.check_field(var.prefix(), utils::ident_as_str(name), value);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::fix::{Applicability, Fix};
 use crate::utils;
 use crate::{context::Context, impl_lint_pass, location::Location};
 use full_moon::{node::Node, visitors::Visitor};
@@ -37,6 +38,11 @@ impl Visitor for CountDownLoop {
             let loc = Location::from((self.ctx().src(), node.start().tokens()))
                 + Location::from((self.ctx().src(), node.end().tokens()));
             // Keep original snippet of the start and end expressions
+            let suggestion = format!(
+                "{}, {}, -1",
+                node.start().to_string().trim(),
+                node.end().to_string().trim()
+            );
             emit_report(
                 self,
                 LintReport::new(
@@ -45,12 +51,15 @@ impl Visitor for CountDownLoop {
                     "Count down loop which never reaches end".to_string(),
                 )
                 .with_label(LintLabel::new(
+                    loc.clone(),
+                    format!("Did you mean `{}`?", suggestion),
+                ))
+                // The loop body starts to run, so the behavior changes
+                .with_fix(Fix::replace(
+                    "Add step `-1`".to_string(),
+                    Applicability::Unsafe,
                     loc,
-                    format!(
-                        "Did you mean `{}, {}, -1`?",
-                        node.start().to_string().trim(),
-                        node.end().to_string().trim()
-                    ),
+                    suggestion,
                 )),
             );
         }
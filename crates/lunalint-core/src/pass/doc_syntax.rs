@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::{context::Context, impl_lint_pass};
+use full_moon::{ast, visitors::Visitor};
+
+/// Report LuaCATS annotations which cannot be parsed.
+pub struct DocSyntax {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "doc-syntax",
+    DocSyntax,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+impl DocSyntax {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl Visitor for DocSyntax {
+    fn visit_ast(&mut self, _ast: &ast::Ast) {
+        for error in self.ctx().annotations().errors() {
+            emit_report(
+                self,
+                LintReport::new(self, error.loc(), error.msg().to_owned())
+                    .with_label(LintLabel::new(error.loc(), error.label().to_owned())),
+            );
+        }
+    }
+}
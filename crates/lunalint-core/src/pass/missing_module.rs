@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::fix::{Applicability, Fix};
+use crate::pass::undefined_global::{closest_name, levenshtein_distance};
+use crate::{context::Context, impl_lint_pass};
+use full_moon::{ast, visitors::Visitor};
+
+/// Report `require` of modules which are not found by the templates of `runtime.path`.
+/// Modules of the library (e.g. `require("string")`) are not reported.
+pub struct MissingModule {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "missing-module",
+    MissingModule,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+impl MissingModule {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl Visitor for MissingModule {
+    fn visit_ast(&mut self, _ast: &ast::Ast) {
+        let Some(workspace) = self.ctx().workspace() else {
+            return;
+        };
+        let library = self.ctx().config().library();
+        let module_names = workspace.module_names();
+
+        for require in self.ctx().imports().requires() {
+            let name = require.name();
+            if require.path().is_some() || library.global(name).is_some() {
+                continue;
+            }
+
+            let loc = require.loc();
+            let report = LintReport::new(self, loc.clone(), format!("Module `{name}` not found"));
+            // A module is likely misspelled if about one in three characters differ
+            let suggestion =
+                closest_name(name, module_names.iter().map(String::as_str)).filter(|found| {
+                    levenshtein_distance(name, found) <= name.chars().count().div_ceil(3)
+                });
+            let report = match suggestion {
+                Some(suggestion) => {
+                    let (open, close) = delimiters(&self.ctx().src().content()[loc.range()]);
+                    report
+                        .with_label(LintLabel::new(
+                            loc.clone(),
+                            format!("Did you mean `{suggestion}`?"),
+                        ))
+                        .with_fix(Fix::replace(
+                            format!("Replace with `{suggestion}`"),
+                            Applicability::Unsafe,
+                            loc,
+                            format!("{open}{suggestion}{close}"),
+                        ))
+                }
+                None => {
+                    let searched = workspace
+                        .candidates(name)
+                        .iter()
+                        .map(|path| format!("`{}`", path.display()))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    report.with_label(LintLabel::new(loc, format!("Searched {searched}")))
+                }
+            };
+            emit_report(self, report);
+        }
+    }
+}
+
+/// Opening and closing delimiters of a string literal, e.g. `"` or `[==[` and `]==]`.
+fn delimiters(literal: &str) -> (String, String) {
+    match literal.strip_prefix('[') {
+        Some(rest) => {
+            let level = "=".repeat(rest.len() - rest.trim_start_matches('=').len());
+            (format!("[{level}["), format!("]{level}]"))
+        }
+        None => (literal[..1].to_owned(), literal[..1].to_owned()),
+    }
+}
@@ -1,14 +1,38 @@
+mod assign_type_mismatch;
 mod count_down_loop;
+mod doc_syntax;
 mod global_in_nil_env;
 mod lowercase_global;
+mod missing_module;
+mod need_check_nil;
+mod param_type_mismatch;
+mod require_cycle;
+mod undefined_field;
 mod undefined_global;
 mod unicode_name;
+mod unreachable_code;
+mod unsupported_syntax;
+mod unused_function;
+mod unused_local;
+mod unused_suppression;
 
+pub use assign_type_mismatch::AssignTypeMismatch;
 pub use count_down_loop::CountDownLoop;
+pub use doc_syntax::DocSyntax;
 pub use global_in_nil_env::GlobalInNilEnv;
 pub use lowercase_global::LowercaseGlobal;
+pub use missing_module::MissingModule;
+pub use need_check_nil::NeedCheckNil;
+pub use param_type_mismatch::ParamTypeMismatch;
+pub use require_cycle::RequireCycle;
+pub use undefined_field::UndefinedField;
 pub use undefined_global::UndefinedGlobal;
 pub use unicode_name::UnicodeName;
+pub use unreachable_code::UnreachableCode;
+pub use unsupported_syntax::UnsupportedSyntax;
+pub use unused_function::UnusedFunction;
+pub use unused_local::UnusedLocal;
+pub use unused_suppression::UnusedSuppression;
 
 use crate::{
     context::Context,
@@ -27,14 +51,46 @@ impl PassManager {
         Self { passes: Vec::new() }
     }
 
-    /// Create a new pass manager with all passes.
+    /// Create a new pass manager with all passes. Passes disabled by the configuration are skipped.
     pub fn with_all_passes(ctx: Arc<Context>) -> Self {
         let mut pass_manager = Self::new();
         pass_manager.add_pass(Box::new(CountDownLoop::new(Arc::clone(&ctx))));
         pass_manager.add_pass(Box::new(GlobalInNilEnv::new(Arc::clone(&ctx))));
         pass_manager.add_pass(Box::new(UnicodeName::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(UnsupportedSyntax::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(DocSyntax::new(Arc::clone(&ctx))));
         pass_manager.add_pass(Box::new(UndefinedGlobal::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(UndefinedField::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(MissingModule::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(RequireCycle::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(ParamTypeMismatch::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(AssignTypeMismatch::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(NeedCheckNil::new(Arc::clone(&ctx))));
         pass_manager.add_pass(Box::new(LowercaseGlobal::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(UnreachableCode::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(UnusedLocal::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(UnusedFunction::new(Arc::clone(&ctx))));
+        // Must be the last pass to see all suppressed reports
+        pass_manager.add_pass(Box::new(UnusedSuppression::new(Arc::clone(&ctx))));
+        pass_manager
+            .passes
+            .retain(|pass| ctx.config().is_enabled(pass.name()));
+        pass_manager
+    }
+
+    /// Create a new pass manager with passes which only look at the syntax. They do not depend on
+    /// name resolution or control flow, so they can run on an incomplete AST (e.g. the parsable
+    /// prefix of a file with a syntax error).
+    pub fn with_syntactic_passes(ctx: Arc<Context>) -> Self {
+        let mut pass_manager = Self::new();
+        pass_manager.add_pass(Box::new(CountDownLoop::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(GlobalInNilEnv::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(UnicodeName::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(UnsupportedSyntax::new(Arc::clone(&ctx))));
+        pass_manager.add_pass(Box::new(DocSyntax::new(Arc::clone(&ctx))));
+        pass_manager
+            .passes
+            .retain(|pass| ctx.config().is_enabled(pass.name()));
         pass_manager
     }
 
@@ -50,6 +106,12 @@ impl PassManager {
     }
 }
 
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Lint pass which traverses the AST
 pub trait Pass {
     fn ctx(&self) -> &Context;
@@ -0,0 +1,542 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::cfg::{BlockId, Cfg};
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::resolver::NodeId;
+use crate::utils;
+use crate::{context::Context, impl_lint_pass, location::Location};
+use full_moon::tokenizer::TokenType;
+use full_moon::visitors::Visit;
+use full_moon::{ast, visitors::Visitor};
+
+/// Report indexing or calling values which may be `nil`. Variables are narrowed along the
+/// control-flow graph by conditions (`if x then`, `if not x then return end`, `x ~= nil`,
+/// `type(x) == "nil"`), by `assert(x)`, and by assignments of values which are not `nil` (e.g.
+/// `x = x or default`).
+pub struct NeedCheckNil {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "need-check-nil",
+    NeedCheckNil,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+/// Definitions of variables known not to be `nil`
+type NonNil = HashSet<NodeId>;
+
+impl NeedCheckNil {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    /// Narrow variables over the graph of a function, then check its statements.
+    fn check_function(&self, block: &ast::Block, func: NodeId) {
+        let Some(cfg) = self.ctx.control_flow().graph(func) else {
+            return;
+        };
+        let mut nodes = Nodes::default();
+        nodes.collect(block);
+
+        let mut states: HashMap<BlockId, NonNil> = HashMap::new();
+        states.insert(cfg.entry(), NonNil::new());
+        let mut worklist = vec![cfg.entry()];
+        while let Some(id) = worklist.pop() {
+            let mut state = states[&id].clone();
+            self.walk_block(cfg, id, &nodes, &mut state, false);
+            for (succ, state) in self.successor_states(cfg, id, &nodes, state) {
+                // Variables are not `nil` at the start of a block only if they are not `nil` on
+                // every incoming edge
+                let changed = match states.get_mut(&succ) {
+                    Some(prev) => {
+                        let len = prev.len();
+                        prev.retain(|def| state.contains(def));
+                        prev.len() != len
+                    }
+                    None => {
+                        states.insert(succ, state);
+                        true
+                    }
+                };
+                if changed && !worklist.contains(&succ) {
+                    worklist.push(succ);
+                }
+            }
+        }
+
+        let mut blocks = states.into_iter().collect::<Vec<_>>();
+        blocks.sort_by_key(|(id, _)| *id);
+        for (id, mut state) in blocks {
+            self.walk_block(cfg, id, &nodes, &mut state, true);
+        }
+    }
+
+    fn walk_block(&self, cfg: &Cfg, id: BlockId, nodes: &Nodes, state: &mut NonNil, report: bool) {
+        let block = cfg.block(id);
+        for stmt in block.stmts() {
+            match nodes.stmts.get(stmt) {
+                Some(Statement::Stmt(stmt)) => self.stmt(stmt, state, report),
+                Some(Statement::Last(ast::LastStmt::Return(ret))) => {
+                    for expr in ret.returns() {
+                        self.expr(expr, state, report);
+                    }
+                }
+                Some(Statement::Last(_)) | None => {}
+            }
+        }
+        let condition = block
+            .branch()
+            .and_then(|branch| nodes.conditions.get(&branch.condition()));
+        if let Some(condition) = condition {
+            self.expr(condition, state, report);
+        }
+    }
+
+    /// States at the start of successors, narrowed by the condition of the branch.
+    fn successor_states(
+        &self,
+        cfg: &Cfg,
+        id: BlockId,
+        nodes: &Nodes,
+        state: NonNil,
+    ) -> Vec<(BlockId, NonNil)> {
+        let branch = cfg.block(id).branch();
+        let condition = branch.and_then(|branch| nodes.conditions.get(&branch.condition()));
+        cfg.successors(id)
+            .iter()
+            .map(|succ| {
+                let mut state = state.clone();
+                if let (Some(branch), Some(condition)) = (branch, condition) {
+                    for value in [true, false] {
+                        if branch.target(value) == Some(*succ) {
+                            self.narrow(condition, value, &mut state);
+                        }
+                    }
+                }
+                (*succ, state)
+            })
+            .collect()
+    }
+
+    /// Check expressions evaluated by the statement and update the state by assignments.
+    /// Bodies of compound statements are other blocks.
+    fn stmt(&self, stmt: &ast::Stmt, state: &mut NonNil, report: bool) {
+        match stmt {
+            ast::Stmt::LocalAssignment(node) => {
+                for expr in node.expressions() {
+                    self.expr(expr, state, report);
+                }
+                for (i, name) in node.names().iter().enumerate() {
+                    self.assign(NodeId::from(name), node.expressions(), i, state);
+                }
+            }
+            ast::Stmt::Assignment(node) => {
+                for var in node.variables() {
+                    if let ast::Var::Expression(var) = var {
+                        let suffixes = var.suffixes().collect::<Vec<_>>();
+                        self.chain(var.prefix(), &suffixes, state, report);
+                    }
+                }
+                for expr in node.expressions() {
+                    self.expr(expr, state, report);
+                }
+                let resolver = self.ctx.resolver();
+                for (i, var) in node.variables().iter().enumerate() {
+                    if let ast::Var::Name(_) = var {
+                        if let Some(def_id) = resolver.lookup_definiton(NodeId::from(var)) {
+                            self.assign(def_id, node.expressions(), i, state);
+                        }
+                    }
+                }
+            }
+            ast::Stmt::FunctionCall(call) => self.call(call, state, report),
+            ast::Stmt::NumericFor(node) => {
+                self.expr(node.start(), state, report);
+                self.expr(node.end(), state, report);
+                if let Some(step) = node.step() {
+                    self.expr(step, state, report);
+                }
+            }
+            ast::Stmt::GenericFor(node) => {
+                for expr in node.expressions() {
+                    self.expr(expr, state, report);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Update the state by assigning the `i`-th value of the expressions.
+    fn assign(
+        &self,
+        def_id: NodeId,
+        exprs: &ast::punctuated::Punctuated<ast::Expression>,
+        i: usize,
+        state: &mut NonNil,
+    ) {
+        let value = exprs.iter().nth(i);
+        let non_nil = value
+            .and_then(|value| self.ctx.types().get(NodeId::from(value)))
+            .is_some_and(|ty| !ty.may_be_nil());
+        set(state, def_id, non_nil);
+    }
+
+    fn expr(&self, expr: &ast::Expression, state: &mut NonNil, report: bool) {
+        match expr {
+            ast::Expression::BinaryOperator { lhs, binop, rhs } => {
+                self.expr(lhs, state, report);
+                // The right operand is evaluated only if the left operand is truthy (`and`) or
+                // falsy (`or`)
+                let mut rhs_state = state.clone();
+                match binop {
+                    ast::BinOp::And(_) => self.narrow(lhs, true, &mut rhs_state),
+                    ast::BinOp::Or(_) => self.narrow(lhs, false, &mut rhs_state),
+                    _ => {}
+                }
+                self.expr(rhs, &mut rhs_state, report);
+            }
+            ast::Expression::UnaryOperator { expression, .. }
+            | ast::Expression::Parentheses { expression, .. } => {
+                self.expr(expression, state, report)
+            }
+            ast::Expression::FunctionCall(call) => self.call(call, state, report),
+            ast::Expression::Var(ast::Var::Expression(var)) => {
+                let suffixes = var.suffixes().collect::<Vec<_>>();
+                self.chain(var.prefix(), &suffixes, state, report);
+            }
+            ast::Expression::TableConstructor(table) => {
+                for field in table.fields() {
+                    match field {
+                        ast::Field::ExpressionKey { key, value, .. } => {
+                            self.expr(key, state, report);
+                            self.expr(value, state, report);
+                        }
+                        ast::Field::NameKey { value, .. } | ast::Field::NoKey(value) => {
+                            self.expr(value, state, report)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn call(&self, call: &ast::FunctionCall, state: &mut NonNil, report: bool) {
+        let suffixes = call.suffixes().collect::<Vec<_>>();
+        self.chain(call.prefix(), &suffixes, state, report);
+
+        // `assert(x)` returns only if `x` is truthy
+        let ast::Prefix::Name(name) = call.prefix() else {
+            return;
+        };
+        let resolver = self.ctx.resolver();
+        let is_assert = utils::ident_as_str(name) == "assert"
+            && resolver
+                .lookup_definiton(NodeId::from(call.prefix()))
+                .is_some_and(|def_id| resolver.is_builtin(def_id));
+        if let (
+            true,
+            [ast::Suffix::Call(ast::Call::AnonymousCall(ast::FunctionArgs::Parentheses {
+                arguments,
+                ..
+            }))],
+        ) = (is_assert, suffixes.as_slice())
+        {
+            if let Some(arg) = arguments.iter().next() {
+                self.narrow(arg, true, state);
+            }
+        }
+    }
+
+    /// Check indexes and calls of `prefix` followed by `suffixes`. Each suffix indexes or calls
+    /// the value before it.
+    fn chain(
+        &self,
+        prefix: &ast::Prefix,
+        suffixes: &[&ast::Suffix],
+        state: &mut NonNil,
+        report: bool,
+    ) {
+        let types = self.ctx.types();
+        let mut may_be_nil = match prefix {
+            ast::Prefix::Name(_) => {
+                let narrowed = self
+                    .ctx
+                    .resolver()
+                    .lookup_definiton(NodeId::from(prefix))
+                    .is_some_and(|def_id| state.contains(&def_id));
+                !narrowed
+                    && types
+                        .get(NodeId::from(prefix))
+                        .is_some_and(|ty| ty.may_be_nil())
+            }
+            ast::Prefix::Expression(expr) => {
+                self.expr(expr, state, report);
+                types
+                    .get(NodeId::from(expr.as_ref()))
+                    .is_some_and(|ty| ty.may_be_nil())
+            }
+            _ => false,
+        };
+
+        let start = NodeId::from(prefix).range().start;
+        let mut value = NodeId::from(prefix);
+        for suffix in suffixes {
+            if may_be_nil && report {
+                self.report(start, value, suffix);
+            }
+            match suffix {
+                ast::Suffix::Index(ast::Index::Brackets { expression, .. }) => {
+                    self.expr(expression, state, report)
+                }
+                ast::Suffix::Call(ast::Call::AnonymousCall(args)) => self.args(args, state, report),
+                ast::Suffix::Call(ast::Call::MethodCall(method)) => {
+                    self.args(method.args(), state, report)
+                }
+                _ => {}
+            }
+            value = NodeId::from(*suffix);
+            may_be_nil = types.get(value).is_some_and(|ty| ty.may_be_nil());
+        }
+    }
+
+    fn args(&self, args: &ast::FunctionArgs, state: &mut NonNil, report: bool) {
+        match args {
+            ast::FunctionArgs::Parentheses { arguments, .. } => {
+                for arg in arguments {
+                    self.expr(arg, state, report);
+                }
+            }
+            ast::FunctionArgs::TableConstructor(table) => {
+                let table = ast::Expression::TableConstructor(table.clone());
+                self.expr(&table, state, report);
+            }
+            _ => {}
+        }
+    }
+
+    /// Narrow variables assuming the condition is truthy (`value` is true) or falsy.
+    fn narrow(&self, condition: &ast::Expression, value: bool, state: &mut NonNil) {
+        match condition {
+            ast::Expression::Parentheses { expression, .. } => {
+                self.narrow(expression, value, state)
+            }
+            ast::Expression::UnaryOperator {
+                unop: ast::UnOp::Not(_),
+                expression,
+            } => self.narrow(expression, !value, state),
+            ast::Expression::Var(ast::Var::Name(_)) => {
+                if let Some(def_id) = self.variable(condition) {
+                    set(state, def_id, value);
+                }
+            }
+            ast::Expression::BinaryOperator { lhs, binop, rhs } => match binop {
+                ast::BinOp::And(_) if value => {
+                    self.narrow(lhs, true, state);
+                    self.narrow(rhs, true, state);
+                }
+                ast::BinOp::Or(_) if !value => {
+                    self.narrow(lhs, false, state);
+                    self.narrow(rhs, false, state);
+                }
+                // `a and b` is falsy if `a` is falsy, or if `a` is truthy and `b` is falsy
+                ast::BinOp::And(_) | ast::BinOp::Or(_) => {
+                    let is_and = matches!(binop, ast::BinOp::And(_));
+                    let mut short = state.clone();
+                    self.narrow(lhs, value, &mut short);
+                    let mut both = state.clone();
+                    self.narrow(lhs, is_and, &mut both);
+                    self.narrow(rhs, value, &mut both);
+                    *state = short.intersection(&both).copied().collect();
+                }
+                ast::BinOp::TwoEqual(_) | ast::BinOp::TildeEqual(_) => {
+                    let equal = matches!(binop, ast::BinOp::TwoEqual(_)) == value;
+                    self.narrow_comparison(lhs, rhs, equal, state);
+                    self.narrow_comparison(rhs, lhs, equal, state);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Narrow by `x == nil` or `type(x) == "name"`, where `equal` is whether the operands are
+    /// equal.
+    fn narrow_comparison(
+        &self,
+        subject: &ast::Expression,
+        other: &ast::Expression,
+        equal: bool,
+        state: &mut NonNil,
+    ) {
+        if is_nil(other) {
+            if let Some(def_id) = self.variable(subject) {
+                set(state, def_id, !equal);
+            }
+            return;
+        }
+        let (Some(arg), Some(name)) = (self.type_call_arg(subject), string_literal(other)) else {
+            return;
+        };
+        let Some(def_id) = self.variable(arg) else {
+            return;
+        };
+        match (name, equal) {
+            ("nil", _) => set(state, def_id, !equal),
+            (_, true) => set(state, def_id, true),
+            (_, false) => {}
+        }
+    }
+
+    /// Argument of `type(x)`
+    fn type_call_arg<'a>(&self, expr: &'a ast::Expression) -> Option<&'a ast::Expression> {
+        let ast::Expression::FunctionCall(call) = expr else {
+            return None;
+        };
+        let ast::Prefix::Name(name) = call.prefix() else {
+            return None;
+        };
+        let resolver = self.ctx.resolver();
+        let def_id = resolver.lookup_definiton(NodeId::from(call.prefix()))?;
+        if utils::ident_as_str(name) != "type" || !resolver.is_builtin(def_id) {
+            return None;
+        }
+        let mut suffixes = call.suffixes();
+        let (
+            Some(ast::Suffix::Call(ast::Call::AnonymousCall(ast::FunctionArgs::Parentheses {
+                arguments,
+                ..
+            }))),
+            None,
+        ) = (suffixes.next(), suffixes.next())
+        else {
+            return None;
+        };
+        arguments.iter().next()
+    }
+
+    /// Definition of the local or global variable read by the expression.
+    fn variable(&self, expr: &ast::Expression) -> Option<NodeId> {
+        match expr {
+            ast::Expression::Parentheses { expression, .. } => self.variable(expression),
+            ast::Expression::Var(ast::Var::Name(_)) => {
+                let resolver = self.ctx.resolver();
+                let def_id = resolver.lookup_definiton(NodeId::from(expr))?;
+                (!resolver.is_builtin(def_id)).then_some(def_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Report the value from `start` to the end of `value`, which is indexed or called by
+    /// `suffix`.
+    fn report(&self, start: usize, value: NodeId, suffix: &ast::Suffix) {
+        let Some(ty) = self.ctx.types().get(value) else {
+            return;
+        };
+        let loc = Location::from_range(Arc::clone(self.ctx.src()), start..value.range().end);
+        let msg = match suffix {
+            ast::Suffix::Call(ast::Call::AnonymousCall(_)) => "Calling a possibly `nil` value",
+            _ => "Indexing a possibly `nil` value",
+        };
+        emit_report(
+            self,
+            LintReport::new(self, loc.clone(), msg.to_owned())
+                .with_label(LintLabel::new(loc, format!("This value is `{ty}`"))),
+        );
+    }
+}
+
+impl Visitor for NeedCheckNil {
+    fn visit_ast(&mut self, ast: &ast::Ast) {
+        self.check_function(ast.nodes(), NodeId::from(ast));
+        ast.nodes().visit(self);
+        ast.eof().visit(self);
+    }
+
+    fn visit_function_body(&mut self, node: &ast::FunctionBody) {
+        self.check_function(node.block(), NodeId::from(node));
+    }
+}
+
+fn set(state: &mut NonNil, def_id: NodeId, non_nil: bool) {
+    if non_nil {
+        state.insert(def_id);
+    } else {
+        state.remove(&def_id);
+    }
+}
+
+fn is_nil(expr: &ast::Expression) -> bool {
+    match expr {
+        ast::Expression::Parentheses { expression, .. } => is_nil(expression),
+        ast::Expression::Symbol(token) => token.token().to_string() == "nil",
+        _ => false,
+    }
+}
+
+fn string_literal(expr: &ast::Expression) -> Option<&str> {
+    let ast::Expression::String(token) = expr else {
+        return None;
+    };
+    match token.token_type() {
+        TokenType::StringLiteral { literal, .. } => Some(literal.as_str()),
+        _ => None,
+    }
+}
+
+enum Statement<'a> {
+    Stmt(&'a ast::Stmt),
+    Last(&'a ast::LastStmt),
+}
+
+/// Statements and conditions of a function by their nodes. Nested functions are not included.
+#[derive(Default)]
+struct Nodes<'a> {
+    stmts: HashMap<NodeId, Statement<'a>>,
+    conditions: HashMap<NodeId, &'a ast::Expression>,
+}
+
+impl<'a> Nodes<'a> {
+    fn collect(&mut self, block: &'a ast::Block) {
+        for stmt in block.stmts() {
+            self.stmts.insert(NodeId::from(stmt), Statement::Stmt(stmt));
+            match stmt {
+                ast::Stmt::Do(node) => self.collect(node.block()),
+                ast::Stmt::If(node) => {
+                    self.condition(node.condition());
+                    self.collect(node.block());
+                    for else_if in node.else_if().into_iter().flatten() {
+                        self.condition(else_if.condition());
+                        self.collect(else_if.block());
+                    }
+                    if let Some(block) = node.else_block() {
+                        self.collect(block);
+                    }
+                }
+                ast::Stmt::While(node) => {
+                    self.condition(node.condition());
+                    self.collect(node.block());
+                }
+                ast::Stmt::Repeat(node) => {
+                    self.collect(node.block());
+                    self.condition(node.until());
+                }
+                ast::Stmt::NumericFor(node) => self.collect(node.block()),
+                ast::Stmt::GenericFor(node) => self.collect(node.block()),
+                _ => {}
+            }
+        }
+        if let Some(last_stmt) = block.last_stmt() {
+            self.stmts
+                .insert(NodeId::from(last_stmt), Statement::Last(last_stmt));
+        }
+    }
+
+    fn condition(&mut self, condition: &'a ast::Expression) {
+        self.conditions.insert(NodeId::from(condition), condition);
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::resolver::NodeId;
+use crate::types::{LuaType, Param};
+use crate::{context::Context, impl_lint_pass, location::Location};
+use full_moon::{ast, node::Node, visitors::Visitor};
+
+/// Report arguments whose types cannot be assigned to parameters declared by `---@param`.
+pub struct ParamTypeMismatch {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "param-type-mismatch",
+    ParamTypeMismatch,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+impl ParamTypeMismatch {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    fn check(&self, arg: &dyn Node, arg_ty: &LuaType, param: &Param) {
+        let types = self.ctx.types();
+        let Some(declaration) = param.id.and_then(|id| types.declaration(id)) else {
+            return;
+        };
+        if types.is_assignable(arg_ty, &param.ty) {
+            return;
+        }
+        let loc = Location::from((self.ctx.src(), arg.tokens()));
+        emit_report(
+            self,
+            LintReport::new(
+                self,
+                loc.clone(),
+                format!("Cannot assign `{arg_ty}` to parameter `{}`", param.ty),
+            )
+            .with_label(LintLabel::new(loc, format!("This argument is `{arg_ty}`")))
+            .with_label(LintLabel::new(
+                declaration.clone(),
+                format!(
+                    "Parameter `{}` is declared as `{}` here",
+                    param.name, param.ty
+                ),
+            )),
+        );
+    }
+}
+
+impl Visitor for ParamTypeMismatch {
+    fn visit_function_call(&mut self, node: &ast::FunctionCall) {
+        let types = self.ctx.types();
+        let Some(LuaType::Function(signature)) = types.callee(NodeId::from(node)) else {
+            return;
+        };
+        let Some(ast::Suffix::Call(call)) = node.suffixes().last() else {
+            return;
+        };
+        // The receiver of a method call is the first argument
+        let (args, receiver) = match call {
+            ast::Call::AnonymousCall(args) => (args, 0),
+            ast::Call::MethodCall(method) => (method.args(), 1),
+            _ => return,
+        };
+        let mut params = signature.params.iter().skip(receiver);
+        match args {
+            ast::FunctionArgs::Parentheses { arguments, .. } => {
+                for (arg, param) in arguments.iter().zip(params) {
+                    let Some(arg_ty) = types.get(NodeId::from(arg)) else {
+                        continue;
+                    };
+                    self.check(arg, arg_ty, param);
+                }
+            }
+            ast::FunctionArgs::String(token) => {
+                if let Some(param) = params.next() {
+                    self.check(token, &LuaType::String, param);
+                }
+            }
+            _ => {}
+        }
+    }
+}
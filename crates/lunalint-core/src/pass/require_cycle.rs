@@ -0,0 +1,55 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::{context::Context, impl_lint_pass};
+use full_moon::{ast, visitors::Visitor};
+
+/// Report `require` of modules which require the file back, directly or through other modules.
+/// Labels follow the cycle through the files.
+pub struct RequireCycle {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "require-cycle",
+    RequireCycle,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+impl RequireCycle {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl Visitor for RequireCycle {
+    fn visit_ast(&mut self, _ast: &ast::Ast) {
+        let Some(workspace) = self.ctx().workspace() else {
+            return;
+        };
+        let path = Path::new(self.ctx().src().path());
+
+        for require in self.ctx().imports().requires() {
+            let Some(cycle) = require
+                .path()
+                .and_then(|module| workspace.cycle(path, module))
+            else {
+                continue;
+            };
+
+            let name = require.name();
+            let loc = require.loc();
+            let mut report =
+                LintReport::new(self, loc.clone(), format!("Cyclic `require` of `{name}`"))
+                    .with_label(LintLabel::new(loc, format!("Requires `{name}`")));
+            for require in cycle {
+                report = report.with_label(LintLabel::new(
+                    require.loc(),
+                    format!("Requires `{}`", require.name()),
+                ));
+            }
+            emit_report(self, report);
+        }
+    }
+}
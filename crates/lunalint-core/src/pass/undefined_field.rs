@@ -0,0 +1,368 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::fix::{Applicability, Fix};
+use crate::library::Member;
+use crate::pass::undefined_global::{closest_name, levenshtein_distance};
+use crate::resolver::NodeId;
+use crate::utils;
+use crate::{context::Context, impl_lint_pass, location::Location};
+use full_moon::tokenizer::{TokenReference, TokenType};
+use full_moon::visitors::Visit;
+use full_moon::{ast, visitors::Visitor};
+
+/// Report accesses to fields which do not exist in tables of the library, or in local tables whose
+/// fields are all visible in the file or in the module returning them.
+pub struct UndefinedField {
+    ctx: Arc<Context>,
+    fields: Fields,
+    /// Variables assigned by assignment statements
+    targets: HashSet<NodeId>,
+}
+impl_lint_pass!(
+    "undefined-field",
+    UndefinedField,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+impl UndefinedField {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self {
+            ctx,
+            fields: Fields::default(),
+            targets: HashSet::new(),
+        }
+    }
+
+    /// Check field accesses of `prefix.a.b:c()`. If `is_target` is true, the last field is
+    /// assigned rather than read.
+    fn check(&self, prefix: &ast::Prefix, suffixes: &[&ast::Suffix], is_target: bool) {
+        let fields = suffixes
+            .iter()
+            .take(suffixes.len() - usize::from(is_target))
+            .map_while(|suffix| field_name(suffix))
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            return;
+        }
+
+        match prefix {
+            ast::Prefix::Name(name) => {
+                let resolver = self.ctx.resolver();
+                let Some(def_id) = resolver.lookup_definiton(NodeId::from(prefix)) else {
+                    return;
+                };
+                let name = utils::ident_as_str(name);
+                if resolver.is_builtin(def_id) {
+                    let added = self.fields.library.get(&def_id);
+                    if added.is_some_and(|added| added.contains(utils::ident_as_str(fields[0]))) {
+                        return;
+                    }
+                    if let Some(member) = self.ctx.config().library().global(name) {
+                        self.check_member(member, name.to_owned(), &fields);
+                    }
+                } else if let Some(shape) = self.fields.locals.get(&def_id) {
+                    let field = fields[0];
+                    let field_name = utils::ident_as_str(field);
+                    if !shape.contains(field_name) {
+                        let candidates = shape.iter().map(String::as_str);
+                        self.report(field, name, candidates);
+                    }
+                }
+            }
+            // Methods of string literals (e.g. `("%d"):format(1)`)
+            ast::Prefix::Expression(expr) if is_string_literal(expr) => {
+                let Some(string) = self.ctx.config().library().global("string") else {
+                    return;
+                };
+                if let Some(ast::Suffix::Call(ast::Call::MethodCall(method))) = suffixes.first() {
+                    self.check_member(string, "string".to_owned(), &[method.name()]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check fields of a table of the library one by one. Fields of other members and open tables
+    /// are unknown.
+    fn check_member(&self, mut member: &Member, mut path: String, fields: &[&TokenReference]) {
+        for field in fields {
+            if !matches!(member, Member::Table { .. }) {
+                return;
+            }
+            let name = utils::ident_as_str(field);
+            match member.field(name) {
+                Some(field_member) => {
+                    member = field_member;
+                    path = format!("{path}.{name}");
+                }
+                None => {
+                    if member.is_closed_table() {
+                        self.report(field, &path, member.fields().map(|(name, _)| name));
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    fn report<'a>(
+        &self,
+        field: &TokenReference,
+        table: &str,
+        candidates: impl IntoIterator<Item = &'a str>,
+    ) {
+        let name = utils::ident_as_str(field);
+        let loc = Location::from((self.ctx.src(), field));
+        let mut report = LintReport::new(
+            self,
+            loc.clone(),
+            format!("Undefined field `{name}` of `{table}`"),
+        );
+        let candidates = candidates.into_iter().collect::<Vec<_>>();
+        // A field is likely misspelled if about one in three characters differ
+        let suggestion = closest_name(name, candidates.iter().copied())
+            .filter(|found| levenshtein_distance(name, found) <= name.chars().count().div_ceil(3));
+        report = match suggestion {
+            Some(suggestion) => report
+                .with_label(LintLabel::new(
+                    loc.clone(),
+                    format!("Did you mean `{suggestion}`?"),
+                ))
+                .with_fix(Fix::replace(
+                    format!("Replace with `{suggestion}`"),
+                    Applicability::Unsafe,
+                    loc,
+                    suggestion.to_owned(),
+                )),
+            None if candidates.is_empty() => {
+                report.with_label(LintLabel::new(loc, format!("`{table}` has no fields")))
+            }
+            None => report.with_label(LintLabel::new(
+                loc,
+                format!("Similar field not found in `{table}`"),
+            )),
+        };
+        emit_report(self, report);
+    }
+}
+
+impl Visitor for UndefinedField {
+    fn visit_ast(&mut self, ast: &ast::Ast) {
+        self.fields = Shapes::collect(&self.ctx, ast);
+        ast.nodes().visit(self);
+        ast.eof().visit(self);
+    }
+
+    fn visit_assignment(&mut self, node: &ast::Assignment) {
+        self.targets
+            .extend(node.variables().iter().map(|var| NodeId::from(var)));
+    }
+
+    fn visit_var(&mut self, node: &ast::Var) {
+        let ast::Var::Expression(var) = node else {
+            return;
+        };
+        let suffixes = var.suffixes().collect::<Vec<_>>();
+        let is_target = self.targets.contains(&NodeId::from(node));
+        self.check(var.prefix(), &suffixes, is_target);
+    }
+
+    fn visit_function_call(&mut self, node: &ast::FunctionCall) {
+        let suffixes = node.suffixes().collect::<Vec<_>>();
+        self.check(node.prefix(), &suffixes, false);
+    }
+}
+
+/// Name of the field accessed by the suffix (`.name` or `:name()`).
+fn field_name(suffix: &ast::Suffix) -> Option<&TokenReference> {
+    match suffix {
+        ast::Suffix::Index(ast::Index::Dot { name, .. }) => Some(name),
+        ast::Suffix::Call(ast::Call::MethodCall(method)) => Some(method.name()),
+        _ => None,
+    }
+}
+
+fn is_string_literal(expr: &ast::Expression) -> bool {
+    match expr {
+        ast::Expression::Parentheses { expression, .. } => is_string_literal(expression),
+        ast::Expression::String(_) => true,
+        _ => false,
+    }
+}
+
+/// Collect fields of local tables whose fields are all visible. Such a table is created by a table
+/// constructor with static keys or loaded from a module whose fields are all visible, never
+/// reassigned, and only used to access fields by names
+/// (`t.name` or `t:name()`). Fields assigned by `t.name = value` or declared by
+/// `function t.name() end` are fields of the table as well.
+struct Shapes<'a> {
+    ctx: &'a Context,
+    /// Fields of tables created by table constructors or loaded from modules
+    constructors: HashMap<NodeId, BTreeSet<String>>,
+    /// Uses of variables which access fields by names
+    field_accesses: HashSet<NodeId>,
+    /// Fields assigned to local variables by definitions of the variables
+    assigned: HashMap<NodeId, BTreeSet<String>>,
+    /// Fields declared by `function name.field() end` or assigned to tables of the library, by
+    /// their definitions. Fields of local tables are found by the resolver.
+    declared: HashMap<NodeId, BTreeSet<String>>,
+}
+
+/// Fields of tables collected by [Shapes]
+#[derive(Default)]
+struct Fields {
+    /// Fields of local tables whose fields are all visible, by their definitions
+    locals: HashMap<NodeId, BTreeSet<String>>,
+    /// Fields added to tables of the library, by their definitions
+    library: HashMap<NodeId, BTreeSet<String>>,
+}
+
+impl<'a> Shapes<'a> {
+    fn collect(ctx: &'a Context, ast: &ast::Ast) -> Fields {
+        let mut shapes = Shapes {
+            ctx,
+            constructors: HashMap::new(),
+            field_accesses: HashSet::new(),
+            assigned: HashMap::new(),
+            declared: HashMap::new(),
+        };
+        ast.nodes().visit(&mut shapes);
+
+        let resolver = ctx.resolver();
+        let mut locals = HashMap::new();
+        for (def_id, mut fields) in shapes.constructors {
+            let static_uses = resolver
+                .uses(def_id)
+                .iter()
+                .all(|use_| shapes.field_accesses.contains(use_));
+            if !static_uses || !resolver.reassignments(def_id).is_empty() {
+                continue;
+            }
+            fields.extend(shapes.assigned.remove(&def_id).unwrap_or_default());
+            if let Some(def) = resolver.get_definition(def_id) {
+                fields.extend(
+                    def.fields()
+                        .iter()
+                        .filter_map(|field| field.path().first().cloned()),
+                );
+            }
+            locals.insert(def_id, fields);
+        }
+        Fields {
+            locals,
+            library: shapes.declared,
+        }
+    }
+
+    fn visit_access(&mut self, prefix: &ast::Prefix, suffixes: &[&ast::Suffix], is_target: bool) {
+        let Some(field) = suffixes.first().and_then(|suffix| field_name(suffix)) else {
+            return;
+        };
+        let use_ = NodeId::from(prefix);
+        self.field_accesses.insert(use_);
+        if is_target && suffixes.len() == 1 {
+            let resolver = self.ctx.resolver();
+            let field = utils::ident_as_str(field).to_owned();
+            match (prefix, resolver.lookup_definiton(use_)) {
+                (ast::Prefix::Name(_), Some(def_id)) if resolver.is_builtin(def_id) => {
+                    self.declared.entry(def_id).or_default().insert(field);
+                }
+                (_, Some(def_id)) => {
+                    self.assigned.entry(def_id).or_default().insert(field);
+                }
+                (_, None) => {}
+            }
+        }
+    }
+}
+
+impl Visitor for Shapes<'_> {
+    fn visit_local_assignment(&mut self, node: &ast::LocalAssignment) {
+        for (name, expr) in node.names().iter().zip(node.expressions().iter()) {
+            let fields = match expr {
+                ast::Expression::TableConstructor(table) => static_fields(table),
+                // Tables returned by modules of the workspace
+                ast::Expression::FunctionCall(call) => self
+                    .ctx
+                    .imports()
+                    .module(NodeId::from(call))
+                    .and_then(|module| module.fields())
+                    .cloned(),
+                _ => None,
+            };
+            if let Some(fields) = fields {
+                self.constructors.insert(NodeId::from(name), fields);
+            }
+        }
+    }
+
+    fn visit_assignment(&mut self, node: &ast::Assignment) {
+        for var in node.variables() {
+            if let ast::Var::Expression(var) = var {
+                let suffixes = var.suffixes().collect::<Vec<_>>();
+                self.visit_access(var.prefix(), &suffixes, true);
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, node: &ast::Expression) {
+        if let ast::Expression::Var(ast::Var::Expression(var)) = node {
+            let suffixes = var.suffixes().collect::<Vec<_>>();
+            self.visit_access(var.prefix(), &suffixes, false);
+        }
+    }
+
+    fn visit_function_call(&mut self, node: &ast::FunctionCall) {
+        let suffixes = node.suffixes().collect::<Vec<_>>();
+        self.visit_access(node.prefix(), &suffixes, false);
+    }
+
+    fn visit_function_declaration(&mut self, node: &ast::FunctionDeclaration) {
+        let name = node.name();
+        let mut names = name.names().iter();
+        let Some(table) = names.next() else {
+            return;
+        };
+        if let Some(field) = names.next().or(name.method_name()) {
+            // Declaring a function is a static access to a field of the table
+            let use_ = NodeId::from(table);
+            self.field_accesses.insert(use_);
+            let resolver = self.ctx.resolver();
+            if let Some(def_id) = resolver.lookup_definiton(use_) {
+                if resolver.is_builtin(def_id) {
+                    self.declared
+                        .entry(def_id)
+                        .or_default()
+                        .insert(utils::ident_as_str(field).to_owned());
+                }
+            }
+        }
+    }
+}
+
+/// Names of fields of the table constructor. `None` if a key is not a name or a string literal.
+fn static_fields(table: &ast::TableConstructor) -> Option<BTreeSet<String>> {
+    let mut fields = BTreeSet::new();
+    for field in table.fields() {
+        match field {
+            ast::Field::NameKey { key, .. } => {
+                fields.insert(utils::ident_as_str(key).to_owned());
+            }
+            ast::Field::ExpressionKey {
+                key: ast::Expression::String(key),
+                ..
+            } => {
+                let TokenType::StringLiteral { literal, .. } = key.token_type() else {
+                    return None;
+                };
+                fields.insert(literal.to_string());
+            }
+            ast::Field::NoKey(_) => {}
+            _ => return None,
+        }
+    }
+    Some(fields)
+}
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
+use crate::config::RuntimeVersion;
 use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::fix::{Applicability, Fix};
+use crate::library;
 use crate::resolver::NodeId;
 use crate::utils;
 use crate::{context::Context, impl_lint_pass, location::Location};
@@ -39,14 +42,27 @@ fn check_name(pass: &UndefinedGlobal, name: &str, use_: NodeId, loc: Location) {
         // release lock
     }
 
+    let versions = library::versions_of(name);
+    if !versions.is_empty() {
+        emit_report(pass, unavailable_builtin(pass, name, &versions, loc));
+        return;
+    }
+
     let mut report = LintReport::new(pass, loc.clone(), format!("Undefined global `{name}`"));
 
     let current_block = pass.current_block.last().unwrap();
     if let Some(suggestion) = get_wrong_name_suggestion(pass.ctx(), *current_block, name) {
-        report = report.with_label(LintLabel::new(
-            loc,
-            format!("Did you mean `{}`?", suggestion),
-        ));
+        report = report
+            .with_label(LintLabel::new(
+                loc.clone(),
+                format!("Did you mean `{}`?", suggestion),
+            ))
+            .with_fix(Fix::replace(
+                format!("Replace with `{}`", suggestion),
+                Applicability::Unsafe,
+                loc,
+                suggestion,
+            ));
     } else {
         report = report.with_label(LintLabel::new(
             loc,
@@ -57,6 +73,45 @@ fn check_name(pass: &UndefinedGlobal, name: &str, use_: NodeId, loc: Location) {
     emit_report(pass, report);
 }
 
+/// Replacements of builtins removed in Lua 5.2
+const REPLACEMENTS: [(&str, &str); 2] = [("unpack", "table.unpack"), ("loadstring", "load")];
+
+/// Report a builtin of other runtime versions.
+fn unavailable_builtin(
+    pass: &UndefinedGlobal,
+    name: &str,
+    versions: &[RuntimeVersion],
+    loc: Location,
+) -> LintReport {
+    let version = pass.ctx().config().runtime_version();
+    let mut versions = versions.iter().map(|v| v.as_str()).collect::<Vec<_>>();
+    let last = versions.pop().unwrap_or_default();
+    let versions = if versions.is_empty() {
+        last.to_owned()
+    } else {
+        format!("{} and {last}", versions.join(", "))
+    };
+    let report = LintReport::new(
+        pass,
+        loc.clone(),
+        format!("`{name}` is not available in {}", version.as_str()),
+    )
+    .with_label(LintLabel::new(
+        loc.clone(),
+        format!("`{name}` is only available in {versions}"),
+    ));
+
+    match REPLACEMENTS.iter().find(|(removed, _)| *removed == name) {
+        Some((_, replacement)) => report.with_fix(Fix::replace(
+            format!("Replace with `{replacement}`"),
+            Applicability::Unsafe,
+            loc,
+            replacement.to_string(),
+        )),
+        None => report,
+    }
+}
+
 impl Visitor for UndefinedGlobal {
     fn visit_block(&mut self, node: &ast::Block) {
         let node_id = NodeId::from(node);
@@ -77,6 +132,20 @@ impl Visitor for UndefinedGlobal {
         check_name(self, name, node_id, loc);
     }
 
+    // The table of `function t.f() end` or `function t:m() end`
+    fn visit_function_declaration(&mut self, node: &ast::FunctionDeclaration) {
+        let name = node.name();
+        let Some(table) = name.names().iter().next() else {
+            return;
+        };
+        if name.names().len() == 1 && name.method_name().is_none() {
+            return;
+        }
+        let node_id = NodeId::from(table);
+        let loc = Location::from((self.ctx().src(), table));
+        check_name(self, utils::ident_as_str(table), node_id, loc);
+    }
+
     fn visit_prefix(&mut self, prefix: &ast::Prefix) {
         let ast::Prefix::Name(name) = prefix else {
             return;
@@ -88,31 +157,40 @@ impl Visitor for UndefinedGlobal {
     }
 }
 
-fn get_wrong_name_suggestion<'a>(
-    ctx: &'a Context,
-    current_block: NodeId,
-    name: &str,
-) -> Option<String> {
-    let mut min_distance = usize::MAX;
-    let mut suggestion = None;
+fn get_wrong_name_suggestion(ctx: &Context, current_block: NodeId, name: &str) -> Option<String> {
+    let mut candidates = Vec::new();
     for scope in ctx.resolver().lookup_scope(current_block).unwrap() {
         let scope = scope.lock();
-        for found in scope.keys() {
-            if found == name {
-                // Use before definiton
-                continue;
-            }
-            let distance = levenshtein_distance(name, found);
-            if distance < min_distance {
-                min_distance = distance;
-                suggestion = Some(found.to_string());
-            }
+        // Exclude the name itself, which is used before its definiton
+        candidates.extend(scope.keys().filter(|found| *found != name).cloned());
+    }
+    closest_name(name, candidates.iter().map(String::as_str)).map(str::to_owned)
+}
+
+/// Find the candidate most similar to `name`. Candidates are ranked by edit distance, then by the
+/// length of the common prefix, then alphabetically so that the result does not depend on the
+/// order of candidates.
+pub(super) fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let mut best: Option<(usize, std::cmp::Reverse<usize>, &str)> = None;
+    for found in candidates {
+        let distance = levenshtein_distance(name, found);
+        let prefix = name
+            .chars()
+            .zip(found.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let candidate = (distance, std::cmp::Reverse(prefix), found);
+        if best.as_ref().is_none_or(|best| candidate < *best) {
+            best = Some(candidate);
         }
     }
-    suggestion
+    best.map(|(_, _, found)| found)
 }
 
-fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+pub(super) fn levenshtein_distance(s1: &str, s2: &str) -> usize {
     let len1 = s1.chars().count();
     let len2 = s2.chars().count();
     let mut cost: Vec<usize> = (0..=len1).collect();
@@ -34,7 +34,7 @@ fn check_name(pass: &UnicodeName, ident_tok: &TokenReference) {
     };
     let name = identifier.as_str();
     let loc = Location::from((pass.ctx().src(), ident_tok));
-    if !name.chars().all(|c| c.is_ascii()) {
+    if !name.is_ascii() {
         emit_report(
             pass,
             LintReport::new(pass, loc.clone(), format!("Unicode name `{name}`")).with_label(
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use crate::cfg::TerminatorKind;
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport, LintTag};
+use crate::resolver::NodeId;
+use crate::{context::Context, impl_lint_pass, location::Location};
+use full_moon::{ast, node::Node, visitors::Visitor};
+
+pub struct UnreachableCode {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "unreachable-code",
+    UnreachableCode,
+    LintKind::Diagnostics,
+    LintLevel::Hint
+);
+
+impl UnreachableCode {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    fn is_reachable(&self, node: &dyn Node) -> bool {
+        self.ctx.control_flow().is_reachable(NodeId::from(node))
+    }
+
+    /// Label the statement after which the code becomes unreachable.
+    fn cause(&self, prev: &dyn Node, prev_stmt: Option<&ast::Stmt>) -> LintLabel {
+        let src = self.ctx.src();
+        let id = NodeId::from(prev);
+        let terminator = self.ctx.control_flow().graph_of(id).and_then(|cfg| {
+            cfg.block_of(id)
+                .and_then(|block| cfg.block(block).terminator())
+        });
+        if let Some(terminator) = terminator {
+            let msg = match terminator.kind() {
+                TerminatorKind::Return => "`return` leaves the function here",
+                TerminatorKind::Break => "`break` leaves the loop here",
+                TerminatorKind::Goto => "`goto` jumps away here",
+                TerminatorKind::Error => "`error` never returns",
+            };
+            return LintLabel::new(terminator.loc(), msg.to_owned());
+        }
+        match prev_stmt {
+            Some(ast::Stmt::While(node)) => LintLabel::new(
+                Location::from((src, node.while_token()))
+                    + Location::from((src, node.condition().tokens())),
+                "This loop never exits".to_owned(),
+            ),
+            Some(ast::Stmt::Repeat(node)) => LintLabel::new(
+                Location::from((src, node.until_token()))
+                    + Location::from((src, node.until().tokens())),
+                "This loop never exits".to_owned(),
+            ),
+            Some(ast::Stmt::If(node)) => LintLabel::new(
+                Location::from((src, node.if_token()))
+                    + Location::from((src, node.condition().tokens())),
+                "Every branch of this `if` exits".to_owned(),
+            ),
+            _ => LintLabel::new(
+                Location::from((src, prev.tokens())),
+                "Every path through this statement exits".to_owned(),
+            ),
+        }
+    }
+}
+
+impl Visitor for UnreachableCode {
+    fn visit_block(&mut self, node: &ast::Block) {
+        let stmts = node
+            .stmts()
+            .map(|stmt| (stmt as &dyn Node, Some(stmt)))
+            .chain(node.last_stmt().map(|stmt| (stmt as &dyn Node, None)))
+            .collect::<Vec<_>>();
+
+        // Report each run of unreachable statements once. Statements in nested blocks of
+        // unreachable code are unreachable as well and not reported again.
+        let mut i = 1;
+        while i < stmts.len() {
+            let (prev, prev_stmt) = stmts[i - 1];
+            if self.is_reachable(stmts[i].0) || !self.is_reachable(prev) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            // A label can make the rest of the block reachable again
+            while i + 1 < stmts.len() && !self.is_reachable(stmts[i + 1].0) {
+                i += 1;
+            }
+
+            let src = self.ctx.src();
+            let loc = Location::from((src, stmts[start].0.tokens()))
+                + Location::from((src, stmts[i].0.tokens()));
+            emit_report(
+                self,
+                LintReport::new(self, loc.clone(), "Unreachable code".to_owned())
+                    .with_label(LintLabel::new(
+                        loc,
+                        "This code is never executed".to_owned(),
+                    ))
+                    .with_label(self.cause(prev, prev_stmt))
+                    .with_tag(LintTag::Unnecessary),
+            );
+            i += 1;
+        }
+    }
+}
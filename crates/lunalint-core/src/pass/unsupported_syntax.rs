@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use crate::{
+    context::Context,
+    diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport},
+    impl_lint_pass,
+    location::Location,
+    utils,
+};
+use full_moon::{ast, node::Node, tokenizer::TokenReference, visitors::Visitor};
+
+/// Report syntax which is not supported by the configured runtime version.
+pub struct UnsupportedSyntax {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "unsupported-syntax",
+    UnsupportedSyntax,
+    LintKind::SyntaxError,
+    LintLevel::Error
+);
+
+impl UnsupportedSyntax {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    fn report(&self, loc: Location, syntax: String, since: &str) {
+        let version = self.ctx.config().runtime_version();
+        emit_report(
+            self,
+            LintReport::new(
+                self,
+                loc.clone(),
+                format!("{syntax} is not supported in {}", version.as_str()),
+            )
+            .with_label(LintLabel::new(loc, format!("Introduced in {since}"))),
+        );
+    }
+
+    fn check_operator(&self, token: &TokenReference) {
+        if self.ctx.config().runtime_version().has_integer_operators() {
+            return;
+        }
+        let loc = Location::from((self.ctx.src(), token));
+        self.report(loc, format!("Operator `{}`", token.token()), "Lua 5.3");
+    }
+}
+
+impl Visitor for UnsupportedSyntax {
+    fn visit_goto(&mut self, node: &ast::lua52::Goto) {
+        if self.ctx.config().runtime_version().has_goto() {
+            return;
+        }
+        let loc = Location::from((self.ctx.src(), node.goto_token()));
+        self.report(loc, "`goto`".to_owned(), "Lua 5.2");
+    }
+
+    fn visit_label(&mut self, node: &ast::lua52::Label) {
+        if self.ctx.config().runtime_version().has_goto() {
+            return;
+        }
+        let loc = Location::from((self.ctx.src(), node.tokens()));
+        self.report(loc, "Label".to_owned(), "Lua 5.2");
+    }
+
+    fn visit_expression(&mut self, node: &ast::Expression) {
+        match node {
+            ast::Expression::BinaryOperator {
+                binop:
+                    ast::BinOp::DoubleSlash(token)
+                    | ast::BinOp::Ampersand(token)
+                    | ast::BinOp::Pipe(token)
+                    | ast::BinOp::Tilde(token)
+                    | ast::BinOp::DoubleLessThan(token)
+                    | ast::BinOp::DoubleGreaterThan(token),
+                ..
+            }
+            | ast::Expression::UnaryOperator {
+                unop: ast::UnOp::Tilde(token),
+                ..
+            } => self.check_operator(token),
+            _ => {}
+        }
+    }
+
+    fn visit_attribute(&mut self, node: &ast::lua54::Attribute) {
+        if self.ctx.config().runtime_version().has_attributes() {
+            return;
+        }
+        let loc = Location::from((self.ctx.src(), node.tokens()));
+        let name = utils::ident_as_str(node.name());
+        self.report(loc, format!("Attribute `<{name}>`"), "Lua 5.4");
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport, LintTag};
+use crate::fix::{removal_range, Applicability, Fix, TextEdit};
+use crate::resolver::{DefinitionKind, Visibility};
+use crate::{context::Context, impl_lint_pass, location::Location};
+use full_moon::{ast, visitors::Visitor};
+
+/// Report local functions which are never referenced.
+pub struct UnusedFunction {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "unused-function",
+    UnusedFunction,
+    LintKind::Diagnostics,
+    LintLevel::Hint
+);
+
+impl UnusedFunction {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl Visitor for UnusedFunction {
+    fn visit_ast(&mut self, _ast: &ast::Ast) {
+        let resolver = self.ctx().resolver();
+        let mut defs = resolver
+            .definitions()
+            .filter(|(_, def)| {
+                def.visibility() == Visibility::Local && def.kind() == DefinitionKind::Function
+            })
+            .filter(|(_, def)| !def.name().starts_with('_'))
+            // The definition of a local function spans the whole statement. Recursive calls do not
+            // make the function used.
+            .filter(|(def_id, _)| {
+                let range = def_id.range();
+                resolver.uses(*def_id).iter().all(|use_id| {
+                    let use_range = use_id.range();
+                    range.start <= use_range.start && use_range.end <= range.end
+                })
+            })
+            .collect::<Vec<_>>();
+        defs.sort_by_key(|(_, def)| def.loc().range().start);
+
+        for (def_id, def) in defs {
+            let name = def.name();
+            let src = self.ctx().src();
+            let stmt = Location::from_range(Arc::clone(src), def_id.range());
+            emit_report(
+                self,
+                LintReport::new(self, def.loc(), format!("Unused function `{name}`"))
+                    .with_label(LintLabel::new(
+                        def.loc(),
+                        format!("`{name}` is never called"),
+                    ))
+                    .with_fix(Fix::new(
+                        format!("Remove function `{name}`"),
+                        Applicability::Safe,
+                        vec![TextEdit::delete(removal_range(src, &stmt))],
+                    ))
+                    .with_tag(LintTag::Unnecessary),
+            );
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport, LintTag};
+use crate::fix::{Applicability, Fix, TextEdit};
+use crate::rename;
+use crate::resolver::{DefinitionKind, Visibility};
+use crate::{context::Context, impl_lint_pass, location::Location};
+use full_moon::{ast, visitors::Visitor};
+
+/// Report local variables, parameters and loop variables which are never read.
+pub struct UnusedLocal {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "unused-local",
+    UnusedLocal,
+    LintKind::Diagnostics,
+    LintLevel::Hint
+);
+
+impl UnusedLocal {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl Visitor for UnusedLocal {
+    fn visit_ast(&mut self, _ast: &ast::Ast) {
+        let resolver = self.ctx().resolver();
+        let mut defs = resolver
+            .definitions()
+            .filter(|(_, def)| {
+                def.visibility() == Visibility::Local && def.kind() != DefinitionKind::Function
+            })
+            // `_` is the convention for intentionally unused variables
+            .filter(|(_, def)| !def.name().starts_with('_') && def.name() != "self")
+            .filter(|(def_id, _)| resolver.uses(*def_id).is_empty())
+            .collect::<Vec<_>>();
+        defs.sort_by_key(|(_, def)| def.loc().range().start);
+
+        for (def_id, def) in defs {
+            let name = def.name();
+            let reassignments = resolver.reassignments(def_id);
+            let label = match def.kind() {
+                DefinitionKind::Parameter => format!("Parameter `{name}` is never used"),
+                _ if !reassignments.is_empty() => format!("`{name}` is assigned but never read"),
+                _ => format!("`{name}` is never used"),
+            };
+
+            // Rename assignments as well so that they do not create a global
+            let new_name = format!("_{name}");
+            let edits = std::iter::once(TextEdit::new(def.loc(), new_name.clone()))
+                .chain(reassignments.iter().map(|node| {
+                    let loc = Location::from_range(Arc::clone(self.ctx().src()), node.range());
+                    TextEdit::new(loc, new_name.clone())
+                }))
+                .collect::<Vec<_>>();
+            // The new name must not capture or be captured by another variable of the same name
+            let src = self.ctx().src();
+            let is_safe = !src.content().contains(&new_name)
+                || rename::verify(src, self.ctx().config(), &edits, &new_name).is_ok();
+
+            let report = LintReport::new(self, def.loc(), format!("Unused local `{name}`"))
+                .with_label(LintLabel::new(def.loc(), label))
+                .with_tag(LintTag::Unnecessary);
+            let report = if is_safe {
+                report.with_fix(Fix::new(
+                    format!("Prefix with `_`: `{new_name}`"),
+                    Applicability::Safe,
+                    edits,
+                ))
+            } else {
+                report
+            };
+            emit_report(self, report);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use crate::diagnostics::{emit_report, LintKind, LintLabel, LintLevel, LintReport};
+use crate::fix::{removal_range, Applicability, Fix, TextEdit};
+use crate::{context::Context, impl_lint_pass};
+use full_moon::{ast, visitors::Visitor};
+
+/// Report `---@diagnostic` comments which never suppressed a report.
+/// This pass must run after all other passes.
+pub struct UnusedSuppression {
+    ctx: Arc<Context>,
+}
+impl_lint_pass!(
+    "unused-suppression",
+    UnusedSuppression,
+    LintKind::Diagnostics,
+    LintLevel::Warning
+);
+
+impl UnusedSuppression {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl Visitor for UnusedSuppression {
+    fn visit_ast(&mut self, _ast: &ast::Ast) {
+        let config = self.ctx().config();
+        for directive in self.ctx().suppressions().unused() {
+            // Diagnostics disabled by the configuration are never reported
+            let names = directive.names();
+            if !names.is_empty() && names.iter().all(|name| !config.is_enabled(name)) {
+                continue;
+            }
+
+            let loc = directive.loc();
+            let label = if names.is_empty() {
+                "No diagnostic is reported here".to_string()
+            } else {
+                format!(
+                    "No {} diagnostic is reported here",
+                    names
+                        .iter()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                )
+            };
+            emit_report(
+                self,
+                LintReport::new(
+                    self,
+                    loc.clone(),
+                    format!("Unused suppression `{}`", directive.kind().as_str()),
+                )
+                .with_label(LintLabel::new(loc.clone(), label))
+                .with_fix(Fix::new(
+                    "Remove the comment".to_string(),
+                    Applicability::Safe,
+                    vec![TextEdit::delete(removal_range(self.ctx().src(), &loc))],
+                )),
+            );
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Scope-aware renaming of variables based on name resolution.
+
+use std::{ops::Range, sync::Arc};
+
+use crate::{
+    config::Config,
+    fix::{apply_fixes, Applicability, Fix, TextEdit},
+    location::{Location, SourceInfo},
+    parse,
+    resolver::{Definition, Resolver, Visibility},
+};
+
+/// Reserved keywords of Lua 5.4
+const KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Location of the renamable name at the byte offset.
+pub fn prepare_rename(src: &Arc<SourceInfo>, config: &Config, offset: usize) -> Option<Location> {
+    let resolver = resolve(src, config)?;
+    let def_id = resolver.definition_at(offset).filter(|def_id| {
+        resolver.get_definition(*def_id).map(Definition::visibility) == Some(Visibility::Local)
+    })?;
+    resolver
+        .occurrences(def_id)
+        .into_iter()
+        .map(|o| o.loc())
+        .find(|loc| loc.range().start <= offset && offset <= loc.range().end)
+}
+
+/// Compute edits which rename the variable at the byte offset and all its references.
+/// A rename is refused if it changes what any name refers to, e.g. a reference is captured by an
+/// inner local of the new name.
+pub fn rename(
+    src: &Arc<SourceInfo>,
+    config: &Config,
+    offset: usize,
+    new_name: &str,
+) -> Result<Vec<TextEdit>, String> {
+    let resolver = resolve(src, config).ok_or("The file has a syntax error")?;
+    let def_id = resolver
+        .definition_at(offset)
+        .ok_or("No variable to rename at the position")?;
+    let def = resolver
+        .get_definition(def_id)
+        .ok_or("No variable to rename at the position")?;
+    // e.g. `self` of methods
+    if !Arc::ptr_eq(def.loc().src(), src) {
+        return Err(format!("`{}` is not defined in the file", def.name()));
+    }
+    // Only references in this file would be renamed
+    if def.visibility() != Visibility::Local {
+        return Err(format!(
+            "`{}` is a global, which other files may use",
+            def.name()
+        ));
+    }
+    let old_name = def.name().to_owned();
+
+    if !is_identifier(new_name) {
+        return Err(format!("`{new_name}` is not a valid name"));
+    }
+    if config.library().global(new_name).is_some() || config.globals().iter().any(|g| g == new_name)
+    {
+        return Err(format!("`{new_name}` collides with a builtin name"));
+    }
+    if new_name == old_name {
+        return Ok(Vec::new());
+    }
+
+    let edits = resolver
+        .occurrences(def_id)
+        .into_iter()
+        .map(|o| TextEdit::new(o.loc(), new_name.to_owned()))
+        .collect::<Vec<_>>();
+    verify(src, config, &edits, new_name)?;
+    Ok(edits)
+}
+
+fn resolve(src: &Arc<SourceInfo>, config: &Config) -> Option<Resolver> {
+    let ast = parse(Arc::clone(src)).ok()?;
+    let mut resolver = Resolver::new(Arc::clone(src), config);
+    resolver.go(&ast);
+    Some(resolver)
+}
+
+/// Resolve names again after renaming and check that exactly the renamed names refer to the
+/// renamed definition.
+pub(crate) fn verify(
+    src: &Arc<SourceInfo>,
+    config: &Config,
+    edits: &[TextEdit],
+    new_name: &str,
+) -> Result<(), String> {
+    let fix = Fix::new(String::new(), Applicability::Safe, edits.to_vec());
+    let (content, _) = apply_fixes(src.content(), &[&fix]);
+    let renamed = Arc::new(SourceInfo::new(src.path().to_owned(), content));
+    let new_resolver = resolve(&renamed, config).ok_or("The renamed file has a syntax error")?;
+
+    // Ranges of the renamed names in the new source
+    let mut shift = 0isize;
+    let expected = edits
+        .iter()
+        .map(|edit| {
+            let range = edit.range();
+            let start = (range.start as isize + shift) as usize;
+            shift += new_name.len() as isize - range.len() as isize;
+            start..start + new_name.len()
+        })
+        .collect::<Vec<_>>();
+
+    let line = |src: &Arc<SourceInfo>, range: &Range<usize>| src.position(range.start).line();
+    let new_def = new_resolver.definition_at(expected[0].start);
+    let actual = new_def
+        .map(|def| {
+            new_resolver
+                .occurrences(def)
+                .iter()
+                .map(|o| o.loc().range())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if let Some(range) = expected.iter().find(|range| !actual.contains(range)) {
+        let other = new_resolver
+            .definition_at(range.start)
+            .and_then(|id| new_resolver.get_definition(id))
+            .filter(|def| Arc::ptr_eq(def.loc().src(), &renamed));
+        return Err(match other {
+            Some(other) => format!(
+                "`{new_name}` at line {} would refer to the definition at line {}",
+                line(&renamed, range),
+                other.loc().start().line()
+            ),
+            None => format!(
+                "`{new_name}` at line {} would refer to another variable",
+                line(&renamed, range)
+            ),
+        });
+    }
+    if let Some(range) = actual.iter().find(|range| !expected.contains(range)) {
+        return Err(format!(
+            "`{new_name}` at line {} would refer to the renamed variable",
+            line(&renamed, range)
+        ));
+    }
+    Ok(())
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+#[cfg(test)]
+fn rename_str(code: &str, offset: usize, new_name: &str) -> Result<String, String> {
+    let src = Arc::new(SourceInfo::new("test.lua".to_owned(), code.to_owned()));
+    let edits = rename(&src, &Config::default(), offset, new_name)?;
+    let fix = Fix::new(String::new(), Applicability::Safe, edits);
+    Ok(apply_fixes(code, &[&fix]).0)
+}
+
+#[test]
+fn test_rename() {
+    assert_eq!(
+        rename_str("local x = 1\nx = 2\nprint(x)", 6, "count").unwrap(),
+        "local count = 1\ncount = 2\nprint(count)"
+    );
+    // Parameters
+    assert_eq!(
+        rename_str("local function f(a) return a end", 17, "b").unwrap(),
+        "local function f(b) return b end"
+    );
+    // Globals
+    assert_eq!(
+        rename_str("foo = 1\nprint(foo)", 0, "bar").unwrap_err(),
+        "`foo` is a global, which other files may use"
+    );
+
+    // Captured by an inner local
+    assert_eq!(
+        rename_str("local x = 1\ndo\n  local y = 2\n  print(x, y)\nend", 6, "y").unwrap_err(),
+        "`y` at line 4 would refer to the definition at line 3"
+    );
+    // Captures a reference to an outer local
+    assert_eq!(
+        rename_str(
+            "local y = 1\ndo\n  local x = 2\n  print(x, y)\nend",
+            23,
+            "y"
+        )
+        .unwrap_err(),
+        "`y` at line 4 would refer to the renamed variable"
+    );
+    assert_eq!(
+        rename_str("local x = 1\nprint(x)", 6, "print").unwrap_err(),
+        "`print` collides with a builtin name"
+    );
+    assert_eq!(
+        rename_str("local x = 1\nprint(x)", 6, "end").unwrap_err(),
+        "`end` is not a valid name"
+    );
+    assert_eq!(
+        rename_str("print(1)", 0, "p").unwrap_err(),
+        "No variable to rename at the position"
+    );
+
+    // Tables of methods
+    let method = "local t = {}\nfunction t:f() return self end";
+    assert_eq!(
+        rename_str(method, 6, "u").unwrap(),
+        "local u = {}\nfunction u:f() return self end"
+    );
+    assert_eq!(
+        rename_str(method, 35, "this").unwrap_err(),
+        "`self` is not defined in the file"
+    );
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 
 use full_moon::visitors::Visit;
 use full_moon::{ast, node::Node, visitors::Visitor};
 use parking_lot::Mutex;
 
+use crate::config::Config;
 use crate::location::{Location, SourceInfo};
 use crate::utils;
 
@@ -36,24 +38,48 @@ impl NodeId {
             private: (range.0.bytes(), range.1.bytes()),
         }
     }
+
+    /// Byte range of the node
+    pub(crate) fn range(&self) -> Range<usize> {
+        self.private.0..self.private.1
+    }
 }
 
 #[derive(Debug)]
 pub struct Definition {
     vis: Visibility,
+    kind: DefinitionKind,
     name: String,
     loc: Location,
+    /// Number of functions enclosing the definition
+    depth: usize,
+    /// Uses and reassignments from inner functions
+    upvalue_uses: Vec<NodeId>,
+    /// Fields defined by function declarations (e.g. `function t.a.b() end`)
+    fields: Vec<FieldDefinition>,
 }
 
 impl Definition {
-    fn new(vis: Visibility, name: String, loc: Location) -> Self {
-        Definition { vis, name, loc }
+    fn new(vis: Visibility, kind: DefinitionKind, name: String, loc: Location) -> Self {
+        Definition {
+            vis,
+            kind,
+            name,
+            loc,
+            depth: 0,
+            upvalue_uses: Vec::new(),
+            fields: Vec::new(),
+        }
     }
 
     pub fn visibility(&self) -> Visibility {
         self.vis
     }
 
+    pub fn kind(&self) -> DefinitionKind {
+        self.kind
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -61,6 +87,52 @@ impl Definition {
     pub fn loc(&self) -> Location {
         self.loc.clone()
     }
+
+    /// Number of functions enclosing the definition. `0` for the main chunk and globals.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Uses and reassignments of the local from inner functions, which capture it as an upvalue.
+    pub fn upvalue_uses(&self) -> &[NodeId] {
+        &self.upvalue_uses
+    }
+
+    /// Whether the local is captured by a closure.
+    pub fn is_captured(&self) -> bool {
+        !self.upvalue_uses.is_empty()
+    }
+
+    /// Fields of the table defined by function declarations, in the order of locations.
+    pub fn fields(&self) -> &[FieldDefinition] {
+        &self.fields
+    }
+}
+
+/// A field of a table defined by a function declaration (e.g. `b.c` of `function a.b.c() end` or
+/// `b` of `function a:b() end`)
+#[derive(Debug)]
+pub struct FieldDefinition {
+    path: Vec<String>,
+    loc: Location,
+    is_method: bool,
+}
+
+impl FieldDefinition {
+    /// Names of the fields from the table
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Location of the last name
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+
+    /// Whether the function is declared with `:`, which has the implicit `self` parameter.
+    pub fn is_method(&self) -> bool {
+        self.is_method
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -73,6 +145,54 @@ pub enum Visibility {
     Function,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DefinitionKind {
+    // Variables defined by assignments (e.g. `local x = 1`, `x = 1`)
+    Variable,
+    // Functions defined by `function` statements
+    Function,
+    // Function parameters
+    Parameter,
+    // Variables of `for` loops
+    LoopVariable,
+}
+
+/// How a use or a reassignment refers to its definition
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UseKind {
+    // Local variables of the same function
+    Local,
+    // Local variables of an enclosing function. The depth is the number of functions between the
+    // use and the definition (e.g. `1` for a local of the directly enclosing function).
+    Upvalue(usize),
+    // Global variables
+    Global,
+}
+
+/// How a name occurs in the source.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OccurrenceKind {
+    Definition,
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone)]
+pub struct Occurrence {
+    kind: OccurrenceKind,
+    loc: Location,
+}
+
+impl Occurrence {
+    pub fn kind(&self) -> OccurrenceKind {
+        self.kind
+    }
+
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+}
+
 pub type Scope = Arc<Mutex<HashMap<String, NodeId>>>;
 
 #[derive(Debug)]
@@ -86,13 +206,33 @@ pub struct Resolver {
     /// e.g. foo = 1; foo = 2
     /// relation: foo (second occurence) -> foo (first occurence)
     reassignments: HashMap<NodeId, NodeId>,
+    /// Def-Reassignment relations
+    def_reassignments: HashMap<NodeId, Vec<NodeId>>,
+
+    /// Kinds of uses and reassignments
+    use_kinds: HashMap<NodeId, UseKind>,
+    /// Locals of enclosing functions captured by functions, by their bodies
+    captures: HashMap<NodeId, Vec<NodeId>>,
+    /// Bodies of functions enclosing the current node
+    functions: Vec<NodeId>,
 
     /// Block to scope mapping
     block_to_scope: HashMap<NodeId, Vec<Scope>>,
+    /// Variables of `for` loops waiting for the bodies, with the depths of the scopes of the bodies.
+    /// Blocks are identified by depths since empty blocks have the same [NodeId].
+    loop_variables: Vec<(usize, Vec<(NodeId, Definition)>)>,
+    /// Depths of the scopes of bodies of `repeat` loops, which end after the conditions
+    repeat_depths: Vec<usize>,
+    /// Bodies of methods by their `:` tokens, which define the implicit `self` parameters
+    methods: HashMap<NodeId, NodeId>,
 
     // current lexical scope. After resolving, the first scope only remains
     scopes: Vec<Scope>,
     definitions: HashMap<NodeId, Definition>,
+    /// Globals of the library and the configuration
+    builtin_names: Vec<String>,
+    /// Definition shared by builtin names and globals defined by the configuration
+    builtin_id: Option<NodeId>,
     src: Arc<SourceInfo>,
 }
 
@@ -100,18 +240,38 @@ pub struct Resolver {
 /// 1. Get all definitions (local variables, global variables, functions)
 /// 2. Resolve uses of variables
 impl Resolver {
-    pub fn new(src: Arc<SourceInfo>) -> Self {
+    pub fn new(src: Arc<SourceInfo>, config: &Config) -> Self {
         Resolver {
             use_defs: HashMap::new(),
             def_uses: HashMap::new(),
             reassignments: HashMap::new(),
+            def_reassignments: HashMap::new(),
+            use_kinds: HashMap::new(),
+            captures: HashMap::new(),
+            functions: Vec::new(),
             block_to_scope: HashMap::new(),
+            loop_variables: Vec::new(),
+            repeat_depths: Vec::new(),
+            methods: HashMap::new(),
             scopes: Vec::new(),
             definitions: HashMap::new(),
+            builtin_names: config
+                .library()
+                .names()
+                .map(str::to_owned)
+                .chain(config.globals().iter().cloned())
+                .collect(),
+            builtin_id: None,
             src,
         }
     }
 
+    /// Add globals defined outside of the source (e.g. by other files of the workspace). They are
+    /// builtin names as well.
+    pub(crate) fn add_globals(&mut self, names: impl IntoIterator<Item = String>) {
+        self.builtin_names.extend(names);
+    }
+
     pub fn go(&mut self, ast: &ast::Ast) {
         log::debug!("resolver: start");
         self.visit_ast(ast);
@@ -137,6 +297,91 @@ impl Resolver {
         self.definitions.get(&def_id)
     }
 
+    /// All definitions including builtin names.
+    pub fn definitions(&self) -> impl Iterator<Item = (NodeId, &Definition)> {
+        self.definitions.iter().map(|(id, def)| (*id, def))
+    }
+
+    /// Uses of the definition, which read the variable.
+    pub fn uses(&self, def_id: NodeId) -> &[NodeId] {
+        self.def_uses.get(&def_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Assignments to the definition other than the definition itself, in the order of appearance.
+    pub fn reassignments(&self, def_id: NodeId) -> &[NodeId] {
+        self.def_reassignments
+            .get(&def_id)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// How the use or the reassignment refers to its definition. `None` for unresolved names.
+    pub fn use_kind(&self, node_id: NodeId) -> Option<UseKind> {
+        self.use_kinds.get(&node_id).copied()
+    }
+
+    /// Locals of enclosing functions captured by the function, in the order of their first uses.
+    /// Locals captured by inner functions are captured by the function as well, so a function
+    /// capturing nothing does not depend on its enclosing functions.
+    pub fn captures(&self, function_body: NodeId) -> &[NodeId] {
+        self.captures.get(&function_body).map_or(&[], Vec::as_slice)
+    }
+
+    /// Find the definition of the name at the byte offset. The name may be the definition itself,
+    /// a use or a reassignment. Builtin names have no definition in the source.
+    pub fn definition_at(&self, offset: usize) -> Option<NodeId> {
+        let contains = |range: Range<usize>| range.start <= offset && offset <= range.end;
+        let def = self.definitions.iter().find(|(def_id, def)| {
+            !self.is_builtin(**def_id)
+                && Arc::ptr_eq(def.loc().src(), &self.src)
+                && contains(def.loc().range())
+        });
+        if let Some((def_id, _)) = def {
+            return Some(*def_id);
+        }
+        self.use_defs
+            .iter()
+            .chain(self.reassignments.iter())
+            .find(|(node_id, _)| contains(node_id.range()))
+            .map(|(_, def_id)| *def_id)
+            .filter(|def_id| !self.is_builtin(*def_id))
+    }
+
+    /// The definition, uses and reassignments of the definition sorted by location. Definitions
+    /// not written in the source (e.g. `self` of methods) are excluded.
+    pub fn occurrences(&self, def_id: NodeId) -> Vec<Occurrence> {
+        let Some(def) = self.definitions.get(&def_id) else {
+            return Vec::new();
+        };
+        let occurrence = |kind, node_id: &NodeId| Occurrence {
+            kind,
+            loc: Location::from_range(Arc::clone(&self.src), node_id.range()),
+        };
+        let mut occurrences = Arc::ptr_eq(def.loc().src(), &self.src)
+            .then(|| Occurrence {
+                kind: OccurrenceKind::Definition,
+                loc: def.loc(),
+            })
+            .into_iter()
+            .chain(
+                self.uses(def_id)
+                    .iter()
+                    .map(|node_id| occurrence(OccurrenceKind::Read, node_id)),
+            )
+            .chain(
+                self.reassignments(def_id)
+                    .iter()
+                    .map(|node_id| occurrence(OccurrenceKind::Write, node_id)),
+            )
+            .collect::<Vec<_>>();
+        occurrences.sort_by_key(|o| o.loc.range().start);
+        occurrences
+    }
+
+    /// Whether the definition is a builtin name or a global defined by the configuration.
+    pub fn is_builtin(&self, def_id: NodeId) -> bool {
+        self.builtin_id == Some(def_id)
+    }
+
     pub fn lookup_scope(&self, block: NodeId) -> Option<&Vec<Scope>> {
         self.block_to_scope.get(&block)
     }
@@ -163,7 +408,32 @@ impl Resolver {
         self.scopes.pop();
     }
 
-    fn insert_local_definiton(&mut self, name: String, node_id: NodeId, def: Definition) {
+    /// Record the kind of a use or a reassignment of the definition.
+    fn record_use(&mut self, node_id: NodeId, def_id: NodeId) {
+        let Some(def) = self.definitions.get_mut(&def_id) else {
+            return;
+        };
+        let depth = self.functions.len();
+        let kind = if def.vis != Visibility::Local {
+            UseKind::Global
+        } else if def.depth == depth {
+            UseKind::Local
+        } else {
+            def.upvalue_uses.push(node_id);
+            // Functions between the definition and the use capture the local
+            for function in self.functions[def.depth..].iter() {
+                let captures = self.captures.entry(*function).or_default();
+                if !captures.contains(&def_id) {
+                    captures.push(def_id);
+                }
+            }
+            UseKind::Upvalue(depth - def.depth)
+        };
+        self.use_kinds.insert(node_id, kind);
+    }
+
+    fn insert_local_definiton(&mut self, name: String, node_id: NodeId, mut def: Definition) {
+        def.depth = self.functions.len();
         let last_scope = self.scopes.last_mut().unwrap();
         let mut last_scope = last_scope.lock();
         last_scope.insert(name, node_id);
@@ -178,15 +448,23 @@ impl Resolver {
     }
 }
 
-impl<'a> Visitor for Resolver {
+impl Visitor for Resolver {
     fn visit_ast(&mut self, ast: &ast::Ast) {
         // push the first lexical scope
         self.push_scope();
-        for name in utils::builtin_names() {
-            let def = Definition::new(Visibility::Global, name.to_owned(), Location::dummy());
+        self.builtin_id = Some(NodeId::from(ast));
+        let builtin_names = std::mem::take(&mut self.builtin_names);
+        for name in builtin_names.iter() {
+            let def = Definition::new(
+                Visibility::Global,
+                DefinitionKind::Variable,
+                name.to_owned(),
+                Location::dummy(),
+            );
             let node_id = NodeId::from(ast);
             self.insert_global_definition(name.to_owned(), node_id, def);
         }
+        self.builtin_names = builtin_names;
 
         ast.nodes().visit(self);
         ast.eof().visit(self);
@@ -194,20 +472,51 @@ impl<'a> Visitor for Resolver {
 
     fn visit_block(&mut self, _node: &ast::Block) {
         self.push_scope();
+        // Variables of `for` loops are only visible in the bodies. Blocks of functions in the
+        // headers of the loops are deeper than the bodies.
+        if self
+            .loop_variables
+            .last()
+            .is_some_and(|(depth, _)| *depth == self.scopes.len())
+        {
+            let (_, variables) = self.loop_variables.pop().unwrap();
+            for (node_id, def) in variables {
+                log::trace!("insert loop var decl {}", def.name());
+                self.insert_local_definiton(def.name().to_owned(), node_id, def);
+            }
+        }
     }
 
     fn visit_block_end(&mut self, node: &ast::Block) {
         let node_id = NodeId::from(node);
         self.block_to_scope.insert(node_id, self.scopes.clone());
+        // The condition of `repeat ... until` sees locals of the body
+        if self.repeat_depths.last() != Some(&self.scopes.len()) {
+            self.pop_scope();
+        }
+    }
+
+    fn visit_repeat(&mut self, _node: &ast::Repeat) {
+        self.repeat_depths.push(self.scopes.len() + 1);
+    }
+
+    fn visit_repeat_end(&mut self, _node: &ast::Repeat) {
+        self.repeat_depths.pop();
         self.pop_scope();
     }
 
-    // local functions (e.g. `local function foo() end`)
+    // local functions (e.g. `local function foo() end`). Unlike `local foo = function() end`, the
+    // name is visible in the body of the function.
     fn visit_local_function(&mut self, node: &ast::LocalFunction) {
         let node_id = NodeId::from(node);
         let name = node.name().to_string();
         let loc = Location::from((&self.src, node.name()));
-        let def = Definition::new(Visibility::Local, name.clone(), loc);
+        let def = Definition::new(
+            Visibility::Local,
+            DefinitionKind::Function,
+            name.clone(),
+            loc,
+        );
         log::trace!("insert local function definition {}", name);
         self.insert_local_definiton(name, node_id, def);
     }
@@ -215,18 +524,78 @@ impl<'a> Visitor for Resolver {
     fn visit_function_declaration(&mut self, node: &ast::FunctionDeclaration) {
         let function_name = node.name();
 
-        if function_name.names().len() == 1 {
+        if function_name.names().len() == 1 && function_name.method_name().is_none() {
             let node_id = NodeId::from(function_name);
             let loc = Location::from((&self.src, function_name.names().first().tokens()));
             let name = function_name.names().first().unwrap().to_string();
             log::trace!("insert global function definition {}", name);
-            let def = Definition::new(Visibility::Function, name.clone(), loc);
+            let def = Definition::new(
+                Visibility::Function,
+                DefinitionKind::Function,
+                name.clone(),
+                loc,
+            );
             self.insert_global_definition(name, node_id, def)
         }
+
+        let names = function_name.names().iter().collect::<Vec<_>>();
+        let Some((table, fields)) = names.split_first() else {
+            return;
+        };
+        if fields.is_empty() && function_name.method_name().is_none() {
+            return;
+        }
+        if let Some(colon) = function_name.method_colon() {
+            self.methods
+                .insert(NodeId::from(node.body()), NodeId::from(colon));
+        }
+
+        // `function t.a:b() end` assigns a field of `t`, which is a use of `t`
+        let node_id = NodeId::from(*table);
+        let name = utils::ident_as_str(table);
+        let Some(def_id) = self.lookup_name(name) else {
+            // Unresolved name. Error is emitted by undefined-global pass.
+            log::debug!("unresolved name: `{}`", name);
+            return;
+        };
+        log::debug!("Resolve use of `{name}`: {:?} -> {:?}", node_id, def_id);
+        self.use_defs.insert(node_id, def_id);
+        self.def_uses.entry(def_id).or_default().push(node_id);
+        self.record_use(node_id, def_id);
+
+        let last = function_name
+            .method_name()
+            .or(fields.last().copied())
+            .unwrap();
+        let field = FieldDefinition {
+            path: fields
+                .iter()
+                .copied()
+                .chain(function_name.method_name())
+                .map(|name| utils::ident_as_str(name).to_owned())
+                .collect(),
+            loc: Location::from((&self.src, last)),
+            is_method: function_name.method_name().is_some(),
+        };
+        if let Some(def) = self.definitions.get_mut(&def_id) {
+            def.fields.push(field);
+        }
     }
 
     fn visit_function_body(&mut self, node: &ast::FunctionBody) {
+        self.functions.push(NodeId::from(node));
         self.push_scope();
+        // Methods have the implicit `self` parameter, which is not written in the source
+        if let Some(colon) = self.methods.remove(&NodeId::from(node)) {
+            let def = Definition::new(
+                Visibility::Local,
+                DefinitionKind::Parameter,
+                "self".to_owned(),
+                Location::dummy(),
+            );
+            log::trace!("insert implicit self parameter");
+            self.insert_local_definiton("self".to_owned(), colon, def);
+        }
         // add parameters
         for param in node.parameters() {
             let ast::Parameter::Name(name) = param else {
@@ -235,7 +604,12 @@ impl<'a> Visitor for Resolver {
             let node_id = NodeId::from(param);
             let loc = Location::from((&self.src, name));
             let name = utils::ident_as_str(name).to_owned();
-            let def = Definition::new(Visibility::Local, name.clone(), loc);
+            let def = Definition::new(
+                Visibility::Local,
+                DefinitionKind::Parameter,
+                name.clone(),
+                loc,
+            );
             log::trace!("insert function parameter {}", name);
             self.insert_local_definiton(name, node_id, def);
         }
@@ -243,15 +617,23 @@ impl<'a> Visitor for Resolver {
 
     fn visit_function_body_end(&mut self, _node: &ast::FunctionBody) {
         self.pop_scope();
+        self.functions.pop();
     }
 
-    fn visit_local_assignment(&mut self, node: &ast::LocalAssignment) {
+    // The names come into scope after the right-hand side is evaluated (e.g. `x` of `local x = x`
+    // refers to the outer variable).
+    fn visit_local_assignment_end(&mut self, node: &ast::LocalAssignment) {
         // local assignments are always a definiton (including shadowing)
         for name in node.names() {
             let node_id = NodeId::from(name);
             let loc = Location::from((&self.src, name));
             let name = utils::ident_as_str(name).to_owned();
-            let def = Definition::new(Visibility::Local, name.clone(), loc);
+            let def = Definition::new(
+                Visibility::Local,
+                DefinitionKind::Variable,
+                name.clone(),
+                loc,
+            );
             log::trace!("insert local assignment definition {}", name);
             self.insert_local_definiton(name, node_id, def);
         }
@@ -269,9 +651,19 @@ impl<'a> Visitor for Resolver {
                 // Found a definition. This is a reassignment.
                 log::trace!("found reassignment {}", name);
                 self.reassignments.insert(node_id, def_id);
+                self.def_reassignments
+                    .entry(def_id)
+                    .or_default()
+                    .push(node_id);
+                self.record_use(node_id, def_id);
             } else {
                 // Definiton not found. This is a global assignment.
-                let def = Definition::new(Visibility::Global, name.clone(), loc);
+                let def = Definition::new(
+                    Visibility::Global,
+                    DefinitionKind::Variable,
+                    name.clone(),
+                    loc,
+                );
                 log::trace!("insert global assignment definition {}", name);
                 self.insert_global_definition(name, node_id, def);
             }
@@ -289,10 +681,8 @@ impl<'a> Visitor for Resolver {
                 log::debug!("Resolve use of `{name}`: {:?} -> {:?}", node_id, def_id);
                 // Register use-def and def-use relation
                 self.use_defs.insert(node_id, def_id);
-                self.def_uses
-                    .entry(def_id)
-                    .or_insert_with(Vec::new)
-                    .push(node_id);
+                self.def_uses.entry(def_id).or_default().push(node_id);
+                self.record_use(node_id, def_id);
             } else {
                 // Unresolved name. Error is emitted by undefined-global pass.
                 log::debug!("unresolved name: `{}`", name);
@@ -306,7 +696,7 @@ impl<'a> Visitor for Resolver {
         };
         let name = utils::ident_as_str(name);
         let node_id = NodeId::from(prefix);
-        if let Some(def_node_id) = self.lookup_name(&name) {
+        if let Some(def_node_id) = self.lookup_name(name) {
             log::debug!(
                 "Resolve use of `{name}`: {:?} -> {:?}",
                 node_id,
@@ -314,10 +704,8 @@ impl<'a> Visitor for Resolver {
             );
             // Register use-def and def-use relation
             self.use_defs.insert(node_id, def_node_id);
-            self.def_uses
-                .entry(def_node_id)
-                .or_insert_with(Vec::new)
-                .push(node_id);
+            self.def_uses.entry(def_node_id).or_default().push(node_id);
+            self.record_use(node_id, def_node_id);
         } else {
             // Unresolved name. Error is emitted by undefined-global pass.
             log::debug!("unresolved name: `{}`", name);
@@ -325,14 +713,19 @@ impl<'a> Visitor for Resolver {
     }
 
     fn visit_generic_for(&mut self, node: &ast::GenericFor) {
-        for name in node.names() {
-            let node_id = NodeId::from(name);
-            let loc = Location::from((&self.src, name));
-            let name = utils::ident_as_str(name).to_owned();
-            let def = Definition::new(Visibility::Local, name.clone(), loc);
-            log::trace!("insert loop var decl {}", name);
-            self.insert_local_definiton(name, node_id, def);
-        }
+        let variables = node
+            .names()
+            .iter()
+            .map(|name| {
+                let node_id = NodeId::from(name);
+                let loc = Location::from((&self.src, name));
+                let name = utils::ident_as_str(name).to_owned();
+                let def =
+                    Definition::new(Visibility::Local, DefinitionKind::LoopVariable, name, loc);
+                (node_id, def)
+            })
+            .collect();
+        self.loop_variables.push((self.scopes.len() + 1, variables));
     }
 
     fn visit_numeric_for(&mut self, node: &ast::NumericFor) {
@@ -340,8 +733,114 @@ impl<'a> Visitor for Resolver {
         let node_id = NodeId::from(name);
         let loc = Location::from((&self.src, name));
         let name = utils::ident_as_str(name).to_owned();
-        let def = Definition::new(Visibility::Local, name.clone(), loc);
-        log::trace!("insert loop var decl {}", name);
-        self.insert_local_definiton(name, node_id, def);
+        let def = Definition::new(Visibility::Local, DefinitionKind::LoopVariable, name, loc);
+        self.loop_variables
+            .push((self.scopes.len() + 1, vec![(node_id, def)]));
     }
 }
+
+#[test]
+fn test_occurrences() {
+    let code = "local x = 1\nx = 2\nprint(x)\nlocal function f() return f end\n";
+    let src = Arc::new(SourceInfo::new("test.lua".to_owned(), code.to_owned()));
+    let Ok(ast) = crate::parse(Arc::clone(&src)) else {
+        panic!("failed to parse");
+    };
+    let mut resolver = Resolver::new(src, &Config::default());
+    resolver.go(&ast);
+
+    let occurrences = |offset| {
+        let def_id = resolver.definition_at(offset).unwrap();
+        resolver
+            .occurrences(def_id)
+            .iter()
+            .map(|o| (o.kind(), o.loc().range()))
+            .collect::<Vec<_>>()
+    };
+    let x = vec![
+        (OccurrenceKind::Definition, 6..7),
+        (OccurrenceKind::Write, 12..13),
+        (OccurrenceKind::Read, 24..25),
+    ];
+    // From the definition, the reassignment and the use
+    assert_eq!(occurrences(6), x);
+    assert_eq!(occurrences(13), x);
+    assert_eq!(occurrences(24), x);
+    assert_eq!(
+        occurrences(53),
+        vec![
+            (OccurrenceKind::Definition, 42..43),
+            (OccurrenceKind::Read, 53..54),
+        ]
+    );
+    // Builtins are not defined in the source
+    assert_eq!(resolver.definition_at(19), None);
+}
+
+#[test]
+fn test_upvalues() {
+    let code = "local a = 1\nlocal function f(b)\n  return function() return a + b + print end\nend\na = 2\n";
+    let src = Arc::new(SourceInfo::new("test.lua".to_owned(), code.to_owned()));
+    let Ok(ast) = crate::parse(Arc::clone(&src)) else {
+        panic!("failed to parse");
+    };
+    let mut resolver = Resolver::new(src, &Config::default());
+    resolver.go(&ast);
+
+    fn node_at<'a>(mut nodes: impl Iterator<Item = &'a NodeId>, offset: usize) -> NodeId {
+        *nodes
+            .find(|node_id| node_id.range().start == offset)
+            .unwrap()
+    }
+    let use_kind = |offset| resolver.use_kind(node_at(resolver.use_kinds.keys(), offset));
+    // `a` is captured through two functions and `b` through one
+    assert_eq!(use_kind(59), Some(UseKind::Upvalue(2)));
+    assert_eq!(use_kind(63), Some(UseKind::Upvalue(1)));
+    assert_eq!(use_kind(67), Some(UseKind::Global));
+    assert_eq!(use_kind(81), Some(UseKind::Local));
+
+    let a = resolver.definition_at(6).unwrap();
+    let b = resolver.definition_at(29).unwrap();
+    assert!(resolver.get_definition(a).unwrap().is_captured());
+    assert_eq!(resolver.get_definition(b).unwrap().depth(), 1);
+
+    let captures = |offset| resolver.captures(node_at(resolver.captures.keys(), offset));
+    // The outer function captures `a` for the inner one
+    assert_eq!(captures(28), [a]);
+    assert_eq!(captures(49), [a, b]);
+}
+
+#[test]
+fn test_function_declarations() {
+    let code = "local t = {}\nfunction t.a.b() end\nfunction t:c() return self end\n";
+    let src = Arc::new(SourceInfo::new("test.lua".to_owned(), code.to_owned()));
+    let Ok(ast) = crate::parse(Arc::clone(&src)) else {
+        panic!("failed to parse");
+    };
+    let mut resolver = Resolver::new(src, &Config::default());
+    resolver.go(&ast);
+
+    let t = resolver.definition_at(6).unwrap();
+    let fields = resolver
+        .get_definition(t)
+        .unwrap()
+        .fields()
+        .iter()
+        .map(|field| (field.path().join("."), field.is_method()))
+        .collect::<Vec<_>>();
+    assert_eq!(fields, [("a.b".to_owned(), false), ("c".to_owned(), true)]);
+    assert_eq!(resolver.uses(t).len(), 2);
+
+    // `self` is a parameter of the method
+    let self_ = resolver.definition_at(56).unwrap();
+    let def = resolver.get_definition(self_).unwrap();
+    assert_eq!(def.name(), "self");
+    assert_eq!(def.kind(), DefinitionKind::Parameter);
+    // Only the use is in the source
+    let occurrences = resolver
+        .occurrences(self_)
+        .iter()
+        .map(|o| (o.kind(), o.loc().range()))
+        .collect::<Vec<_>>();
+    assert_eq!(occurrences, [(OccurrenceKind::Read, 56..60)]);
+}
@@ -0,0 +1,193 @@
+use std::{collections::HashMap, sync::Arc};
+
+use full_moon::{
+    ast,
+    node::Node,
+    tokenizer::{Token, TokenType},
+};
+use parking_lot::Mutex;
+
+use crate::{
+    diagnostics::LintReport,
+    location::{Location, Position, SourceInfo},
+};
+
+/// Name of the diagnostic reported for suppressions which never match a report.
+/// Reports of this diagnostic cannot be suppressed by comments.
+pub(crate) const UNUSED_SUPPRESSION: &str = "unused-suppression";
+
+/// Action of a `---@diagnostic` comment.
+/// See https://luals.github.io/wiki/annotations/#diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectiveKind {
+    /// `---@diagnostic disable`: disable diagnostics until `enable`
+    Disable,
+    /// `---@diagnostic enable`: enable diagnostics disabled by `disable`
+    Enable,
+    /// `---@diagnostic disable-line`: disable diagnostics on the same line
+    DisableLine,
+    /// `---@diagnostic disable-next-line`: disable diagnostics on the next line
+    DisableNextLine,
+}
+
+impl DirectiveKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Enable => "enable",
+            Self::DisableLine => "disable-line",
+            Self::DisableNextLine => "disable-next-line",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Directive {
+    kind: DirectiveKind,
+    /// Diagnostic names. Empty if the directive applies to all diagnostics.
+    names: Vec<String>,
+    loc: Location,
+}
+
+impl Directive {
+    pub fn kind(&self) -> DirectiveKind {
+        self.kind
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+
+    fn applies_to(&self, name: &str) -> bool {
+        self.names.is_empty() || self.names.iter().any(|n| n == name)
+    }
+}
+
+/// `---@diagnostic` comments in a file
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    /// Directives sorted by their position
+    directives: Vec<Directive>,
+    /// Whether each directive has suppressed a report
+    used: Mutex<Vec<bool>>,
+}
+
+impl Suppressions {
+    /// Collect `---@diagnostic` comments from the trivia of the AST.
+    pub(crate) fn collect(src: &Arc<SourceInfo>, ast: &ast::Ast) -> Self {
+        let mut directives = Vec::new();
+        for token in ast.nodes().tokens().chain(std::iter::once(ast.eof())) {
+            for trivia in token.leading_trivia().chain(token.trailing_trivia()) {
+                if let Some(directive) = parse_directive(src, trivia) {
+                    directives.push(directive);
+                }
+            }
+        }
+        directives.sort_by_key(|d| d.loc.start().bytes());
+        let used = Mutex::new(vec![false; directives.len()]);
+        Suppressions { directives, used }
+    }
+
+    /// Check if the report is suppressed by a directive. The directive is marked as used.
+    pub(crate) fn suppress(&self, report: &LintReport) -> bool {
+        if report.name() == UNUSED_SUPPRESSION {
+            return false;
+        }
+        let Some(index) = self.find_directive(report) else {
+            return false;
+        };
+        self.used.lock()[index] = true;
+        true
+    }
+
+    fn find_directive(&self, report: &LintReport) -> Option<usize> {
+        let name = report.name();
+        let pos = report.loc().start();
+
+        // Line directives take precedence over regions
+        let line_directive = self.directives.iter().position(|d| {
+            let line = d.loc.start().line();
+            d.applies_to(name)
+                && match d.kind {
+                    DirectiveKind::DisableLine => line == pos.line(),
+                    DirectiveKind::DisableNextLine => line + 1 == pos.line(),
+                    _ => false,
+                }
+        });
+        if line_directive.is_some() {
+            return line_directive;
+        }
+
+        // Replay `disable` and `enable` before the report. The value is the index of the
+        // directive which disabled the diagnostic.
+        let mut all = None;
+        let mut named: HashMap<&str, Option<usize>> = HashMap::new();
+        for (index, d) in self.directives.iter().enumerate() {
+            if d.loc.start().bytes() >= pos.bytes() {
+                break;
+            }
+            let state = match d.kind {
+                DirectiveKind::Disable => Some(index),
+                DirectiveKind::Enable => None,
+                _ => continue,
+            };
+            if d.names.is_empty() {
+                all = state;
+                named.clear();
+            } else {
+                for n in &d.names {
+                    named.insert(n, state);
+                }
+            }
+        }
+        named.get(name).copied().unwrap_or(all)
+    }
+
+    /// Directives which disable diagnostics but have never suppressed a report.
+    pub fn unused(&self) -> Vec<&Directive> {
+        let used = self.used.lock();
+        self.directives
+            .iter()
+            .zip(used.iter())
+            .filter(|(d, used)| d.kind != DirectiveKind::Enable && !**used)
+            .map(|(d, _)| d)
+            .collect()
+    }
+}
+
+/// Parse a comment such as `---@diagnostic disable-next-line: undefined-global, lowercase-global`.
+fn parse_directive(src: &Arc<SourceInfo>, token: &Token) -> Option<Directive> {
+    let TokenType::SingleLineComment { comment } = token.token_type() else {
+        return None;
+    };
+    // `--` is already stripped
+    let rest = comment
+        .as_str()
+        .strip_prefix('-')?
+        .trim_start()
+        .strip_prefix("@diagnostic")?;
+    let (action, names) = rest.split_once(':').unwrap_or((rest, ""));
+    let kind = match action.trim() {
+        "disable" => DirectiveKind::Disable,
+        "enable" => DirectiveKind::Enable,
+        "disable-line" => DirectiveKind::DisableLine,
+        "disable-next-line" => DirectiveKind::DisableNextLine,
+        _ => return None,
+    };
+    let names = names
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_owned)
+        .collect();
+    let loc = Location::new(
+        Arc::clone(src),
+        Position::from_fullmoon_position(token.start_position()),
+        Position::from_fullmoon_position(token.end_position()),
+    );
+    Some(Directive { kind, names, loc })
+}
@@ -0,0 +1,1696 @@
+//! Type inference of expressions and variables.
+//!
+//! Inference is flow-insensitive: the type of a variable is the union of all values assigned to it,
+//! unless it is declared by an annotation (`---@type`, `---@param` or `---@class`). The file is
+//! walked repeatedly until the types of variables stop changing, so that a use sees assignments
+//! which appear later in the file (e.g. in a function called after them).
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use full_moon::{
+    ast::{self, punctuated::Punctuated},
+    tokenizer::TokenType,
+};
+
+use crate::{
+    annotation::{self, Annotation, Annotations, DocBlock, FieldKey, Tag},
+    cfg::ControlFlow,
+    config::Config,
+    library::Member,
+    location::Location,
+    resolver::{NodeId, Resolver},
+    utils,
+    workspace::Imports,
+};
+
+/// Walks over the file are stopped after this number even if types are still changing.
+const MAX_ITERATIONS: usize = 5;
+/// Unions with more members are widened to `unknown`.
+const MAX_UNION_MEMBERS: usize = 8;
+/// Aliases and classes nested deeper are `unknown`, which stops recursive definitions.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LuaType {
+    /// Any value
+    Unknown,
+    Nil,
+    Boolean,
+    Number,
+    /// Integer subtype of numbers since Lua 5.3
+    Integer,
+    String,
+    Table(TableShape),
+    Function(Signature),
+    /// Class declared by `---@class`. Fields are looked up by [Types::field].
+    Class(String),
+    /// Values of any of the types. Members are not unions, and `unknown` is only paired with `nil`.
+    Union(Vec<LuaType>),
+}
+
+/// Fields of a table known from constructors, assignments and annotations
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableShape {
+    pub fields: BTreeMap<String, LuaType>,
+    /// Type of values at integer keys (e.g. elements of arrays)
+    pub items: Option<Box<LuaType>>,
+    /// Types of other keys and their values (e.g. `table<string, T>`). Looking up a key may find
+    /// no value.
+    pub map: Option<Box<(LuaType, LuaType)>>,
+}
+
+impl TableShape {
+    /// Type of the value at a key which is not a field name.
+    fn lookup(&self, key: &LuaType) -> LuaType {
+        match (&self.items, &self.map) {
+            (Some(items), _) if key.is_numeric() => (**items).clone(),
+            (_, Some(map)) => map.1.clone().join(LuaType::Nil),
+            _ => LuaType::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Param>,
+    /// Whether the function accepts `...`
+    pub variadic: bool,
+    /// Types of returned values. `None` if unknown (e.g. functions of the library).
+    pub returns: Option<Vec<LuaType>>,
+    /// Whether more values of unknown types may follow `returns`
+    pub variadic_returns: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// Name of the parameter. Empty for parameters of library functions.
+    pub name: String,
+    pub ty: LuaType,
+    pub optional: bool,
+    /// Definition of the parameter. `None` for parameters of library functions, function types
+    /// of annotations, and the implicit `self` of methods.
+    pub id: Option<NodeId>,
+}
+
+impl LuaType {
+    /// Union of the types. Nested unions are flattened, duplicates are removed, `integer` is
+    /// absorbed by `number`, and types other than `nil` are absorbed by `unknown`.
+    pub fn union(types: impl IntoIterator<Item = LuaType>) -> LuaType {
+        let mut members = Vec::new();
+        for ty in types {
+            let flattened = match ty {
+                LuaType::Union(types) => types,
+                ty => vec![ty],
+            };
+            for ty in flattened {
+                if !members.contains(&ty) {
+                    members.push(ty);
+                }
+            }
+        }
+        if members.contains(&LuaType::Number) {
+            members.retain(|ty| *ty != LuaType::Integer);
+        }
+        if members.contains(&LuaType::Unknown) {
+            members.retain(|ty| matches!(ty, LuaType::Unknown | LuaType::Nil));
+        }
+        match members.len() {
+            0 => LuaType::Unknown,
+            1 => members.pop().unwrap(),
+            n if n > MAX_UNION_MEMBERS => LuaType::Unknown,
+            _ => LuaType::Union(members),
+        }
+    }
+
+    pub fn join(self, other: LuaType) -> LuaType {
+        LuaType::union([self, other])
+    }
+
+    /// Members of a union, or the type itself.
+    pub fn members(&self) -> &[LuaType] {
+        match self {
+            LuaType::Union(members) => members,
+            ty => std::slice::from_ref(ty),
+        }
+    }
+
+    /// Whether a value of the type is known to be `nil` in some cases (e.g. `string|nil` but not
+    /// `unknown`).
+    pub fn may_be_nil(&self) -> bool {
+        self.members().contains(&LuaType::Nil)
+    }
+
+    /// The type without `nil`.
+    pub fn without_nil(&self) -> LuaType {
+        match self {
+            LuaType::Nil => LuaType::Unknown,
+            ty => LuaType::union(
+                ty.members()
+                    .iter()
+                    .filter(|ty| **ty != LuaType::Nil)
+                    .cloned(),
+            ),
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        self.members()
+            .iter()
+            .all(|ty| matches!(ty, LuaType::Number | LuaType::Integer))
+    }
+
+    /// Whether arithmetic on the value may call a metamethod
+    fn may_have_metatable(&self) -> bool {
+        self.members()
+            .iter()
+            .any(|ty| matches!(ty, LuaType::Unknown | LuaType::Table(_) | LuaType::Class(_)))
+    }
+
+    /// Whether the type needs parentheses in `T[]` or in return types.
+    fn is_compound(&self) -> bool {
+        matches!(self, LuaType::Union(_) | LuaType::Function(_))
+    }
+
+    /// The type without definitions of parameters, which refer to nodes of the file where the
+    /// type is inferred.
+    pub(crate) fn detached(&self) -> LuaType {
+        match self {
+            LuaType::Table(shape) => LuaType::Table(TableShape {
+                fields: shape
+                    .fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), ty.detached()))
+                    .collect(),
+                items: shape.items.as_ref().map(|items| Box::new(items.detached())),
+                map: shape
+                    .map
+                    .as_ref()
+                    .map(|map| Box::new((map.0.detached(), map.1.detached()))),
+            }),
+            LuaType::Function(signature) => LuaType::Function(Signature {
+                params: signature
+                    .params
+                    .iter()
+                    .map(|param| Param {
+                        ty: param.ty.detached(),
+                        id: None,
+                        ..param.clone()
+                    })
+                    .collect(),
+                returns: signature
+                    .returns
+                    .as_ref()
+                    .map(|returns| returns.iter().map(LuaType::detached).collect()),
+                ..signature.clone()
+            }),
+            LuaType::Union(members) => {
+                LuaType::Union(members.iter().map(LuaType::detached).collect())
+            }
+            ty => ty.clone(),
+        }
+    }
+}
+
+impl fmt::Display for LuaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LuaType::Unknown => f.write_str("unknown"),
+            LuaType::Nil => f.write_str("nil"),
+            LuaType::Boolean => f.write_str("boolean"),
+            LuaType::Number => f.write_str("number"),
+            LuaType::Integer => f.write_str("integer"),
+            LuaType::String => f.write_str("string"),
+            LuaType::Table(shape) => match (&shape.items, &shape.map) {
+                _ if !shape.fields.is_empty() => {
+                    let fields = shape
+                        .fields
+                        .iter()
+                        .map(|(name, ty)| format!("{name}: {ty}"))
+                        .chain(
+                            shape
+                                .items
+                                .iter()
+                                .map(|items| format!("[integer]: {items}")),
+                        )
+                        .chain(
+                            shape
+                                .map
+                                .iter()
+                                .map(|map| format!("[{}]: {}", map.0, map.1)),
+                        );
+                    write!(f, "{{ {} }}", fields.collect::<Vec<_>>().join(", "))
+                }
+                (None, None) => f.write_str("table"),
+                (Some(items), None) if items.is_compound() => write!(f, "({items})[]"),
+                (Some(items), None) => write!(f, "{items}[]"),
+                (None, Some(map)) => write!(f, "table<{}, {}>", map.0, map.1),
+                (Some(items), Some(map)) => {
+                    write!(f, "{{ [integer]: {items}, [{}]: {} }}", map.0, map.1)
+                }
+            },
+            LuaType::Function(signature) => {
+                let mut params = signature
+                    .params
+                    .iter()
+                    .map(|param| {
+                        let optional = if param.optional { "?" } else { "" };
+                        match param.name.as_str() {
+                            "" => format!("{}{optional}", param.ty),
+                            name => format!("{name}{optional}: {}", param.ty),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if signature.variadic {
+                    params.push("...".to_owned());
+                }
+                write!(f, "fun({})", params.join(", "))?;
+                match &signature.returns {
+                    Some(returns) if !returns.is_empty() => {
+                        let mut returns = returns
+                            .iter()
+                            .map(|ty| match ty {
+                                LuaType::Function(_) => format!("({ty})"),
+                                ty => ty.to_string(),
+                            })
+                            .collect::<Vec<_>>();
+                        if signature.variadic_returns {
+                            returns.push("...".to_owned());
+                        }
+                        write!(f, ": {}", returns.join(", "))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            LuaType::Class(name) => f.write_str(name),
+            LuaType::Union(members) => {
+                let members = members
+                    .iter()
+                    .map(|ty| match ty {
+                        LuaType::Function(_) => format!("({ty})"),
+                        ty => ty.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                f.write_str(&members.join("|"))
+            }
+        }
+    }
+}
+
+/// Inferred types of a file
+#[derive(Debug, Default)]
+pub struct Types {
+    /// Types of expressions by their nodes, and of variables by their definitions
+    types: HashMap<NodeId, LuaType>,
+    /// Fields of classes declared by annotations or assigned to variables of classes
+    classes: HashMap<String, TableShape>,
+    /// Parent classes by class names
+    parents: HashMap<String, Vec<String>>,
+    /// Annotations declaring types of variables by their definitions
+    declarations: HashMap<NodeId, Location>,
+    /// `---@field` annotations by class and field names
+    field_declarations: HashMap<String, BTreeMap<String, Location>>,
+    /// Called values by function calls
+    callees: HashMap<NodeId, LuaType>,
+    /// Type of the `string` library, whose functions are methods of strings
+    string: Option<Box<LuaType>>,
+}
+
+impl Types {
+    pub(crate) fn infer(
+        ast: &ast::Ast,
+        resolver: &Resolver,
+        control_flow: &ControlFlow,
+        annotations: &Annotations,
+        config: &Config,
+        imports: &Imports,
+    ) -> Self {
+        let mut inferrer = Inferrer::new(resolver, control_flow, annotations, config, imports);
+        for _ in 0..MAX_ITERATIONS {
+            inferrer.exprs.clear();
+            inferrer.callees.clear();
+            inferrer.prev = std::mem::take(&mut inferrer.next);
+            inferrer.block(ast.nodes());
+            if inferrer.next == inferrer.prev {
+                break;
+            }
+        }
+
+        let string = inferrer.library.get("string").cloned().map(Box::new);
+        let mut types = inferrer.exprs;
+        types.extend(inferrer.declared);
+        for (def_id, ty) in inferrer.next.vars {
+            types.entry(def_id).or_insert(ty);
+        }
+        let mut classes = inferrer.classes;
+        for (name, fields) in inferrer.next.class_fields {
+            let class = classes.entry(name).or_default();
+            for (field, ty) in fields {
+                class.fields.entry(field).or_insert(ty);
+            }
+        }
+        Types {
+            types,
+            classes,
+            parents: inferrer.parents,
+            declarations: inferrer.declarations,
+            field_declarations: inferrer.field_declarations,
+            callees: inferrer.callees,
+            string,
+        }
+    }
+
+    /// Type of an expression or a variable. The definition of a variable is a local name, a
+    /// parameter, a `local function` statement, or the name of a `function` statement.
+    pub fn get(&self, id: NodeId) -> Option<&LuaType> {
+        self.types.get(&id)
+    }
+
+    /// Fields of a class declared by `---@class`
+    pub fn class(&self, name: &str) -> Option<&TableShape> {
+        self.classes.get(name)
+    }
+
+    /// Type of the field of a value of the type. `unknown` if the field is not known.
+    pub fn field(&self, ty: &LuaType, name: &str) -> LuaType {
+        field_of(ty, name, &self.classes, self.string.as_deref())
+    }
+
+    /// Annotation declaring the type of the variable (`---@type`, `---@param` or `---@class`)
+    pub fn declaration(&self, def_id: NodeId) -> Option<&Location> {
+        self.declarations.get(&def_id)
+    }
+
+    /// `---@field` annotation declaring the field of the class
+    pub fn field_declaration(&self, class: &str, field: &str) -> Option<&Location> {
+        self.field_declarations.get(class)?.get(field)
+    }
+
+    /// Type of the function called by the call. For method calls (`a:f()`), the type of `a.f`.
+    pub fn callee(&self, call: NodeId) -> Option<&LuaType> {
+        self.callees.get(&call)
+    }
+
+    /// Whether a value of the type `value` can be assigned to a variable of the type `target`.
+    /// Types unknown to inference are assignable, and so are numbers to integers since their
+    /// values may be integral.
+    pub fn is_assignable(&self, value: &LuaType, target: &LuaType) -> bool {
+        self.is_assignable_at(value, target, 0)
+    }
+
+    fn is_assignable_at(&self, value: &LuaType, target: &LuaType, depth: usize) -> bool {
+        if depth > MAX_DEPTH {
+            return true;
+        }
+        value.members().iter().all(|value| {
+            target
+                .members()
+                .iter()
+                .any(|target| self.is_member_assignable(value, target, depth))
+        })
+    }
+
+    fn is_member_assignable(&self, value: &LuaType, target: &LuaType, depth: usize) -> bool {
+        match (value, target) {
+            (LuaType::Unknown, _) | (_, LuaType::Unknown) => true,
+            (LuaType::Integer | LuaType::Number, LuaType::Integer | LuaType::Number) => true,
+            (LuaType::Table(value), LuaType::Table(target)) => {
+                self.is_shape_assignable(value, target, depth)
+            }
+            (LuaType::Table(value), LuaType::Class(class)) => self
+                .classes
+                .get(class)
+                .is_none_or(|target| self.is_shape_assignable(value, target, depth)),
+            (LuaType::Class(_), LuaType::Table(_)) => true,
+            (LuaType::Class(value), LuaType::Class(target)) => self.is_subclass(value, target),
+            // Signatures are not compared
+            (LuaType::Function(_), LuaType::Function(_)) => true,
+            (value, target) => value == target,
+        }
+    }
+
+    /// Whether fields of `value` can be assigned to fields of `target`. Fields missing in `value`
+    /// may be assigned later.
+    fn is_shape_assignable(&self, value: &TableShape, target: &TableShape, depth: usize) -> bool {
+        let fields = value.fields.iter().all(|(name, value)| {
+            target
+                .fields
+                .get(name)
+                .is_none_or(|target| self.is_assignable_at(value, target, depth + 1))
+        });
+        let items = match (&value.items, &target.items) {
+            (Some(value), Some(target)) => self.is_assignable_at(value, target, depth + 1),
+            _ => true,
+        };
+        let map = match (&value.map, &target.map) {
+            (Some(value), Some(target)) => self.is_assignable_at(&value.1, &target.1, depth + 1),
+            _ => true,
+        };
+        fields && items && map
+    }
+
+    /// Whether the class is `parent` or inherits it.
+    fn is_subclass(&self, class: &str, parent: &str) -> bool {
+        let mut classes = vec![class];
+        for _ in 0..MAX_DEPTH {
+            if classes.contains(&parent) {
+                return true;
+            }
+            classes = classes
+                .iter()
+                .filter_map(|class| self.parents.get(*class))
+                .flatten()
+                .map(String::as_str)
+                .collect();
+        }
+        false
+    }
+}
+
+fn field_of(
+    ty: &LuaType,
+    name: &str,
+    classes: &HashMap<String, TableShape>,
+    string: Option<&LuaType>,
+) -> LuaType {
+    match ty {
+        LuaType::Table(shape) => match shape.fields.get(name) {
+            Some(ty) => Some(ty.clone()),
+            None => Some(shape.lookup(&LuaType::String)),
+        },
+        LuaType::Class(class) => classes
+            .get(class)
+            .and_then(|shape| shape.fields.get(name).cloned()),
+        LuaType::String => string.map(|string| field_of(string, name, classes, None)),
+        LuaType::Union(members) => Some(LuaType::union(
+            members
+                .iter()
+                .filter(|ty| **ty != LuaType::Nil)
+                .map(|ty| field_of(ty, name, classes, string)),
+        )),
+        _ => None,
+    }
+    .unwrap_or(LuaType::Unknown)
+}
+
+/// Types of variables and fields assigned during a walk over the file
+#[derive(Debug, Default, PartialEq)]
+struct Env {
+    vars: HashMap<NodeId, LuaType>,
+    /// Fields assigned to variables of classes (e.g. `function Class.new() end`)
+    class_fields: HashMap<String, BTreeMap<String, LuaType>>,
+}
+
+struct Inferrer<'a> {
+    resolver: &'a Resolver,
+    control_flow: &'a ControlFlow,
+    annotations: &'a Annotations,
+    config: &'a Config,
+    imports: &'a Imports,
+    integers: bool,
+    /// Types of expressions in the current walk
+    exprs: HashMap<NodeId, LuaType>,
+    /// Types of variables declared by annotations
+    declared: HashMap<NodeId, LuaType>,
+    /// Annotations declaring types of variables
+    declarations: HashMap<NodeId, Location>,
+    /// `---@field` annotations by class and field names
+    field_declarations: HashMap<String, BTreeMap<String, Location>>,
+    /// Parent classes by class names
+    parents: HashMap<String, Vec<String>>,
+    /// Called values by function calls in the current walk
+    callees: HashMap<NodeId, LuaType>,
+    /// Assignments in the previous walk, which are complete
+    prev: Env,
+    /// Assignments in the current walk
+    next: Env,
+    /// Fields of classes declared by `---@field`
+    classes: HashMap<String, TableShape>,
+    aliases: HashMap<String, annotation::Type>,
+    /// Values returned by `return` statements in each enclosing function. `None` if a function
+    /// returns values of unknown number.
+    returns: Vec<Option<Vec<Vec<LuaType>>>>,
+    /// Types of globals of the library by their names
+    library: HashMap<String, LuaType>,
+}
+
+impl<'a> Inferrer<'a> {
+    fn new(
+        resolver: &'a Resolver,
+        control_flow: &'a ControlFlow,
+        annotations: &'a Annotations,
+        config: &'a Config,
+        imports: &'a Imports,
+    ) -> Self {
+        let mut inferrer = Inferrer {
+            resolver,
+            control_flow,
+            annotations,
+            config,
+            imports,
+            integers: config.runtime_version().has_integer_operators(),
+            exprs: HashMap::new(),
+            declared: HashMap::new(),
+            declarations: HashMap::new(),
+            field_declarations: HashMap::new(),
+            parents: HashMap::new(),
+            callees: HashMap::new(),
+            prev: Env::default(),
+            next: Env::default(),
+            classes: HashMap::new(),
+            aliases: HashMap::new(),
+            returns: Vec::new(),
+            library: HashMap::new(),
+        };
+        inferrer.collect_classes();
+        // Functions of the `string` library are methods of strings
+        if let Some(string) = config.library().global("string") {
+            let string = inferrer.library_type(string);
+            inferrer.library.insert("string".to_owned(), string);
+        }
+        // Globals defined by other files are builtin names as well
+        inferrer.library.extend(
+            imports
+                .globals()
+                .iter()
+                .map(|(name, ty)| (name.clone(), ty.clone())),
+        );
+        inferrer
+    }
+
+    /// Collect classes and aliases declared anywhere in the file.
+    fn collect_classes(&mut self) {
+        let mut declared = Vec::new();
+        for block in self.annotations.blocks() {
+            let mut class = None;
+            for annotation in block.annotations() {
+                match annotation.tag() {
+                    Tag::Class { name, parents, .. } => {
+                        class = Some(declared.len());
+                        declared.push((name.clone(), parents.clone(), Vec::new()));
+                    }
+                    Tag::Field(field) => {
+                        if let (Some(class), FieldKey::Name(name)) = (class, &field.key) {
+                            declared[class].2.push((name.clone(), field.clone()));
+                            let (class, _, _) = &declared[class];
+                            self.field_declarations
+                                .entry(class.clone())
+                                .or_default()
+                                .insert(name.clone(), annotation.loc());
+                        }
+                    }
+                    Tag::Alias { name, ty, .. } => {
+                        self.aliases.insert(name.clone(), ty.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Register names first since fields may refer to classes
+        for (name, _, _) in declared.iter() {
+            self.classes.insert(name.clone(), TableShape::default());
+        }
+        let mut parents = HashMap::new();
+        for (name, class_parents, fields) in declared {
+            let parent_names = class_parents.iter().filter_map(|parent| match parent {
+                annotation::Type::Name { name, .. } => Some(name.clone()),
+                _ => None,
+            });
+            self.parents
+                .entry(name.clone())
+                .or_default()
+                .extend(parent_names);
+            let shape = self.classes.get_mut(&name).map(std::mem::take);
+            let mut shape = shape.unwrap_or_default();
+            for (field_name, field) in fields {
+                let mut ty = self.convert(&field.ty, 0);
+                if field.optional {
+                    ty = ty.join(LuaType::Nil);
+                }
+                shape.fields.insert(field_name, ty);
+            }
+            self.classes.insert(name.clone(), shape);
+            parents.insert(name, class_parents);
+        }
+
+        // Inherit fields of parent classes
+        let names = self.classes.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            let mut ancestors = parents.get(&name).cloned().unwrap_or_default();
+            let mut depth = 0;
+            while let Some(parent) = ancestors.pop() {
+                depth += 1;
+                if depth > MAX_DEPTH {
+                    break;
+                }
+                let annotation::Type::Name { name: parent, .. } = parent else {
+                    continue;
+                };
+                let Some(parent_shape) = self.classes.get(&parent).cloned() else {
+                    continue;
+                };
+                let shape = self.classes.get_mut(&name).unwrap();
+                for (field, ty) in parent_shape.fields {
+                    shape.fields.entry(field).or_insert(ty);
+                }
+                ancestors.extend(parents.get(&parent).cloned().unwrap_or_default());
+            }
+        }
+    }
+
+    fn integer(&self) -> LuaType {
+        if self.integers {
+            LuaType::Integer
+        } else {
+            LuaType::Number
+        }
+    }
+
+    /// Convert a type written in an annotation.
+    fn convert(&self, ty: &annotation::Type, depth: usize) -> LuaType {
+        if depth > MAX_DEPTH {
+            return LuaType::Unknown;
+        }
+        let convert = |ty: &annotation::Type| self.convert(ty, depth + 1);
+        match ty {
+            annotation::Type::Name { name, args } => match name.as_str() {
+                "nil" | "void" => LuaType::Nil,
+                "boolean" => LuaType::Boolean,
+                "number" => LuaType::Number,
+                "integer" => self.integer(),
+                "string" => LuaType::String,
+                "table" => {
+                    let mut shape = TableShape::default();
+                    if let [key, value] = args.as_slice() {
+                        let key = convert(key);
+                        if key.is_numeric() {
+                            shape.items = Some(Box::new(convert(value)));
+                        } else {
+                            shape.map = Some(Box::new((key, convert(value))));
+                        }
+                    }
+                    LuaType::Table(shape)
+                }
+                "function" => LuaType::Function(Signature {
+                    params: Vec::new(),
+                    variadic: true,
+                    returns: None,
+                    variadic_returns: false,
+                }),
+                name if self.classes.contains_key(name) => LuaType::Class(name.to_owned()),
+                name => match self.aliases.get(name) {
+                    Some(alias) => convert(alias),
+                    // `any`, `unknown`, `thread`, `userdata`, type parameters and undefined names
+                    None => LuaType::Unknown,
+                },
+            },
+            annotation::Type::Literal(annotation::Literal::String(_)) => LuaType::String,
+            annotation::Type::Literal(annotation::Literal::Number(n)) => {
+                if is_integer_literal(n) {
+                    self.integer()
+                } else {
+                    LuaType::Number
+                }
+            }
+            annotation::Type::Literal(annotation::Literal::Boolean(_)) => LuaType::Boolean,
+            annotation::Type::Array(ty) => LuaType::Table(TableShape {
+                items: Some(Box::new(convert(ty))),
+                ..TableShape::default()
+            }),
+            annotation::Type::Tuple(types) => LuaType::Table(TableShape {
+                items: Some(Box::new(LuaType::union(types.iter().map(convert)))),
+                ..TableShape::default()
+            }),
+            annotation::Type::Table(fields) => {
+                let mut shape = TableShape::default();
+                for field in fields {
+                    let mut ty = convert(&field.ty);
+                    if field.optional {
+                        ty = ty.join(LuaType::Nil);
+                    }
+                    match &field.key {
+                        FieldKey::Name(name) => {
+                            shape.fields.insert(name.clone(), ty);
+                        }
+                        FieldKey::Type(key) => {
+                            let key = convert(key);
+                            if key.is_numeric() {
+                                shape.items = Some(Box::new(ty));
+                            } else {
+                                shape.map = Some(Box::new((key, ty)));
+                            }
+                        }
+                    }
+                }
+                LuaType::Table(shape)
+            }
+            annotation::Type::Function(function) => {
+                let mut params = Vec::new();
+                let mut variadic = false;
+                for param in function.params.iter() {
+                    if param.name == "..." {
+                        variadic = true;
+                        continue;
+                    }
+                    params.push(Param {
+                        name: param.name.clone(),
+                        ty: convert(&param.ty),
+                        optional: param.optional,
+                        id: None,
+                    });
+                }
+                LuaType::Function(Signature {
+                    params,
+                    variadic,
+                    returns: Some(function.returns.iter().map(convert).collect()),
+                    variadic_returns: false,
+                })
+            }
+            annotation::Type::Union(types) => LuaType::union(types.iter().map(convert)),
+            annotation::Type::Optional(ty) => convert(ty).join(LuaType::Nil),
+        }
+    }
+
+    /// Type of a global of the library
+    fn library_type(&self, member: &Member) -> LuaType {
+        match member {
+            Member::Function {
+                min_args,
+                max_args,
+                nilable,
+            } => {
+                let params = (0..max_args.unwrap_or(*min_args))
+                    .map(|i| Param {
+                        name: String::new(),
+                        ty: LuaType::Unknown,
+                        optional: i >= *min_args,
+                        id: None,
+                    })
+                    .collect();
+                // Only the first value of a nilable function is known to be `nil` on failure
+                let returns = nilable.then(|| vec![LuaType::Unknown.join(LuaType::Nil)]);
+                LuaType::Function(Signature {
+                    params,
+                    variadic: max_args.is_none(),
+                    returns,
+                    variadic_returns: true,
+                })
+            }
+            Member::Table { .. } => LuaType::Table(TableShape {
+                fields: member
+                    .fields()
+                    .map(|(name, field)| (name.to_owned(), self.library_type(field)))
+                    .collect(),
+                ..TableShape::default()
+            }),
+            Member::Value => LuaType::Unknown,
+        }
+    }
+
+    /// Type of the variable used by the node.
+    fn variable(&mut self, use_id: NodeId, name: &str) -> LuaType {
+        let Some(def_id) = self.resolver.lookup_definiton(use_id) else {
+            return LuaType::Unknown;
+        };
+        if self.resolver.is_builtin(def_id) {
+            if let Some(ty) = self.library.get(name) {
+                return ty.clone();
+            }
+            let ty = match self.config.library().global(name) {
+                Some(member) => self.library_type(member),
+                None => LuaType::Unknown,
+            };
+            self.library.insert(name.to_owned(), ty.clone());
+            return ty;
+        }
+        self.declared
+            .get(&def_id)
+            .or_else(|| self.prev.vars.get(&def_id))
+            .or_else(|| self.next.vars.get(&def_id))
+            .cloned()
+            .unwrap_or(LuaType::Unknown)
+    }
+
+    /// Assign a value to the variable. Variables declared by annotations keep their types.
+    fn assign(&mut self, def_id: NodeId, ty: LuaType) {
+        if self.declared.contains_key(&def_id) {
+            return;
+        }
+        let ty = match self.next.vars.remove(&def_id) {
+            Some(prev) => prev.join(ty),
+            None => ty,
+        };
+        self.next.vars.insert(def_id, ty);
+    }
+
+    fn field(&self, ty: &LuaType, name: &str) -> LuaType {
+        if let LuaType::Class(class) = ty {
+            let assigned = self
+                .prev
+                .class_fields
+                .get(class)
+                .or_else(|| self.next.class_fields.get(class))
+                .and_then(|fields| fields.get(name));
+            if let Some(ty) = assigned {
+                return ty.clone();
+            }
+        }
+        let string = self.library.get("string");
+        field_of(ty, name, &self.classes, string)
+    }
+
+    fn block(&mut self, block: &ast::Block) {
+        for stmt in block.stmts() {
+            self.stmt(stmt);
+        }
+        if let Some(ast::LastStmt::Return(ret)) = block.last_stmt() {
+            let (values, open) = self.values(ret.returns(), None);
+            // The number of returned values is unknown if a `return` has open values
+            if let Some(returns) = self.returns.last_mut() {
+                match returns {
+                    Some(returns) if !open => returns.push(values),
+                    _ => *returns = None,
+                }
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &ast::Stmt) {
+        let doc = self.annotations.for_statement(stmt);
+        match stmt {
+            ast::Stmt::LocalAssignment(node) => {
+                let type_annotation = doc.and_then(type_annotation);
+                let declared = match type_annotation.map(Annotation::tag) {
+                    Some(Tag::Type(types)) => types.as_slice(),
+                    _ => &[],
+                };
+                let class = doc.and_then(|doc| {
+                    doc.annotations().iter().find_map(|a| match a.tag() {
+                        Tag::Class { name, .. } => Some((name.clone(), a.loc())),
+                        _ => None,
+                    })
+                });
+                let (values, open) =
+                    self.values(node.expressions(), doc.filter(|_| class.is_none()));
+                for (i, name) in node.names().iter().enumerate() {
+                    let def_id = NodeId::from(name);
+                    if let Some(ty) = declared.get(i) {
+                        let ty = self.convert(ty, 0);
+                        self.declared.insert(def_id, ty);
+                        if let Some(annotation) = type_annotation {
+                            self.declarations.insert(def_id, annotation.loc());
+                        }
+                    } else if let (0, Some((class, loc))) = (i, &class) {
+                        self.declared.insert(def_id, LuaType::Class(class.clone()));
+                        self.declarations.insert(def_id, loc.clone());
+                    } else {
+                        self.assign(def_id, value_at(&values, open, i));
+                    }
+                }
+            }
+            ast::Stmt::Assignment(node) => {
+                let declared = match doc.and_then(type_annotation).map(Annotation::tag) {
+                    Some(Tag::Type(types)) => types.as_slice(),
+                    _ => &[],
+                };
+                let (values, open) = self.values(node.expressions(), doc);
+                for (i, var) in node.variables().iter().enumerate() {
+                    let ty = match declared.get(i) {
+                        Some(ty) => self.convert(ty, 0),
+                        None => value_at(&values, open, i),
+                    };
+                    match var {
+                        ast::Var::Name(_) => {
+                            if let Some(def_id) = self.resolver.lookup_definiton(NodeId::from(var))
+                            {
+                                self.assign(def_id, ty);
+                            }
+                        }
+                        ast::Var::Expression(var) => {
+                            let suffixes = var.suffixes().collect::<Vec<_>>();
+                            let prefix = self.prefix(var.prefix());
+                            let Some((last, init)) = suffixes.split_last() else {
+                                continue;
+                            };
+                            self.suffixes(prefix, init);
+                            match (var.prefix(), init, last) {
+                                (
+                                    ast::Prefix::Name(_),
+                                    [],
+                                    ast::Suffix::Index(ast::Index::Dot { name, .. }),
+                                ) => {
+                                    let def_id =
+                                        self.resolver.lookup_definiton(NodeId::from(var.prefix()));
+                                    if let Some(def_id) = def_id {
+                                        self.assign_field(def_id, utils::ident_as_str(name), ty);
+                                    }
+                                }
+                                (
+                                    _,
+                                    _,
+                                    ast::Suffix::Index(ast::Index::Brackets { expression, .. }),
+                                ) => {
+                                    self.expr(expression);
+                                }
+                                _ => {}
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ast::Stmt::FunctionCall(call) => {
+                self.call(call);
+            }
+            ast::Stmt::FunctionDeclaration(node) => {
+                let mut ty = self.function(node.body(), doc);
+                let name = node.name();
+                // Methods have the implicit `self` parameter
+                if let (Some(table), Some(_), LuaType::Function(signature)) =
+                    (name.names().iter().next(), name.method_name(), &mut ty)
+                {
+                    let table = self.variable(NodeId::from(table), utils::ident_as_str(table));
+                    signature.params.insert(
+                        0,
+                        Param {
+                            name: "self".to_owned(),
+                            ty: table,
+                            optional: false,
+                            id: None,
+                        },
+                    );
+                }
+                let names = name
+                    .names()
+                    .iter()
+                    .chain(name.method_name())
+                    .collect::<Vec<_>>();
+                match names.as_slice() {
+                    [_] => {
+                        if let Some(def_id) = self.resolver.lookup_definiton(NodeId::from(name)) {
+                            self.assign(def_id, ty);
+                        }
+                    }
+                    [table, field] => {
+                        if let Some(def_id) = self.resolver.lookup_definiton(NodeId::from(table)) {
+                            self.assign_field(def_id, utils::ident_as_str(field), ty);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            ast::Stmt::LocalFunction(node) => {
+                let ty = self.function(node.body(), doc);
+                self.assign(NodeId::from(node), ty);
+            }
+            ast::Stmt::Do(node) => self.block(node.block()),
+            ast::Stmt::While(node) => {
+                self.expr(node.condition());
+                self.block(node.block());
+            }
+            ast::Stmt::Repeat(node) => {
+                self.block(node.block());
+                self.expr(node.until());
+            }
+            ast::Stmt::If(node) => {
+                self.expr(node.condition());
+                self.block(node.block());
+                for else_if in node.else_if().into_iter().flatten() {
+                    self.expr(else_if.condition());
+                    self.block(else_if.block());
+                }
+                if let Some(block) = node.else_block() {
+                    self.block(block);
+                }
+            }
+            ast::Stmt::NumericFor(node) => {
+                let mut ty = self.expr(node.start());
+                ty = ty.join(self.expr(node.end()));
+                if let Some(step) = node.step() {
+                    ty = ty.join(self.expr(step));
+                }
+                let ty = if ty == self.integer() {
+                    ty
+                } else {
+                    LuaType::Number
+                };
+                self.assign(NodeId::from(node.index_variable()), ty);
+                self.block(node.block());
+            }
+            ast::Stmt::GenericFor(node) => {
+                let exprs = node.expressions().iter().collect::<Vec<_>>();
+                let mut values = vec![LuaType::Unknown; node.names().len()];
+                if let [ast::Expression::FunctionCall(call)] = exprs.as_slice() {
+                    if let Some(items) = self.ipairs_items(call) {
+                        values = vec![self.integer(), items];
+                    }
+                }
+                for expr in exprs.iter() {
+                    self.expr(expr);
+                }
+                for (i, name) in node.names().iter().enumerate() {
+                    let ty = values.get(i).cloned().unwrap_or(LuaType::Unknown);
+                    self.assign(NodeId::from(name), ty);
+                }
+                self.block(node.block());
+            }
+            _ => {}
+        }
+    }
+
+    /// Assign a value to a field of the variable
+    fn assign_field(&mut self, def_id: NodeId, name: &str, ty: LuaType) {
+        let prefix_declared = self.declared.get(&def_id).cloned();
+        if let Some(LuaType::Class(class)) = prefix_declared {
+            let declared = self.classes.get(&class);
+            if declared.is_some_and(|shape| shape.fields.contains_key(name)) {
+                return;
+            }
+            let fields = self.next.class_fields.entry(class).or_default();
+            let ty = match fields.remove(name) {
+                Some(prev) => prev.join(ty),
+                None => ty,
+            };
+            fields.insert(name.to_owned(), ty);
+        } else if let Some(LuaType::Table(shape)) = self.next.vars.get_mut(&def_id) {
+            let ty = match shape.fields.remove(name) {
+                Some(prev) => prev.join(ty),
+                None => ty,
+            };
+            shape.fields.insert(name.to_owned(), ty);
+        }
+    }
+
+    /// Type of elements of `t` in `ipairs(t)`
+    fn ipairs_items(&mut self, call: &ast::FunctionCall) -> Option<LuaType> {
+        let ast::Prefix::Name(name) = call.prefix() else {
+            return None;
+        };
+        let def_id = self
+            .resolver
+            .lookup_definiton(NodeId::from(call.prefix()))?;
+        if utils::ident_as_str(name) != "ipairs" || !self.resolver.is_builtin(def_id) {
+            return None;
+        }
+        let Some(ast::Suffix::Call(ast::Call::AnonymousCall(ast::FunctionArgs::Parentheses {
+            arguments,
+            ..
+        }))) = call.suffixes().next()
+        else {
+            return None;
+        };
+        let table = self.expr(arguments.iter().next()?);
+        match table {
+            LuaType::Table(TableShape {
+                items: Some(items), ..
+            }) => Some(*items),
+            _ => Some(LuaType::Unknown),
+        }
+    }
+
+    /// Types of the values of an expression list. The last expression may have several values.
+    /// Returns whether the number of values is unknown (e.g. `...` or a call of a library
+    /// function).
+    fn values(
+        &mut self,
+        exprs: &Punctuated<ast::Expression>,
+        doc: Option<&DocBlock>,
+    ) -> (Vec<LuaType>, bool) {
+        let mut values = Vec::new();
+        let len = exprs.len();
+        for (i, expr) in exprs.iter().enumerate() {
+            if i + 1 < len {
+                values.push(self.expr_with_doc(expr, doc));
+                continue;
+            }
+            match expr {
+                ast::Expression::FunctionCall(call) => {
+                    let (returns, open) = self.call(call);
+                    self.exprs
+                        .insert(NodeId::from(expr), first_value(&returns, open));
+                    values.extend(returns);
+                    return (values, open);
+                }
+                ast::Expression::Symbol(token) if token.token().to_string() == "..." => {
+                    self.expr(expr);
+                    return (values, true);
+                }
+                expr => values.push(self.expr_with_doc(expr, doc)),
+            }
+        }
+        (values, false)
+    }
+
+    /// Type of an expression. Annotations of the statement apply to function expressions.
+    fn expr_with_doc(&mut self, expr: &ast::Expression, doc: Option<&DocBlock>) -> LuaType {
+        match expr {
+            ast::Expression::Function((_, body)) if doc.is_some() => {
+                let ty = self.function(body, doc);
+                self.exprs.insert(NodeId::from(expr), ty.clone());
+                ty
+            }
+            expr => self.expr(expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &ast::Expression) -> LuaType {
+        let ty = match expr {
+            ast::Expression::Symbol(token) => match token.token().to_string().as_str() {
+                "nil" => LuaType::Nil,
+                "true" | "false" => LuaType::Boolean,
+                _ => LuaType::Unknown,
+            },
+            ast::Expression::Number(token) => {
+                if is_integer_literal(&token.token().to_string()) {
+                    self.integer()
+                } else {
+                    LuaType::Number
+                }
+            }
+            ast::Expression::String(_) => LuaType::String,
+            ast::Expression::TableConstructor(table) => self.table(table),
+            ast::Expression::Function((_, body)) => self.function(body, None),
+            ast::Expression::FunctionCall(call) => {
+                let (returns, open) = self.call(call);
+                first_value(&returns, open)
+            }
+            ast::Expression::Var(var) => self.var(var),
+            ast::Expression::Parentheses { expression, .. } => self.expr(expression),
+            ast::Expression::UnaryOperator { unop, expression } => {
+                let operand = self.expr(expression);
+                match unop {
+                    ast::UnOp::Minus(_) if operand.is_numeric() => operand,
+                    ast::UnOp::Minus(_) if operand.may_have_metatable() => LuaType::Unknown,
+                    ast::UnOp::Minus(_) => LuaType::Number,
+                    ast::UnOp::Not(_) => LuaType::Boolean,
+                    ast::UnOp::Hash(_) if operand.may_have_metatable() => LuaType::Unknown,
+                    ast::UnOp::Hash(_) | ast::UnOp::Tilde(_) => self.integer(),
+                    _ => LuaType::Unknown,
+                }
+            }
+            ast::Expression::BinaryOperator { lhs, binop, rhs } => {
+                let lhs = self.expr(lhs);
+                let rhs = self.expr(rhs);
+                self.binary(binop, lhs, rhs)
+            }
+            _ => LuaType::Unknown,
+        };
+        self.exprs.insert(NodeId::from(expr), ty.clone());
+        ty
+    }
+
+    fn binary(&self, binop: &ast::BinOp, lhs: LuaType, rhs: LuaType) -> LuaType {
+        let metamethod = lhs.may_have_metatable() || rhs.may_have_metatable();
+        match binop {
+            ast::BinOp::And(_) => {
+                // `lhs` is the result if it is falsy
+                let falsy = lhs
+                    .members()
+                    .iter()
+                    .filter(|ty| matches!(ty, LuaType::Nil | LuaType::Boolean | LuaType::Unknown))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                LuaType::union(falsy.into_iter().chain([rhs]))
+            }
+            // `lhs` is the result unless it is falsy
+            ast::BinOp::Or(_) if lhs == LuaType::Nil => rhs,
+            ast::BinOp::Or(_) => lhs.without_nil().join(rhs),
+            ast::BinOp::TwoEqual(_)
+            | ast::BinOp::TildeEqual(_)
+            | ast::BinOp::LessThan(_)
+            | ast::BinOp::LessThanEqual(_)
+            | ast::BinOp::GreaterThan(_)
+            | ast::BinOp::GreaterThanEqual(_) => LuaType::Boolean,
+            _ if metamethod => LuaType::Unknown,
+            ast::BinOp::TwoDots(_) => LuaType::String,
+            ast::BinOp::Plus(_)
+            | ast::BinOp::Minus(_)
+            | ast::BinOp::Star(_)
+            | ast::BinOp::Percent(_)
+            | ast::BinOp::DoubleSlash(_) => {
+                if lhs == LuaType::Integer && rhs == LuaType::Integer {
+                    LuaType::Integer
+                } else {
+                    LuaType::Number
+                }
+            }
+            ast::BinOp::Slash(_) | ast::BinOp::Caret(_) => LuaType::Number,
+            ast::BinOp::Ampersand(_)
+            | ast::BinOp::Pipe(_)
+            | ast::BinOp::Tilde(_)
+            | ast::BinOp::DoubleLessThan(_)
+            | ast::BinOp::DoubleGreaterThan(_) => self.integer(),
+            _ => LuaType::Unknown,
+        }
+    }
+
+    fn table(&mut self, table: &ast::TableConstructor) -> LuaType {
+        let mut shape = TableShape::default();
+        let mut items = Vec::new();
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        for field in table.fields() {
+            match field {
+                ast::Field::NameKey { key, value, .. } => {
+                    let ty = self.expr(value);
+                    shape.fields.insert(utils::ident_as_str(key).to_owned(), ty);
+                }
+                ast::Field::ExpressionKey { key, value, .. } => {
+                    let key_ty = self.expr(key);
+                    let ty = self.expr(value);
+                    match string_literal(key) {
+                        Some(name) => {
+                            shape.fields.insert(name.to_owned(), ty);
+                        }
+                        None if key_ty.is_numeric() => items.push(ty),
+                        None => {
+                            keys.push(key_ty);
+                            values.push(ty);
+                        }
+                    }
+                }
+                ast::Field::NoKey(value) => items.push(self.expr(value)),
+                _ => {}
+            }
+        }
+        if !items.is_empty() {
+            shape.items = Some(Box::new(LuaType::union(items)));
+        }
+        if !keys.is_empty() {
+            shape.map = Some(Box::new((LuaType::union(keys), LuaType::union(values))));
+        }
+        LuaType::Table(shape)
+    }
+
+    fn function(&mut self, body: &ast::FunctionBody, doc: Option<&DocBlock>) -> LuaType {
+        let mut declared_params = HashMap::new();
+        let mut declared_returns: Option<Vec<LuaType>> = None;
+        for annotation in doc.map(DocBlock::annotations).unwrap_or_default() {
+            match annotation.tag() {
+                Tag::Param(param) => {
+                    declared_params.insert(param.name.as_str(), (param, annotation.loc()));
+                }
+                Tag::Return(types) => declared_returns
+                    .get_or_insert_with(Vec::new)
+                    .extend(types.iter().map(|ty| self.convert(ty, 0))),
+                _ => {}
+            }
+        }
+
+        let mut params = Vec::new();
+        let mut variadic = false;
+        for param in body.parameters() {
+            match param {
+                ast::Parameter::Name(name) => {
+                    let name = utils::ident_as_str(name);
+                    let id = NodeId::from(param);
+                    let (mut ty, optional) = match declared_params.get(name) {
+                        Some((declared, loc)) => {
+                            self.declarations.insert(id, loc.clone());
+                            (self.convert(&declared.ty, 0), declared.optional)
+                        }
+                        None => (LuaType::Unknown, false),
+                    };
+                    if optional {
+                        ty = ty.join(LuaType::Nil);
+                    }
+                    self.declared.insert(id, ty.clone());
+                    params.push(Param {
+                        name: name.to_owned(),
+                        ty,
+                        optional,
+                        id: Some(id),
+                    });
+                }
+                ast::Parameter::Ellipse(_) => variadic = true,
+                _ => {}
+            }
+        }
+
+        self.returns.push(Some(Vec::new()));
+        self.block(body.block());
+        let returned = self.returns.pop().flatten();
+        let returns = match declared_returns {
+            Some(returns) => Some(returns),
+            None => self.returned_types(body, returned),
+        };
+        LuaType::Function(Signature {
+            params,
+            variadic,
+            returns,
+            variadic_returns: false,
+        })
+    }
+
+    /// Types of values returned by a function, where each item of `returned` is the values of a
+    /// `return` statement.
+    fn returned_types(
+        &self,
+        body: &ast::FunctionBody,
+        returned: Option<Vec<Vec<LuaType>>>,
+    ) -> Option<Vec<LuaType>> {
+        let returned = returned?;
+        let falls_through = self
+            .control_flow
+            .graph(NodeId::from(body))
+            .is_none_or(|cfg| cfg.falls_through());
+        let count = returned.iter().map(Vec::len).max().unwrap_or(0);
+        let returns = (0..count)
+            .map(|i| {
+                let values = returned
+                    .iter()
+                    .map(|values| values.get(i).cloned().unwrap_or(LuaType::Nil));
+                let ty = LuaType::union(values);
+                if falls_through {
+                    ty.join(LuaType::Nil)
+                } else {
+                    ty
+                }
+            })
+            .collect();
+        Some(returns)
+    }
+
+    fn var(&mut self, var: &ast::Var) -> LuaType {
+        match var {
+            ast::Var::Name(name) => self.variable(NodeId::from(var), utils::ident_as_str(name)),
+            ast::Var::Expression(var) => {
+                let prefix = self.prefix(var.prefix());
+                let suffixes = var.suffixes().collect::<Vec<_>>();
+                self.suffixes(prefix, &suffixes)
+            }
+            _ => LuaType::Unknown,
+        }
+    }
+
+    fn prefix(&mut self, prefix: &ast::Prefix) -> LuaType {
+        match prefix {
+            ast::Prefix::Name(name) => {
+                let ty = self.variable(NodeId::from(prefix), utils::ident_as_str(name));
+                self.exprs.insert(NodeId::from(prefix), ty.clone());
+                ty
+            }
+            ast::Prefix::Expression(expr) => self.expr(expr),
+            _ => LuaType::Unknown,
+        }
+    }
+
+    /// Type of the value after applying indexes and calls to the value of `ty`. The value after
+    /// each suffix is recorded by the suffix.
+    fn suffixes(&mut self, mut ty: LuaType, suffixes: &[&ast::Suffix]) -> LuaType {
+        for suffix in suffixes {
+            let (returns, open) = self.suffix(&ty, suffix);
+            ty = first_value(&returns, open);
+            self.exprs.insert(NodeId::from(*suffix), ty.clone());
+        }
+        ty
+    }
+
+    /// Values after applying a suffix. Indexes have one value and calls have returned values.
+    fn suffix(&mut self, ty: &LuaType, suffix: &ast::Suffix) -> (Vec<LuaType>, bool) {
+        match suffix {
+            ast::Suffix::Index(ast::Index::Dot { name, .. }) => {
+                (vec![self.field(ty, utils::ident_as_str(name))], false)
+            }
+            ast::Suffix::Index(ast::Index::Brackets { expression, .. }) => {
+                let key = self.expr(expression);
+                let value = match (string_literal(expression), ty) {
+                    (Some(name), ty) => self.field(ty, name),
+                    (None, LuaType::Table(shape)) => shape.lookup(&key),
+                    _ => LuaType::Unknown,
+                };
+                (vec![value], false)
+            }
+            ast::Suffix::Call(ast::Call::AnonymousCall(args)) => {
+                self.args(args);
+                returns_of(ty)
+            }
+            ast::Suffix::Call(ast::Call::MethodCall(method)) => {
+                let method_ty = self.field(ty, utils::ident_as_str(method.name()));
+                self.args(method.args());
+                returns_of(&method_ty)
+            }
+            _ => (Vec::new(), true),
+        }
+    }
+
+    fn args(&mut self, args: &ast::FunctionArgs) -> Vec<LuaType> {
+        match args {
+            ast::FunctionArgs::Parentheses { arguments, .. } => {
+                arguments.iter().map(|arg| self.expr(arg)).collect()
+            }
+            ast::FunctionArgs::String(_) => vec![LuaType::String],
+            ast::FunctionArgs::TableConstructor(table) => vec![self.table(table)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Values returned by the call.
+    fn call(&mut self, call: &ast::FunctionCall) -> (Vec<LuaType>, bool) {
+        // `setmetatable(t, mt)` returns `t`
+        if let ast::Prefix::Name(name) = call.prefix() {
+            let def_id = self.resolver.lookup_definiton(NodeId::from(call.prefix()));
+            let suffixes = call.suffixes().collect::<Vec<_>>();
+            if utils::ident_as_str(name) == "setmetatable"
+                && def_id.is_some_and(|def_id| self.resolver.is_builtin(def_id))
+                && suffixes.len() == 1
+            {
+                if let ast::Suffix::Call(ast::Call::AnonymousCall(args)) = suffixes[0] {
+                    let args = self.args(args);
+                    if let Some(table) = args.into_iter().next() {
+                        return (vec![table], false);
+                    }
+                }
+            }
+        }
+
+        let prefix = self.prefix(call.prefix());
+        let suffixes = call.suffixes().collect::<Vec<_>>();
+        let Some((last, init)) = suffixes.split_last() else {
+            return (vec![prefix], false);
+        };
+        let callee = self.suffixes(prefix, init);
+        let function = match last {
+            ast::Suffix::Call(ast::Call::MethodCall(method)) => {
+                self.field(&callee, utils::ident_as_str(method.name()))
+            }
+            _ => callee.clone(),
+        };
+        self.callees.insert(NodeId::from(call), function);
+        let (mut returns, mut open) = self.suffix(&callee, last);
+        // `require` returns the value returned by the module
+        if let Some(module) = self.imports.module(NodeId::from(call)) {
+            returns = vec![module.returned().clone()];
+            open = false;
+        }
+        self.exprs
+            .insert(NodeId::from(*last), first_value(&returns, open));
+        (returns, open)
+    }
+}
+
+/// `---@type` annotation on a statement
+fn type_annotation(doc: &DocBlock) -> Option<&Annotation> {
+    doc.annotations()
+        .iter()
+        .find(|annotation| matches!(annotation.tag(), Tag::Type(_)))
+}
+
+/// Values returned by calling a value of the type
+fn returns_of(ty: &LuaType) -> (Vec<LuaType>, bool) {
+    match ty {
+        LuaType::Function(Signature {
+            returns: Some(returns),
+            variadic_returns,
+            ..
+        }) => (returns.clone(), *variadic_returns),
+        _ => (Vec::new(), true),
+    }
+}
+
+/// The first value of a list, which is `nil` if the list is empty.
+fn first_value(values: &[LuaType], open: bool) -> LuaType {
+    value_at(values, open, 0)
+}
+
+/// The value at the index of a list adjusted to a number of values as in assignments.
+fn value_at(values: &[LuaType], open: bool, index: usize) -> LuaType {
+    match values.get(index) {
+        Some(ty) => ty.clone(),
+        None if open => LuaType::Unknown,
+        None => LuaType::Nil,
+    }
+}
+
+fn string_literal(expr: &ast::Expression) -> Option<&str> {
+    let ast::Expression::String(token) = expr else {
+        return None;
+    };
+    match token.token_type() {
+        TokenType::StringLiteral { literal, .. } => Some(literal.as_str()),
+        _ => None,
+    }
+}
+
+/// Whether a numeric literal is an integer (e.g. `1` or `0xff` but not `1.0` or `1e3`).
+fn is_integer_literal(text: &str) -> bool {
+    let text = text.to_ascii_lowercase();
+    match text.strip_prefix("0x") {
+        Some(hex) => !hex.contains(['.', 'p']),
+        None => !text.contains(['.', 'e']),
+    }
+}
+
+#[cfg(test)]
+fn definition_types(code: &str) -> BTreeMap<String, String> {
+    use std::sync::Arc;
+
+    let src = Arc::new(crate::location::SourceInfo::new(
+        "test.lua".to_owned(),
+        code.to_owned(),
+    ));
+    let Ok(ast) = crate::parse(Arc::clone(&src)) else {
+        panic!("failed to parse: {code}");
+    };
+    let mut ctx = crate::Context::new(src);
+    ctx.analyze(&ast);
+    ctx.resolver()
+        .definitions()
+        .filter(|(def_id, _)| !ctx.resolver().is_builtin(*def_id))
+        .map(|(def_id, def)| {
+            let ty = ctx.types().get(def_id).unwrap_or(&LuaType::Unknown);
+            (def.name().to_owned(), ty.to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn test_infer() {
+    let types = definition_types(
+        r#"
+local i, f, s, b = 1, 1.5, "s", true
+local sum = i + i
+local quot = i / i
+local len = #s
+local concat = s .. i
+local maybe = b and i
+local default = maybe or "x"
+local t = { x = 1, ["y"] = "y" }
+t.z = false
+local arr = { 1, 2, 3 }
+local first = arr[1]
+local field = t.x
+local n = nil
+n = 1
+"#,
+    );
+    assert_eq!(types["i"], "integer");
+    assert_eq!(types["f"], "number");
+    assert_eq!(types["s"], "string");
+    assert_eq!(types["b"], "boolean");
+    assert_eq!(types["sum"], "integer");
+    assert_eq!(types["quot"], "number");
+    assert_eq!(types["len"], "integer");
+    assert_eq!(types["concat"], "string");
+    assert_eq!(types["maybe"], "boolean|integer");
+    assert_eq!(types["default"], "boolean|integer|string");
+    assert_eq!(types["t"], "{ x: integer, y: string, z: boolean }");
+    assert_eq!(types["arr"], "integer[]");
+    assert_eq!(types["first"], "integer");
+    assert_eq!(types["field"], "integer");
+    assert_eq!(types["n"], "nil|integer");
+}
+
+#[test]
+fn test_infer_functions() {
+    let types = definition_types(
+        r#"
+local function add(a, b)
+    return a + b
+end
+
+local function find(xs, x)
+    for i, v in ipairs(xs) do
+        if v == x then
+            return i
+        end
+    end
+end
+
+---@param name string
+---@param greeting? string
+---@return string
+local function greet(name, greeting)
+    return (greeting or "Hello") .. name
+end
+
+local function make()
+    return { count = 0 }, "made"
+end
+
+local obj, msg = make()
+local count = obj.count
+local cache = nil
+local function cached()
+    return cache
+end
+cache = "x"
+local pos = find({}, 1)
+local str = string.format("%d", 1)
+local upper = ("s"):upper()
+"#,
+    );
+    assert_eq!(types["add"], "fun(a: unknown, b: unknown): unknown");
+    assert_eq!(types["find"], "fun(xs: unknown, x: unknown): integer|nil");
+    assert_eq!(
+        types["greet"],
+        "fun(name: string, greeting?: string|nil): string"
+    );
+    assert_eq!(types["name"], "string");
+    assert_eq!(types["make"], "fun(): { count: integer }, string");
+    assert_eq!(types["obj"], "{ count: integer }");
+    assert_eq!(types["msg"], "string");
+    assert_eq!(types["count"], "integer");
+    assert_eq!(types["cached"], "fun(): nil|string");
+    assert_eq!(types["pos"], "integer|nil");
+    assert_eq!(types["str"], "unknown");
+    assert_eq!(types["upper"], "unknown");
+}
+
+#[test]
+fn test_infer_annotations() {
+    let types = definition_types(
+        r#"
+---@class Point
+---@field x number
+---@field y number
+local Point = {}
+
+---@alias Id integer|string
+
+---@type Id
+local id = 1
+
+---@type Point[]
+local points = {}
+
+---@param p Point
+function Point.norm(p)
+    return p.x
+end
+
+local point = points[1]
+local x = point.x
+local norm = Point.norm
+"#,
+    );
+    assert_eq!(types["Point"], "Point");
+    assert_eq!(types["id"], "integer|string");
+    assert_eq!(types["points"], "Point[]");
+    assert_eq!(types["point"], "Point");
+    assert_eq!(types["x"], "number");
+    assert_eq!(types["p"], "Point");
+}
@@ -7,62 +7,6 @@ use num_rational::Ratio;
 use num_traits::Num;
 use num_traits::ToPrimitive;
 
-// Basic functions
-// https://www.lua.org/manual/5.4/manual.html#6.1
-// https://github.com/LuaLS/lua-language-server/blob/ba8f90eb0fab18ce8aee2bdbf7007dc63050381d/doc/en-us/config.md?plain=1#L1714
-const BUILTIN_FUNCTIONS: [&str; 24] = [
-    "assert",
-    "collectgarbage",
-    "dofile",
-    "error",
-    "getmetatable",
-    "ipairs",
-    "load",
-    "loadfile",
-    "next",
-    "pairs",
-    "pcall",
-    "print",
-    "rawequal",
-    "rawget",
-    "rawlen",
-    "rawset",
-    "select",
-    "setmetatable",
-    "tonumber",
-    "tostring",
-    "type",
-    "warn",
-    "xpcall",
-    // FIXME: should be here?
-    "require",
-];
-
-const BUILTIN_VARIABLES: [&str; 4] = [
-    "_VERSION", // FIXME: _ENV should be here?
-    "_ENV", "_G", "arg",
-];
-
-const STDLIB_MODULES: [&str; 9] = [
-    "coroutine",
-    "debug",
-    "io",
-    "math",
-    "os",
-    "package",
-    "string",
-    "table",
-    "utf8",
-];
-
-pub fn builtin_names() -> Vec<&'static str> {
-    let mut names = Vec::new();
-    names.extend_from_slice(&BUILTIN_FUNCTIONS);
-    names.extend_from_slice(&BUILTIN_VARIABLES);
-    names.extend_from_slice(&STDLIB_MODULES);
-    names
-}
-
 pub(super) fn ident_as_str(token: &TokenReference) -> &str {
     match token.token_type() {
         TokenType::Identifier { identifier } => identifier.as_str(),
@@ -120,68 +64,55 @@ fn parse_lua_number(e: &str) -> Option<Ratio<BigInt>> {
     let e = e.to_ascii_lowercase();
 
     // Check and strip hex, binary, octal prefix
-    let (e, radix): (_, u32) = if e.starts_with("0x") {
-        (&e[2..], 16)
-    } else if e.starts_with("0b") {
-        (&e[2..], 2)
-    } else if e.starts_with("0o") {
-        (&e[2..], 8)
+    let (e, radix): (_, u32) = if let Some(e) = e.strip_prefix("0x") {
+        (e, 16)
+    } else if let Some(e) = e.strip_prefix("0b") {
+        (e, 2)
+    } else if let Some(e) = e.strip_prefix("0o") {
+        (e, 8)
     } else {
-        (&e, 10)
+        (e.as_str(), 10)
     };
 
     // Retrieve exponent part if any. e.g. p+0, e-9
     let (e, mantissa) = if let Some(pos) = e.find("p+") {
         let n = &e[pos + 2..];
         let exp = BigUint::from_str_radix(n, radix).unwrap();
-        let Some(exp) = exp.to_u32() else {
-            return None;
-        };
+        let exp = exp.to_u32()?;
         let v = BigUint::from(2_u32).pow(exp);
         (&e[..pos], Some(Ratio::new(v.into(), BigInt::from(1))))
     } else if let Some(pos) = e.find("p-") {
         let n = &e[pos + 2..];
-        let Some(exp) = BigUint::from_str_radix(n, radix).unwrap().to_u32() else {
-            return None;
-        };
+        let exp = BigUint::from_str_radix(n, radix).unwrap().to_u32()?;
         let v = BigUint::from(2_u32).pow(exp);
         (&e[..pos], Some(Ratio::new(BigInt::from(1), v.into())))
     } else if let Some(pos) = e.find("p") {
         let n = &e[pos + 1..];
-        let Some(exp) = BigUint::from_str_radix(n, radix).unwrap().to_u32() else {
-            return None;
-        };
+        let exp = BigUint::from_str_radix(n, radix).unwrap().to_u32()?;
         let v = BigUint::from(2_u32).pow(exp);
         (&e[..pos], Some(Ratio::new(v.into(), BigInt::from(1))))
     } else if let Some(pos) = e.find("e+") {
         let n = &e[pos + 2..];
-        let Some(exp) = BigUint::from_str_radix(n, radix).unwrap().to_u32() else {
-            return None;
-        };
+        let exp = BigUint::from_str_radix(n, radix).unwrap().to_u32()?;
         let v = BigUint::from(10_u32).pow(exp);
         (&e[..pos], Some(Ratio::new(v.into(), BigInt::from(1))))
     } else if let Some(pos) = e.find("e-") {
         let n = &e[pos + 2..];
-        let Some(exp) = BigUint::from_str_radix(n, radix).unwrap().to_u32() else {
-            // fractional part is too big
-            return None;
-        };
+        let exp = BigUint::from_str_radix(n, radix).unwrap().to_u32()?;
         let v = BigUint::from(10_u32).pow(exp);
         (&e[..pos], Some(Ratio::new(BigInt::from(1), v.into())))
     } else if let Some(pos) = e.find('e') {
         if radix != 16 {
             let n = &e[pos + 1..];
             let exp = BigUint::from_str_radix(n, radix).unwrap();
-            let Some(exp) = exp.to_u32() else {
-                return None;
-            };
+            let exp = exp.to_u32()?;
             let v = BigUint::from(10_u32).pow(exp);
             (&e[..pos], Some(Ratio::new(v.into(), BigInt::from(1))))
         } else {
-            (&e[..], None)
+            (e, None)
         }
     } else {
-        (&e[..], None)
+        (e, None)
     };
 
     let parts = e.split('.').collect::<Vec<&str>>();
@@ -216,6 +147,7 @@ fn parse_lua_number(e: &str) -> Option<Ratio<BigInt>> {
 }
 
 #[test]
+#[allow(clippy::approx_constant)]
 fn test_parse_lua_number() {
     fn approx_assert_eq(f: Ratio<BigInt>, expected: f64) {
         let e = Ratio::from_float(expected).unwrap();
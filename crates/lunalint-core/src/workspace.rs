@@ -0,0 +1,649 @@
+//! Modules of a workspace.
+//!
+//! `require("a.b")` is resolved to a file by the templates of `runtime.path` (e.g. `?.lua` finds
+//! `a/b.lua`), relative to the directory of the configuration file. Each file is parsed and
+//! summarized once until it is invalidated: the type of the value returned by the main chunk and
+//! the globals defined by the file. Globals defined by files linted together are visible from each
+//! other.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use full_moon::tokenizer::TokenType;
+use full_moon::visitors::Visit;
+use full_moon::{ast, visitors::Visitor};
+use parking_lot::Mutex;
+
+use crate::{
+    config::Config,
+    context::Context,
+    location::{Location, SourceInfo},
+    resolver::{NodeId, Resolver, Visibility},
+    types::LuaType,
+    utils,
+};
+
+/// Summary of a file computed once. `None` if the file cannot be read or parsed.
+type SummaryCell = OnceLock<Option<Arc<Module>>>;
+
+/// Types of globals and the absolute paths of the files defining them, by their names
+type Globals = BTreeMap<String, (PathBuf, LuaType)>;
+
+#[derive(Debug)]
+pub struct Workspace {
+    config: Arc<Config>,
+    /// Directory which templates of `runtime.path` are relative to
+    root: PathBuf,
+    /// Files linted together, whose globals are visible from each other
+    files: Mutex<BTreeSet<PathBuf>>,
+    /// Summaries of files by their absolute paths
+    modules: Mutex<HashMap<PathBuf, Arc<SummaryCell>>>,
+    /// Globals defined by the files, and the absolute paths of the files defining them. If several
+    /// files define a global, the first file in the order of paths is used.
+    globals: Mutex<Option<Arc<Globals>>>,
+    /// Strongly connected components of the module graph which contain cycles, by the absolute
+    /// paths of their modules
+    components: Mutex<Option<Arc<HashMap<PathBuf, usize>>>>,
+}
+
+impl Workspace {
+    pub fn new(config: Arc<Config>) -> Self {
+        let root = config
+            .path()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Self {
+            config,
+            root,
+            files: Mutex::new(BTreeSet::new()),
+            modules: Mutex::new(HashMap::new()),
+            globals: Mutex::new(None),
+            components: Mutex::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &Arc<Config> {
+        &self.config
+    }
+
+    /// Add a file linted with the workspace. Files must be added before linting any of them.
+    pub fn add_file(&self, path: &Path) {
+        self.files.lock().insert(absolute(path));
+    }
+
+    /// Paths searched for a module by the templates of `runtime.path`.
+    pub fn candidates(&self, name: &str) -> Vec<PathBuf> {
+        let name = name.replace('.', "/");
+        self.config
+            .runtime_path()
+            .iter()
+            .map(|template| self.root.join(template.replace('?', &name)))
+            .collect()
+    }
+
+    /// Find the file of a module by the templates of `runtime.path`.
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        self.candidates(name)
+            .into_iter()
+            .find(|path| path.is_file())
+    }
+
+    /// Names of modules which `require` finds in the linted files, in alphabetical order.
+    pub fn module_names(&self) -> Vec<String> {
+        let files = self.files.lock().iter().cloned().collect::<Vec<_>>();
+        let mut names = BTreeSet::new();
+        for template in self.config.runtime_path() {
+            let template = absolute(&self.root.join(template));
+            let template = template.to_string_lossy();
+            let Some((prefix, suffix)) = template.split_once('?') else {
+                continue;
+            };
+            for file in files.iter() {
+                let file = file.to_string_lossy();
+                let name = file
+                    .strip_prefix(prefix)
+                    .and_then(|name| name.strip_suffix(suffix));
+                // Names with `.` cannot be required since `.` is a separator of directories
+                if let Some(name) = name.filter(|name| !name.is_empty() && !name.contains('.')) {
+                    names.insert(name.replace(std::path::MAIN_SEPARATOR, "."));
+                }
+            }
+        }
+        names.into_iter().collect()
+    }
+
+    /// Summary of a file, which is parsed on the first call.
+    pub fn module(&self, path: &Path) -> Option<Arc<Module>> {
+        let cell = Arc::clone(self.modules.lock().entry(absolute(path)).or_default());
+        cell.get_or_init(|| self.summarize(path).map(Arc::new))
+            .clone()
+    }
+
+    /// Forget the summary of a file which has changed. Summaries of modules whose `require` may
+    /// find the file are forgotten as well since the file may have just been created.
+    pub fn invalidate(&self, path: &Path) {
+        let path = absolute(path);
+        let mut modules = self.modules.lock();
+        let dependents = modules
+            .iter()
+            .filter(|(_, cell)| {
+                cell.get().into_iter().flatten().any(|module| {
+                    module.requires().iter().any(|require| {
+                        self.candidates(require.name())
+                            .iter()
+                            .any(|candidate| absolute(candidate) == path)
+                    })
+                })
+            })
+            .map(|(dependent, _)| dependent.clone())
+            .collect::<Vec<_>>();
+        modules.remove(&path);
+        for dependent in dependents {
+            modules.remove(&dependent);
+        }
+        drop(modules);
+        *self.globals.lock() = None;
+        *self.components.lock() = None;
+    }
+
+    /// Globals defined by files of the workspace other than the file.
+    pub fn globals(&self, path: &Path) -> BTreeMap<String, LuaType> {
+        let path = absolute(path);
+        let globals = Arc::clone(self.globals.lock().get_or_insert_with(|| {
+            let files = self.files.lock().iter().cloned().collect::<Vec<_>>();
+            let mut globals = BTreeMap::new();
+            for file in files {
+                let Some(module) = self.module(&file) else {
+                    continue;
+                };
+                for (name, ty) in module.globals.iter() {
+                    globals
+                        .entry(name.clone())
+                        .or_insert_with(|| (file.clone(), ty.clone()));
+                }
+            }
+            Arc::new(globals)
+        }));
+        globals
+            .iter()
+            .filter(|(_, (file, _))| *file != path)
+            .map(|(name, (_, ty))| (name.clone(), ty.clone()))
+            .collect()
+    }
+
+    /// Chain of `require` calls from the module at `to` back to the file at `from`, if requiring
+    /// `to` from `from` makes a cycle. The chain is the shortest one within the strongly connected
+    /// component of the modules, and is empty if a file requires itself.
+    pub fn cycle(&self, from: &Path, to: &Path) -> Option<Vec<Require>> {
+        let (from, to) = (absolute(from), absolute(to));
+        let components = self.components();
+        let component = components.get(&from)?;
+        if components.get(&to) != Some(component) {
+            return None;
+        }
+        if from == to {
+            return Some(Vec::new());
+        }
+
+        // Breadth-first search from `to` to `from`
+        let mut prev: HashMap<PathBuf, (PathBuf, Require)> = HashMap::new();
+        let mut queue = VecDeque::from([to.clone()]);
+        while let Some(path) = queue.pop_front() {
+            let Some(module) = self.module(&path) else {
+                continue;
+            };
+            for require in module.requires() {
+                let Some(next) = require.path().map(absolute) else {
+                    continue;
+                };
+                if next == to
+                    || prev.contains_key(&next)
+                    || components.get(&next) != Some(component)
+                {
+                    continue;
+                }
+                prev.insert(next.clone(), (path.clone(), require.clone()));
+                if next == from {
+                    let mut chain = Vec::new();
+                    let mut node = from;
+                    while let Some((parent, require)) = prev.remove(&node) {
+                        chain.push(require);
+                        node = parent;
+                    }
+                    chain.reverse();
+                    return Some(chain);
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    /// Strongly connected components of modules reachable from the linted files, which contain
+    /// cycles.
+    fn components(&self) -> Arc<HashMap<PathBuf, usize>> {
+        let mut cached = self.components.lock();
+        Arc::clone(cached.get_or_insert_with(|| {
+            let files = self.files.lock().iter().cloned().collect::<Vec<_>>();
+            let mut tarjan = Tarjan {
+                workspace: self,
+                indices: HashMap::new(),
+                stack: Vec::new(),
+                on_stack: HashSet::new(),
+                components: Vec::new(),
+            };
+            for file in files {
+                if !tarjan.indices.contains_key(&file) {
+                    tarjan.visit(file);
+                }
+            }
+
+            let mut components = HashMap::new();
+            for (i, component) in tarjan.components.into_iter().enumerate() {
+                let cyclic = match component.as_slice() {
+                    [path] => self.dependencies(path).contains(path),
+                    _ => true,
+                };
+                if cyclic {
+                    components.extend(component.into_iter().map(|path| (path, i)));
+                }
+            }
+            Arc::new(components)
+        }))
+    }
+
+    /// Absolute paths of modules required by the module.
+    fn dependencies(&self, path: &Path) -> Vec<PathBuf> {
+        self.module(path)
+            .map(|module| {
+                module
+                    .requires()
+                    .iter()
+                    .filter_map(|require| require.path().map(absolute))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn summarize(&self, path: &Path) -> Option<Module> {
+        log::debug!("summarizing module: {:?}", path);
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                log::debug!("failed to read module {:?}: {}", path, e);
+                return None;
+            }
+        };
+        let src = Arc::new(SourceInfo::new(
+            relative(path).to_string_lossy().into_owned(),
+            content,
+        ));
+        let ast = crate::parse(Arc::clone(&src)).ok()?;
+        // Modules required by the module are not summarized, which would never end for cycles
+        let mut ctx = Context::with_config(Arc::clone(&src), Arc::clone(&self.config));
+        ctx.analyze(&ast);
+        let resolver = ctx.resolver();
+        let types = ctx.types();
+
+        let value = match ast.nodes().last_stmt() {
+            Some(ast::LastStmt::Return(ret)) => ret.returns().iter().next(),
+            _ => None,
+        };
+        let returned = value
+            .and_then(|value| types.get(NodeId::from(value)))
+            .map(LuaType::detached)
+            .unwrap_or(LuaType::Unknown);
+        let fields = match (&returned, value) {
+            (LuaType::Table(shape), Some(value))
+                if shape.map.is_none() && has_visible_fields(&ctx, &ast, value) =>
+            {
+                Some(shape.fields.keys().cloned().collect())
+            }
+            _ => None,
+        };
+
+        let globals = resolver
+            .definitions()
+            .filter(|(def_id, def)| {
+                !resolver.is_builtin(*def_id) && def.visibility() != Visibility::Local
+            })
+            .map(|(def_id, def)| {
+                let ty = types
+                    .get(def_id)
+                    .map(LuaType::detached)
+                    .unwrap_or(LuaType::Unknown);
+                (def.name().to_owned(), ty)
+            })
+            .collect();
+
+        let requires = collect_requires(self, &src, &ast, resolver)
+            .into_iter()
+            .map(|(_, require)| require)
+            .collect();
+
+        Some(Module {
+            path: path.to_path_buf(),
+            returned,
+            fields,
+            globals,
+            requires,
+        })
+    }
+}
+
+/// Summary of a file loaded as a module
+#[derive(Debug)]
+pub struct Module {
+    path: PathBuf,
+    /// Type of the value returned by the main chunk
+    returned: LuaType,
+    /// Fields of the returned table if all of them are visible in the file
+    fields: Option<BTreeSet<String>>,
+    /// Globals defined by the file
+    globals: BTreeMap<String, LuaType>,
+    /// Calls of `require` in the file
+    requires: Vec<Require>,
+}
+
+impl Module {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Type of the value returned by `require`. `unknown` if the module returns nothing.
+    pub fn returned(&self) -> &LuaType {
+        &self.returned
+    }
+
+    /// Fields of the returned table, if the table is created by the module and all of its fields
+    /// are visible.
+    pub fn fields(&self) -> Option<&BTreeSet<String>> {
+        self.fields.as_ref()
+    }
+
+    pub fn globals(&self) -> &BTreeMap<String, LuaType> {
+        &self.globals
+    }
+
+    /// Calls of `require` in the order of locations.
+    pub fn requires(&self) -> &[Require] {
+        &self.requires
+    }
+}
+
+/// A call of `require` with a string literal
+#[derive(Debug, Clone)]
+pub struct Require {
+    name: String,
+    /// Location of the string literal
+    loc: Location,
+    /// File of the module found by `runtime.path`
+    path: Option<PathBuf>,
+}
+
+impl Require {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn loc(&self) -> Location {
+        self.loc.clone()
+    }
+
+    /// File of the module. `None` if no file is found.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+/// Globals and modules of the workspace visible from a file
+#[derive(Debug, Default)]
+pub struct Imports {
+    /// Globals defined by other files of the workspace
+    globals: BTreeMap<String, LuaType>,
+    /// Calls of `require` in the order of locations
+    requires: Vec<(NodeId, Require)>,
+    /// Modules loaded by the calls
+    modules: HashMap<NodeId, Arc<Module>>,
+}
+
+impl Imports {
+    pub(crate) fn new(workspace: &Workspace, src: &SourceInfo) -> Self {
+        Self {
+            globals: workspace.globals(Path::new(src.path())),
+            requires: Vec::new(),
+            modules: HashMap::new(),
+        }
+    }
+
+    /// Resolve calls of `require` in the file.
+    pub(crate) fn collect_requires(
+        &mut self,
+        workspace: &Workspace,
+        src: &Arc<SourceInfo>,
+        ast: &ast::Ast,
+        resolver: &Resolver,
+    ) {
+        self.requires = collect_requires(workspace, src, ast, resolver);
+        for (call, require) in self.requires.iter() {
+            if let Some(module) = require.path().and_then(|path| workspace.module(path)) {
+                self.modules.insert(*call, module);
+            }
+        }
+    }
+
+    pub fn globals(&self) -> &BTreeMap<String, LuaType> {
+        &self.globals
+    }
+
+    /// Calls of `require` with string literals in the order of locations.
+    pub fn requires(&self) -> impl Iterator<Item = &Require> {
+        self.requires.iter().map(|(_, require)| require)
+    }
+
+    /// Module loaded by the function call.
+    pub fn module(&self, call: NodeId) -> Option<&Module> {
+        self.modules.get(&call).map(Arc::as_ref)
+    }
+}
+
+/// Calls of `require` with string literals in the file, resolved by `runtime.path`.
+fn collect_requires(
+    workspace: &Workspace,
+    src: &Arc<SourceInfo>,
+    ast: &ast::Ast,
+    resolver: &Resolver,
+) -> Vec<(NodeId, Require)> {
+    let mut collector = RequireCollector {
+        src,
+        resolver,
+        calls: Vec::new(),
+    };
+    ast.nodes().visit(&mut collector);
+    collector
+        .calls
+        .into_iter()
+        .map(|(call, name, loc)| {
+            let path = workspace.resolve(&name);
+            (call, Require { name, loc, path })
+        })
+        .collect()
+}
+
+/// Tarjan's algorithm finding strongly connected components of the module graph
+struct Tarjan<'a> {
+    workspace: &'a Workspace,
+    /// Indices of visited modules in the order of visits
+    indices: HashMap<PathBuf, usize>,
+    stack: Vec<PathBuf>,
+    on_stack: HashSet<PathBuf>,
+    components: Vec<Vec<PathBuf>>,
+}
+
+impl Tarjan<'_> {
+    /// Visit a module and return its low link, the smallest index reachable from it on the stack.
+    fn visit(&mut self, path: PathBuf) -> usize {
+        let index = self.indices.len();
+        self.indices.insert(path.clone(), index);
+        self.stack.push(path.clone());
+        self.on_stack.insert(path.clone());
+
+        let mut low = index;
+        for dep in self.workspace.dependencies(&path) {
+            match self.indices.get(&dep) {
+                None => low = low.min(self.visit(dep)),
+                Some(dep_index) if self.on_stack.contains(&dep) => low = low.min(*dep_index),
+                Some(_) => {}
+            }
+        }
+
+        if low == index {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(&member);
+                let done = member == path;
+                component.push(member);
+                if done {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+        low
+    }
+}
+
+struct RequireCollector<'a> {
+    src: &'a Arc<SourceInfo>,
+    resolver: &'a Resolver,
+    /// Calls, module names and locations of the names
+    calls: Vec<(NodeId, String, Location)>,
+}
+
+impl Visitor for RequireCollector<'_> {
+    fn visit_function_call(&mut self, node: &ast::FunctionCall) {
+        let ast::Prefix::Name(name) = node.prefix() else {
+            return;
+        };
+        let is_require = utils::ident_as_str(name) == "require"
+            && self
+                .resolver
+                .lookup_definiton(NodeId::from(node.prefix()))
+                .is_some_and(|def_id| self.resolver.is_builtin(def_id));
+        let suffixes = node.suffixes().collect::<Vec<_>>();
+        let token = match suffixes.as_slice() {
+            [ast::Suffix::Call(ast::Call::AnonymousCall(args))] if is_require => match args {
+                ast::FunctionArgs::Parentheses { arguments, .. } if arguments.len() == 1 => {
+                    match arguments.iter().next() {
+                        Some(ast::Expression::String(token)) => token,
+                        _ => return,
+                    }
+                }
+                ast::FunctionArgs::String(token) => token,
+                _ => return,
+            },
+            _ => return,
+        };
+        if let TokenType::StringLiteral { literal, .. } = token.token_type() {
+            let loc = Location::from((self.src, token));
+            self.calls
+                .push((NodeId::from(node), literal.to_string(), loc));
+        }
+    }
+}
+
+/// Whether all fields of the returned table are visible. The table is created by a table
+/// constructor, or is a local table which is never reassigned and whose other uses only access
+/// fields by names.
+fn has_visible_fields(ctx: &Context, ast: &ast::Ast, value: &ast::Expression) -> bool {
+    match value {
+        ast::Expression::TableConstructor(_) => true,
+        ast::Expression::Var(ast::Var::Name(_)) => {
+            let resolver = ctx.resolver();
+            let Some(def_id) = resolver.lookup_definiton(NodeId::from(value)) else {
+                return false;
+            };
+            let mut accesses = FieldAccesses::default();
+            ast.nodes().visit(&mut accesses);
+            accesses.constructors.contains(&def_id)
+                && resolver.reassignments(def_id).is_empty()
+                && resolver
+                    .uses(def_id)
+                    .iter()
+                    .all(|use_| *use_ == NodeId::from(value) || accesses.uses.contains(use_))
+        }
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct FieldAccesses {
+    /// Local variables defined by table constructors
+    constructors: HashSet<NodeId>,
+    /// Uses of variables which access fields by names
+    uses: HashSet<NodeId>,
+}
+
+impl FieldAccesses {
+    fn visit_access(&mut self, prefix: &ast::Prefix, first: Option<&ast::Suffix>) {
+        if let (
+            ast::Prefix::Name(_),
+            Some(
+                ast::Suffix::Index(ast::Index::Dot { .. })
+                | ast::Suffix::Call(ast::Call::MethodCall(_)),
+            ),
+        ) = (prefix, first)
+        {
+            self.uses.insert(NodeId::from(prefix));
+        }
+    }
+}
+
+impl Visitor for FieldAccesses {
+    fn visit_local_assignment(&mut self, node: &ast::LocalAssignment) {
+        for (name, expr) in node.names().iter().zip(node.expressions().iter()) {
+            if let ast::Expression::TableConstructor(_) = expr {
+                self.constructors.insert(NodeId::from(name));
+            }
+        }
+    }
+
+    fn visit_var(&mut self, node: &ast::Var) {
+        if let ast::Var::Expression(var) = node {
+            self.visit_access(var.prefix(), var.suffixes().next());
+        }
+    }
+
+    fn visit_function_call(&mut self, node: &ast::FunctionCall) {
+        self.visit_access(node.prefix(), node.suffixes().next());
+    }
+
+    fn visit_function_declaration(&mut self, node: &ast::FunctionDeclaration) {
+        // The table of `function t.f() end` or `function t:m() end`
+        let name = node.name();
+        if name.names().len() > 1 || name.method_name().is_some() {
+            if let Some(table) = name.names().iter().next() {
+                self.uses.insert(NodeId::from(table));
+            }
+        }
+    }
+}
+
+/// Path identifying a file. Paths relative to the working directory and paths found by
+/// `runtime.path` refer to the same file.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Path relative to the current directory if it is under the directory, for displaying in reports.
+fn relative(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path)
+        .to_path_buf()
+}
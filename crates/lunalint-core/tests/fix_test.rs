@@ -0,0 +1,44 @@
+#[allow(dead_code)]
+mod helper;
+
+use std::path::PathBuf;
+
+use insta::Settings;
+use lunalint_core::fix::{apply_fixes, Applicability};
+
+macro_rules! fix_test {
+    ($name:ident, $applicability:expr) => {
+        fix_test!($name, "fix.lua", $applicability);
+    };
+    ($name:ident, $path:expr, $applicability:expr) => {
+        #[test]
+        fn $name() {
+            let path = PathBuf::from(format!("tests/lua/{}", $path));
+            let code = std::fs::read_to_string(&path).unwrap();
+            let reports = helper::lint(&path);
+            let fixes = reports
+                .iter()
+                .flat_map(|r| r.fixes())
+                .filter(|f| f.applicability() <= $applicability)
+                .collect::<Vec<_>>();
+            let (out, _) = apply_fixes(&code, &fixes);
+            let mut settings = Settings::new();
+            settings.set_prepend_module_to_snapshot(false);
+            settings.set_omit_expression(true);
+            settings.bind(|| insta::assert_snapshot!(out));
+        }
+    };
+}
+
+fix_test!(fix_safe, Applicability::Safe);
+fix_test!(fix_unsafe, Applicability::Unsafe);
+fix_test!(
+    fix_unused_local,
+    "fix-unused-local.lua",
+    Applicability::Safe
+);
+fix_test!(
+    fix_missing_module,
+    "require-cycle/main.lua",
+    Applicability::Unsafe
+);
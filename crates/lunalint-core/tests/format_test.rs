@@ -0,0 +1,37 @@
+#[allow(dead_code)]
+mod helper;
+
+use std::path::PathBuf;
+
+use insta::Settings;
+use lunalint_core::diagnostics::{format::OutputFormat, sort_reports};
+
+macro_rules! format_test {
+    ($name:ident, $format:expr) => {
+        #[test]
+        fn $name() {
+            let path = PathBuf::from("tests/lua/format.lua");
+            let mut reports = helper::lint(&path);
+            sort_reports(&mut reports);
+            let files = vec![
+                path.to_str().unwrap().to_string(),
+                "tests/lua/empty.lua".to_string(),
+            ];
+            let mut out = Vec::new();
+            $format
+                .formatter()
+                .format(&files, &reports, &mut out)
+                .unwrap();
+            let out = String::from_utf8(out).unwrap();
+            let mut settings = Settings::new();
+            settings.set_prepend_module_to_snapshot(false);
+            settings.set_omit_expression(true);
+            settings.bind(|| insta::assert_snapshot!(out));
+        }
+    };
+}
+
+format_test!(format_json, OutputFormat::Json);
+format_test!(format_sarif, OutputFormat::Sarif);
+format_test!(format_checkstyle, OutputFormat::Checkstyle);
+format_test!(format_junit, OutputFormat::Junit);
@@ -1,17 +1,37 @@
 use std::{
     fs::OpenOptions,
     io::{BufWriter, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
-use lunalint_core::{diagnostics::write_report, location::SourceInfo, parse, pass, Context};
+use lunalint_core::{
+    diagnostics::{write_report, LintReport},
+    location::SourceInfo,
+    parse, pass,
+    workspace::Workspace,
+    Config, Context,
+};
 
-pub fn run_linter(path: &PathBuf) -> String {
-    let mut file = OpenOptions::new().read(true).open(&path).unwrap();
-    // buffer
-    let mut out = BufWriter::new(Vec::new());
+/// Lint the file and return reports including ones of the configuration file. Files in a test
+/// directory are linted together as a workspace.
+pub fn lint(path: &PathBuf) -> Vec<Arc<LintReport>> {
+    let (config, config_reports) = Config::discover(path);
+    let mut reports = config_reports.into_iter().map(Arc::new).collect::<Vec<_>>();
+
+    let workspace = Arc::new(Workspace::new(Arc::new(config)));
+    workspace.add_file(path);
+    let dir = path.parent().unwrap();
+    if dir != Path::new("tests/lua") {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let file = entry.unwrap().path();
+            if file.extension().is_some_and(|ext| ext == "lua") {
+                workspace.add_file(&file);
+            }
+        }
+    }
 
+    let mut file = OpenOptions::new().read(true).open(path).unwrap();
     let mut code = String::new();
     file.read_to_string(&mut code).unwrap();
 
@@ -20,21 +40,27 @@ pub fn run_linter(path: &PathBuf) -> String {
     let ast = match parse(Arc::clone(&src)) {
         Ok(ast) => ast,
         Err(e) => {
-            write_report(&e, &mut out);
-            return String::from_utf8(out.into_inner().unwrap()).unwrap();
+            reports.push(Arc::new(e));
+            return reports;
         }
     };
 
-    let mut ctx = Context::new(src);
-    ctx.resolver_mut().go(&ast);
+    let mut ctx = Context::with_workspace(src, workspace);
+    ctx.analyze(&ast);
 
     let ctx = Arc::new(ctx);
     let mut pass_manager = pass::PassManager::with_all_passes(Arc::clone(&ctx));
     pass_manager.run(&ast);
 
-    for report in ctx.reports().iter() {
+    reports.extend(ctx.reports().iter().cloned());
+    reports
+}
+
+pub fn run_linter(path: &PathBuf) -> String {
+    // buffer
+    let mut out = BufWriter::new(Vec::new());
+    for report in lint(path).iter() {
         write_report(report, &mut out);
     }
-
     String::from_utf8(out.into_inner().unwrap()).unwrap()
 }
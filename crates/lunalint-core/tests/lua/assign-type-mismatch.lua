@@ -0,0 +1,26 @@
+---@type integer
+local count = "zero"
+
+---@type string|nil
+local name = nil
+name = "x"
+name = 1
+
+count = 1
+count = nil
+count = count + 0.5
+
+---@class Config
+---@field verbose boolean
+---@field level? integer
+local config = {}
+config.verbose = true
+config.level = "high"
+config.other = "x"
+
+---@param n number
+local function f(n)
+    n = "s"
+    return n
+end
+f(1)
@@ -0,0 +1,2 @@
+-- Reports point at the invalid entries
+print(undefined_var)
@@ -0,0 +1,2 @@
+-- Invalid entries are ignored
+print(undefined_var)
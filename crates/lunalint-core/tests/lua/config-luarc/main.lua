@@ -0,0 +1,8 @@
+-- OK. `vim` is declared in .luarc.json
+vim.print("hello")
+
+-- OK. lowercase-global is disabled
+foo = 1
+
+-- Warning
+print(undefined_var)
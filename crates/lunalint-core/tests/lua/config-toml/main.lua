@@ -0,0 +1,9 @@
+-- OK. `love` is declared in lunalint.toml
+function love.draw() end
+
+-- OK. count-down-loop is disabled
+for i = 10, 1 do
+end
+
+-- Hint
+foo = 1
@@ -0,0 +1,3 @@
+host.log("started", host_version)
+print(unpack({ jit_host }))
+host.exit()
@@ -0,0 +1,34 @@
+---@class (exact) Point: Shape
+---@field x number
+---@field private y? number
+---@field [string] fun(self: Point): boolean
+
+---@alias Direction
+---| "up" # Going up
+---| "down"
+
+---@alias Empty
+
+--- Move a point
+---@param p Point
+---@param dir Direction?
+---@param ... integer
+---@return Point moved, string? err
+local function move(p, dir, ...)
+    return p, dir
+end
+
+---@type table<string, Point[]
+local points = {}
+
+---@param
+---@return fun(a: ): string
+---@field name "unclosed
+---@overload table
+---@class (strict) Line
+---@type { x: number, y }
+local function broken() end
+
+---@diagnostic disable-next-line: unused-local
+local unused = move(points, "up")
+broken()
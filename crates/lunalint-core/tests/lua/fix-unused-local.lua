@@ -0,0 +1,12 @@
+local _x = 10
+local function f()
+    -- `_x` would refer to this local instead of the outer one
+    local x = 1
+    return _x
+end
+print(f())
+
+local function g()
+    local y = 2
+end
+g()
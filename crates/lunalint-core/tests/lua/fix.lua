@@ -0,0 +1,7 @@
+for i = 10, 1 do
+    prnt(i)
+end
+
+---@diagnostic disable-next-line: lowercase-global
+local x = 1 ---@diagnostic disable-line: undefined-global
+print(x)
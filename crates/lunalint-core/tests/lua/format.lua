@@ -0,0 +1,9 @@
+-- Reports of various diagnostics and levels
+---@diagnostic disable-next-line: lowercase-global
+print(undefined_var)
+
+for i = 10, 1 do
+end
+
+---@diagnostic disable-next-line: count-down-loop
+print("unused")
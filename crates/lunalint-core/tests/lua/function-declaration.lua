@@ -0,0 +1,24 @@
+local M = {}
+M.config = {}
+
+function M.new()
+    return setmetatable({}, { __index = M })
+end
+
+-- Methods have the implicit `self` parameter
+function M:greet(name)
+    print(self, name)
+end
+
+function M.config.load()
+    -- `self` is only defined in methods
+    return self
+end
+
+function Undefined.method() end
+
+function Undefinedd:method()
+    return self
+end
+
+return M
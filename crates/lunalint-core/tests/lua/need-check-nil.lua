@@ -0,0 +1,62 @@
+---@param name? string
+local function greet(name)
+    print(name:upper())
+
+    if name then
+        print(name:upper())
+    end
+
+    if not name then
+        return
+    end
+    print(name:lower())
+end
+
+---@param path? string
+local function open(path)
+    path = path or "default.txt"
+    return path:len()
+end
+
+---@param n? number
+local function double(n)
+    assert(n)
+    return n.value
+end
+
+---@param s? string
+local function describe(s)
+    if type(s) == "nil" then
+        return "nothing"
+    end
+    return s:sub(1, 1)
+end
+
+---@param s? string
+local function compare(s)
+    if s ~= nil and s:len() > 0 then
+        return s
+    end
+    while s do
+        s = s:sub(2)
+        print(s:len())
+    end
+    return s:len()
+end
+
+-- Functions which may return `nil`
+local found = string.match("abc", "b")
+print(found:upper())
+local file = io.open("a.txt")
+if file then
+    file:close()
+end
+
+---@type fun()?
+local callback
+callback()
+
+local list = { 1, 2, 3 }
+print(list[1] + 1)
+
+return { greet = greet, open = open, double = double, describe = describe, compare = compare }
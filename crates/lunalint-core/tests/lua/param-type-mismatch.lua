@@ -0,0 +1,33 @@
+---@param n integer
+---@param name? string
+local function repeat_name(n, name)
+    return (name or "x"):rep(n)
+end
+
+repeat_name(1)
+repeat_name(2, "y")
+repeat_name("3")
+repeat_name(1.5, nil)
+repeat_name(1, { "y" })
+repeat_name(1, true)
+
+---@class Vector
+---@field x number
+---@field y number
+local Vector = {}
+
+---@param v Vector
+---@return number
+local function length(v)
+    return math.sqrt(v.x * v.x + v.y * v.y)
+end
+
+length({ x = 1, y = 2 })
+length({ x = "1" })
+length(Vector)
+
+-- Parameters without annotations accept anything
+local function id(x)
+    return x
+end
+id("x")
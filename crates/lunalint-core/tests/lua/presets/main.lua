@@ -0,0 +1,20 @@
+function love.load()
+    love.window.setTitle("presets")
+end
+
+function love.draw()
+    love.graphics.rectangle("fill", 0, 0, 10, 10)
+    -- Modules of LÖVE are open
+    love.graphics.drawInstanced()
+    love.grpahics.print("typo", 0, 0)
+end
+
+function love.onframe() end
+love.onframe()
+
+local value = redis.call("GET", KEYS[1])
+redis.log(redis.LOG_NOTICE, cjson.encode({ value = value, args = ARGV }))
+redis.cal("SET", KEYS[1], value)
+
+-- Neovim is not selected
+vim.notify(value)
@@ -0,0 +1,3 @@
+local b = require("b")
+
+return { b = b }
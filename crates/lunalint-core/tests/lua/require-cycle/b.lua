@@ -0,0 +1,3 @@
+local main = require("main")
+
+return { main = main }
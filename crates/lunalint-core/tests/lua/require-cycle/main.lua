@@ -0,0 +1,7 @@
+local a = require("a")
+local utils = require("utlis")
+local missing = require('nowhere')
+local string = require("string")
+local helpers = require [==[utlis]==]
+
+print(a, utils, missing, string, helpers)
@@ -0,0 +1,24 @@
+-- Builtins of Lua 5.1
+local env = getfenv(1)
+setfenv(1, env)
+print(unpack({ 1, 2 }), loadstring("return 1"))
+
+-- Builtins introduced later
+print(rawlen({}))
+warn("@on")
+print(utf8.char(72))
+
+for i = 1, 3 do
+  if i == 2 then
+    goto continue
+  end
+  print(i)
+  ::continue::
+end
+
+local a, b = 7, 2
+print(a // b, a & b, a | b, a ~ b, a << b, a >> b, ~a)
+
+local c <const> = 1
+local f <close> = nil
+print(c, f)
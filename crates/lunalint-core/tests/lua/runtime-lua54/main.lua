@@ -0,0 +1,16 @@
+-- Builtins removed in Lua 5.2
+local env = getfenv(1)
+setfenv(1, env)
+print(unpack({ 1, 2 }), loadstring("return 1"))
+print(bit.band(1, 2))
+
+-- Syntax of Lua 5.4
+for i = 1, 3 do
+  if i == 2 then
+    goto continue
+  end
+  print(i // 2, i & 1, ~i)
+  ::continue::
+end
+local c <const> = 1
+warn("@on", c)
@@ -0,0 +1,40 @@
+-- The right-hand side is evaluated before the local comes into scope
+local value = value
+
+-- A local function is visible in its own body
+local function fact(n)
+    if n <= 1 then
+        return 1
+    end
+    return n * fact(n - 1)
+end
+
+-- A function assigned to a local does not see the local
+local fib = function(n)
+    return fib(n - 1) + fib(n - 2)
+end
+
+-- The condition of `repeat ... until` sees locals of the loop body
+repeat
+    local done = true
+until done
+
+-- Variables of `for` loops are only visible in the loop body
+for i = 1, 10 do
+    print(i)
+end
+for k in pairs({}) do
+    print(k)
+end
+print(i, k)
+
+-- Empty bodies do not confuse scopes of later blocks
+repeat until true
+do
+    local inner = 1
+    if inner then end
+end
+for _ = 1, 2 do end
+print(inner)
+
+print(value, fact, fib)
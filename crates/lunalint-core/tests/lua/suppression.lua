@@ -0,0 +1,24 @@
+---@diagnostic disable-next-line: undefined-global
+print(undefined1)
+
+print(undefined2) ---@diagnostic disable-line
+
+---@diagnostic disable: lowercase-global
+foo = 1
+bar = 2
+---@diagnostic enable: lowercase-global
+
+-- NG. lowercase-global is enabled again
+baz = 3
+
+---@diagnostic disable
+print(undefined3)
+---@diagnostic enable
+
+-- NG. different diagnostic
+---@diagnostic disable-next-line: lowercase-global
+print(undefined4)
+
+-- NG. unused
+---@diagnostic disable-next-line: undefined-global
+print("ok")
@@ -0,0 +1,37 @@
+-- Members of the standard library
+print(math.flor(1.5), math.floor(1.5))
+print(os.tiem())
+print(string.subb("abc", 1))
+print(("%d"):fromat(1))
+
+-- Assigning a new field is not an error
+string.trim = function(s)
+  return s
+end
+print(string.trim(" a "))
+
+-- Tables whose fields are all visible
+local person = { name = "lua", ["age"] = 30 }
+person.email = "lua@example.com"
+function person.greet() end
+print(person.nmae, person.age, person.email, person:greet(), person.phone)
+
+-- Tables which escape are not checked
+local config = { debug = true }
+setmetatable(config, {})
+print(config.verbose)
+
+local dynamic = { a = 1 }
+dynamic[1] = 2
+print(dynamic.b)
+
+local empty = {}
+print(empty.anything)
+
+-- Fields declared on a local table do not add fields to the library table of the same name
+do
+  local string = {}
+  function string.split(s) return s end
+  print(string.split("a"))
+end
+print(string.split("a"))
@@ -0,0 +1,28 @@
+local function f(x)
+    if x > 10 then
+        do return "big" end
+        print("never")
+    end
+    if x < 0 then
+        return "negative"
+    else
+        error("unexpected")
+    end
+    print("never")
+    print("never")
+end
+
+for i = 1, 10 do
+    do break end
+    print(i)
+end
+
+goto skip
+print("skipped")
+::skip::
+print("reachable")
+
+while true do
+    print(f(1))
+end
+print("never")
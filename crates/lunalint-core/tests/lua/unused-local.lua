@@ -0,0 +1,21 @@
+local used = 1
+local unused = 2
+local assigned = 3
+assigned = 4
+local _ignored = 5
+
+local function f(a, b, _c)
+    return a
+end
+
+for i, v in ipairs({}) do
+    print(v)
+end
+
+local function unused_func()
+    return unused_func()
+end
+
+local function _ignored_func() end
+
+print(used, f(1))
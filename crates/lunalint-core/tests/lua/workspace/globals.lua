@@ -0,0 +1,6 @@
+-- Globals defined here are visible from other files of the workspace
+Settings = { verbose = false }
+
+function Log(msg)
+    print(msg)
+end
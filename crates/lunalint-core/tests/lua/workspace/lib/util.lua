@@ -0,0 +1,20 @@
+local M = {}
+
+---@param s string
+---@return string
+function M.trim(s)
+    return (s:gsub("^%s+", ""))
+end
+
+---@param list string[]
+---@param name string
+---@return string?
+function M.find(list, name)
+    for _, item in ipairs(list) do
+        if item == name then
+            return item
+        end
+    end
+end
+
+return M
@@ -0,0 +1,12 @@
+local util = require("util")
+
+print(util.trim("  a"))
+print(util.trimm("  a"))
+print(util.find({ "a" }, "a"):upper())
+
+Log(Settings.verbose)
+Logg("undefined")
+
+-- Modules which are not found are unknown
+local missing = require("missing.module")
+print(missing.anything)
@@ -13,10 +13,13 @@ macro_rules! ident_to_str {
 
 macro_rules! lua_test {
     ($name:ident) => {
+        lua_test!($name, format!("{}.lua", ident_to_str!($name)));
+    };
+    // Tests which need a directory (e.g. with a configuration file)
+    ($name:ident, $path:expr) => {
         #[test]
         fn $name() {
-            let name = ident_to_str!($name);
-            let path = PathBuf::from(format!("tests/lua/{}.lua", name));
+            let path = PathBuf::from(format!("tests/lua/{}", $path));
             let out = strip_str(helper::run_linter(&path));
             let mut settings = Settings::new();
             settings.set_prepend_module_to_snapshot(false);
@@ -29,9 +32,29 @@ macro_rules! lua_test {
 lua_test!(undefined_global);
 lua_test!(undefined_global2);
 lua_test!(undefined_global3);
+lua_test!(undefined_field);
 lua_test!(parse_error);
 lua_test!(count_down_loop);
 lua_test!(unicode_name);
 lua_test!(global_in_nil_env);
 lua_test!(lowercase_global);
 lua_test!(empty);
+lua_test!(suppression);
+lua_test!(unreachable_code);
+lua_test!(unused_local);
+lua_test!(config_luarc, "config-luarc/main.lua");
+lua_test!(config_toml, "config-toml/main.lua");
+lua_test!(config_invalid, "config-invalid/main.lua");
+lua_test!(config_invalid_toml, "config-invalid-toml/main.lua");
+lua_test!(runtime_lua51, "runtime-lua51/main.lua");
+lua_test!(runtime_lua54, "runtime-lua54/main.lua");
+lua_test!(definitions, "definitions/main.lua");
+lua_test!(presets, "presets/main.lua");
+lua_test!(doc_syntax);
+lua_test!(param_type_mismatch);
+lua_test!(assign_type_mismatch);
+lua_test!(need_check_nil);
+lua_test!(function_declaration);
+lua_test!(scoping);
+lua_test!(workspace, "workspace/main.lua");
+lua_test!(require_cycle, "require-cycle/main.lua");
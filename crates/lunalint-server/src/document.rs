@@ -0,0 +1,131 @@
+use tower_lsp::lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+/// A document opened in the client. The text is kept in sync with the buffer of the client.
+#[derive(Debug, Clone)]
+pub struct Document {
+    version: i32,
+    text: String,
+}
+
+impl Document {
+    pub fn new(version: i32, text: String) -> Self {
+        Self { version, text }
+    }
+
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+    }
+
+    /// Apply changes in order. A change without a range replaces the whole text.
+    pub fn apply_changes(&mut self, version: i32, changes: Vec<TextDocumentContentChangeEvent>) {
+        for change in changes {
+            match change.range {
+                Some(range) => {
+                    let start = self.offset(range.start);
+                    let end = self.offset(range.end).max(start);
+                    self.text.replace_range(start..end, &change.text);
+                }
+                None => self.text = change.text,
+            }
+        }
+        self.version = version;
+    }
+
+    /// Byte offset of the position. Characters of LSP positions are counted in UTF-16 code units.
+    /// Positions beyond the end of a line or the document are clamped.
+    pub fn offset(&self, pos: Position) -> usize {
+        let mut line_start = 0;
+        for _ in 0..pos.line {
+            match self.text[line_start..].find('\n') {
+                Some(i) => line_start += i + 1,
+                None => return self.text.len(),
+            }
+        }
+        let line = self.text[line_start..].split('\n').next().unwrap_or("");
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= pos.character as usize {
+                return line_start + i;
+            }
+            units += c.len_utf16();
+        }
+        line_start + line.len()
+    }
+
+    /// Position of the byte offset, which is the inverse of [Document::offset].
+    pub fn position(&self, offset: usize) -> Position {
+        position(&self.text, offset)
+    }
+
+    pub fn range(&self, range: std::ops::Range<usize>) -> Range {
+        Range::new(self.position(range.start), self.position(range.end))
+    }
+}
+
+/// Position of the byte offset in the text. Characters are counted in UTF-16 code units.
+fn position(text: &str, offset: usize) -> Position {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+/// Range of the byte range in the text, which may not be an opened document (e.g. a configuration
+/// file or another file of the workspace).
+pub fn range_of(text: &str, range: std::ops::Range<usize>) -> Range {
+    Range::new(position(text, range.start), position(text, range.end))
+}
+
+#[cfg(test)]
+fn change(range: Option<((u32, u32), (u32, u32))>, text: &str) -> TextDocumentContentChangeEvent {
+    TextDocumentContentChangeEvent {
+        range: range.map(|(start, end)| Range {
+            start: Position::new(start.0, start.1),
+            end: Position::new(end.0, end.1),
+        }),
+        range_length: None,
+        text: text.to_owned(),
+    }
+}
+
+#[test]
+fn test_apply_changes() {
+    let mut doc = Document::new(1, "local x = 1\nprint(x)\n".to_owned());
+    doc.apply_changes(2, vec![change(Some(((1, 6), (1, 7))), "y")]);
+    assert_eq!(doc.text(), "local x = 1\nprint(y)\n");
+    assert_eq!(doc.version(), 2);
+
+    // Changes are applied in order
+    doc.apply_changes(
+        3,
+        vec![
+            change(Some(((0, 6), (0, 7))), "y"),
+            change(Some(((2, 0), (2, 0))), "print(1)"),
+        ],
+    );
+    assert_eq!(doc.text(), "local y = 1\nprint(y)\nprint(1)");
+
+    doc.apply_changes(4, vec![change(None, "return")]);
+    assert_eq!(doc.text(), "return");
+}
+
+#[test]
+fn test_apply_changes_utf16() {
+    // `𝄞` is two UTF-16 code units and four bytes
+    let mut doc = Document::new(1, "s = '𝄞é'".to_owned());
+    doc.apply_changes(2, vec![change(Some(((0, 7), (0, 8))), "e")]);
+    assert_eq!(doc.text(), "s = '𝄞e'");
+    assert_eq!(doc.position(doc.text().len()), Position::new(0, 9));
+    assert_eq!(doc.offset(Position::new(0, 9)), doc.text().len());
+}
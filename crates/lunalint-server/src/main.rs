@@ -1,15 +1,35 @@
-use std::sync::Arc;
+mod document;
 
-use lunalint_core::diagnostics::{LintLevel, LintReport};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use document::Document;
+use lunalint_core::diagnostics::{LintKind, LintLevel, LintReport, LintTag};
 use lunalint_core::location::SourceInfo;
-use lunalint_core::{full_moon, pass, Context};
-use tower_lsp::jsonrpc::Result;
+use lunalint_core::resolver::{OccurrenceKind, Resolver};
+use lunalint_core::workspace::Workspace;
+use lunalint_core::{parse, parse_prefix, pass, rename, Config, Context};
+use tokio::task::AbortHandle;
+use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
+/// Delay before linting a changed document so that a burst of keystrokes is linted once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Stack size of worker threads. The parser is recursive and needs more than the default.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
 #[derive(Debug)]
 struct Backend {
     client: Client,
+    /// Opened documents keyed by URI
+    documents: Arc<Mutex<HashMap<Url, Document>>>,
+    /// Scheduled or running lints. A lint is aborted when the document changes again.
+    pending: Mutex<HashMap<Url, AbortHandle>>,
+    workspaces: Arc<Workspaces>,
 }
 
 #[tower_lsp::async_trait]
@@ -20,14 +40,20 @@ impl LanguageServer for Backend {
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
                         open_close: Some(true),
-                        change: Some(TextDocumentSyncKind::NONE),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
                         save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
-                            // Set true to lint on save
                             include_text: Some(true),
                         })),
                         ..TextDocumentSyncOptions::default()
                     },
                 )),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                })),
                 ..ServerCapabilities::default()
             },
             ..Default::default()
@@ -47,10 +73,16 @@ impl LanguageServer for Backend {
         Ok(())
     }
 
-    async fn did_change(&self, _: DidChangeTextDocumentParams) {
-        self.client
-            .log_message(MessageType::INFO, format!("file changed, ignored"))
-            .await;
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri;
+        {
+            let mut documents = self.documents.lock().unwrap();
+            let Some(doc) = documents.get_mut(&uri) else {
+                return;
+            };
+            doc.apply_changes(params.text_document.version, params.content_changes);
+        }
+        self.schedule_lint(uri, DEBOUNCE);
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
@@ -58,7 +90,9 @@ impl LanguageServer for Backend {
         self.client
             .log_message(MessageType::INFO, format!("file opened: {}", uri))
             .await;
-        self.lint(uri, params.text_document.text).await;
+        let doc = Document::new(params.text_document.version, params.text_document.text);
+        self.documents.lock().unwrap().insert(uri.clone(), doc);
+        self.schedule_lint(uri, Duration::ZERO);
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
@@ -66,88 +100,471 @@ impl LanguageServer for Backend {
         self.client
             .log_message(MessageType::INFO, format!("file saved: {}", uri))
             .await;
-        if let Some(src) = params.text {
-            self.lint(uri, src).await;
+        if let Some(text) = params.text {
+            if let Some(doc) = self.documents.lock().unwrap().get_mut(&uri) {
+                doc.set_text(text);
+            }
+        }
+        // Configuration files or globals and modules of the saved file may have changed, which
+        // other documents of the workspace depend on
+        if let Ok(path) = uri.to_file_path() {
+            self.workspaces.invalidate(&path);
+        }
+        let uris = self
+            .documents
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for uri in uris {
+            self.schedule_lint(uri, Duration::ZERO);
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.lock().unwrap().remove(&uri);
+        if let Some(handle) = self.pending.lock().unwrap().remove(&uri) {
+            handle.abort();
+        }
+        self.client.publish_diagnostics(uri, vec![], None).await;
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position_params;
+        let uri = text_document.uri.clone();
+        let response = self
+            .with_resolver(&text_document.uri, move |doc, src, resolver| {
+                let def_id = resolver.definition_at(doc.offset(position))?;
+                let def = resolver.get_definition(def_id)?;
+                // e.g. `self` of methods
+                if !Arc::ptr_eq(def.loc().src(), src) {
+                    return None;
+                }
+                let range = doc.range(def.loc().range());
+                Some(GotoDefinitionResponse::Scalar(Location::new(uri, range)))
+            })
+            .await;
+        Ok(response.flatten())
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position;
+        let include_declaration = params.context.include_declaration;
+        let uri = text_document.uri.clone();
+        let response = self
+            .with_resolver(&text_document.uri, move |doc, _, resolver| {
+                let def_id = resolver.definition_at(doc.offset(position))?;
+                let locations = resolver
+                    .occurrences(def_id)
+                    .into_iter()
+                    .filter(|o| include_declaration || o.kind() != OccurrenceKind::Definition)
+                    .map(|o| Location::new(uri.clone(), doc.range(o.loc().range())))
+                    .collect();
+                Some(locations)
+            })
+            .await;
+        Ok(response.flatten())
+    }
+
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position_params;
+        let response = self
+            .with_resolver(&text_document.uri, move |doc, _, resolver| {
+                let def_id = resolver.definition_at(doc.offset(position))?;
+                let highlights = resolver
+                    .occurrences(def_id)
+                    .into_iter()
+                    .map(|o| DocumentHighlight {
+                        range: doc.range(o.loc().range()),
+                        kind: Some(match o.kind() {
+                            OccurrenceKind::Read => DocumentHighlightKind::READ,
+                            OccurrenceKind::Definition | OccurrenceKind::Write => {
+                                DocumentHighlightKind::WRITE
+                            }
+                        }),
+                    })
+                    .collect();
+                Some(highlights)
+            })
+            .await;
+        Ok(response.flatten())
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params;
+        let response = self
+            .with_source(&text_document.uri, move |doc, src, config| {
+                let loc = rename::prepare_rename(src, &config, doc.offset(position))?;
+                Some(PrepareRenameResponse::Range(doc.range(loc.range())))
+            })
+            .await;
+        match response {
+            Some(Ok(response)) => Ok(response),
+            Some(Err(msg)) => Err(Error::invalid_params(msg)),
+            None => Ok(None),
+        }
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position;
+        let uri = text_document.uri.clone();
+        let response = self
+            .with_source(&text_document.uri, move |doc, src, config| {
+                let edits = rename::rename(src, &config, doc.offset(position), &params.new_name)?;
+                let edits = edits
+                    .into_iter()
+                    .map(|edit| TextEdit::new(doc.range(edit.range()), edit.new_text().to_owned()))
+                    .collect();
+                Ok::<_, String>(WorkspaceEdit::new(HashMap::from([(uri, edits)])))
+            })
+            .await
+            .map(|response| response.and_then(|edit| edit));
+        match response {
+            Some(Ok(edit)) => Ok(Some(edit)),
+            Some(Err(msg)) => Err(Error::invalid_params(msg)),
+            None => Ok(None),
         }
     }
 }
 
 impl Backend {
-    async fn lint(&self, uri: Url, src: String) {
-        let ast = match full_moon::parse(&src) {
-            Ok(ast) => ast,
-            Err(err) => {
-                self.client
-                    .log_message(MessageType::ERROR, format!("parse error: {}", err))
-                    .await;
+    fn new(client: Client) -> Self {
+        Self {
+            client,
+            documents: Arc::new(Mutex::new(HashMap::new())),
+            pending: Mutex::new(HashMap::new()),
+            workspaces: Arc::new(Workspaces::default()),
+        }
+    }
+
+    /// Run `f` with the latest text of the document and its configuration. Returns `None` if the
+    /// document is not opened, and an error if the configuration file has errors, which would
+    /// make a rename miss builtins and globals declared there.
+    async fn with_source<T: Send + 'static>(
+        &self,
+        uri: &Url,
+        f: impl FnOnce(&Document, &Arc<SourceInfo>, Config) -> T + Send + 'static,
+    ) -> Option<std::result::Result<T, String>> {
+        let doc = self.documents.lock().unwrap().get(uri).cloned()?;
+        let uri = uri.clone();
+        tokio::task::spawn_blocking(move || {
+            let src = Arc::new(SourceInfo::new(uri.to_string(), doc.text().to_owned()));
+            // Globals declared in the configuration are builtins
+            let (config, reports) = match uri.to_file_path() {
+                Ok(path) => Config::discover(&path),
+                Err(()) => (Config::default(), Vec::new()),
+            };
+            if let Some(report) = reports.first() {
+                return Err(format!(
+                    "Invalid configuration file `{}`: {}",
+                    report.loc().src().path(),
+                    report.msg()
+                ));
+            }
+            Ok(f(&doc, &src, config))
+        })
+        .await
+        .ok()
+    }
+
+    /// Resolve names in the latest text of the document and run `f` with the resolver. Returns
+    /// `None` if the document is not opened or cannot be parsed.
+    async fn with_resolver<T: Send + 'static>(
+        &self,
+        uri: &Url,
+        f: impl FnOnce(&Document, &Arc<SourceInfo>, &Resolver) -> T + Send + 'static,
+    ) -> Option<T> {
+        let doc = self.documents.lock().unwrap().get(uri).cloned()?;
+        let uri = uri.clone();
+        let workspaces = Arc::clone(&self.workspaces);
+        tokio::task::spawn_blocking(move || {
+            let ctx = analyze(&uri, doc.text(), &workspaces)?;
+            Some(f(&doc, ctx.src(), ctx.resolver()))
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// Lint the document after `delay`. A previously scheduled lint of the document is cancelled,
+    /// and results are discarded if the document changed while linting, so that diagnostics never
+    /// refer to an older version of the buffer.
+    fn schedule_lint(&self, uri: Url, delay: Duration) {
+        let client = self.client.clone();
+        let documents = Arc::clone(&self.documents);
+        let workspaces = Arc::clone(&self.workspaces);
+        let task_uri = uri.clone();
+        let task = tokio::spawn(async move {
+            let uri = task_uri;
+            tokio::time::sleep(delay).await;
+            let Some(doc) = documents.lock().unwrap().get(&uri).cloned() else {
+                return;
+            };
+
+            let lint_uri = uri.clone();
+            let version = doc.version();
+            let Ok(result) =
+                tokio::task::spawn_blocking(move || lint(&lint_uri, doc.text(), &workspaces)).await
+            else {
+                return;
+            };
+
+            let latest = documents.lock().unwrap().get(&uri).map(Document::version);
+            if latest != Some(version) {
                 return;
             }
+            if let Some((config_uri, diags)) = result.config {
+                client.publish_diagnostics(config_uri, diags, None).await;
+            }
+            client
+                .publish_diagnostics(uri, result.diags, Some(version))
+                .await;
+        });
+        if let Some(prev) = self
+            .pending
+            .lock()
+            .unwrap()
+            .insert(uri, task.abort_handle())
+        {
+            prev.abort();
+        }
+    }
+}
+
+struct LintResult {
+    diags: Vec<Diagnostic>,
+    /// Errors in the configuration file, which are published to the configuration file itself
+    config: Option<ConfigDiagnostics>,
+}
+
+/// Configuration file and its errors
+type ConfigDiagnostics = (Url, Vec<Diagnostic>);
+
+/// Workspace and errors in its configuration file
+type WorkspaceEntry = (Arc<Workspace>, Vec<Diagnostic>);
+
+/// Workspaces of opened documents, by their configuration files. Files in the directory of a
+/// configuration file are linted together as `lunalint <DIR>` does.
+#[derive(Debug, Default)]
+struct Workspaces {
+    workspaces: Mutex<HashMap<PathBuf, WorkspaceEntry>>,
+}
+
+impl Workspaces {
+    /// Workspace of the file, and errors in its configuration file. Files without configuration
+    /// files are linted alone.
+    fn get(&self, file: Option<&Path>) -> (Arc<Workspace>, Option<ConfigDiagnostics>) {
+        let Some(config_path) = file.and_then(Config::find) else {
+            let workspace = Workspace::new(Arc::new(Config::default()));
+            if let Some(file) = file {
+                workspace.add_file(file);
+            }
+            return (Arc::new(workspace), None);
         };
-        self.client
-            .log_message(MessageType::ERROR, format!("parse finished"))
-            .await;
 
-        let src = Arc::new(SourceInfo::new(uri.to_string(), src));
-        let reports = {
-            let mut ctx = Context::new(src);
-            ctx.resolver_mut().go(&ast);
-
-            let ctx = Arc::new(ctx);
-            let mut pass_manager = pass::PassManager::with_all_passes(Arc::clone(&ctx));
-            pass_manager.run(&ast);
-
-            let reports = ctx
-                .reports()
-                .iter()
-                .map(|r| Arc::clone(r))
-                .collect::<Vec<_>>();
-            reports
+        let cached = self.workspaces.lock().unwrap().get(&config_path).cloned();
+        let (workspace, diags) = match cached {
+            Some(entry) => entry,
+            None => {
+                // The directory is walked without the lock so that other documents are linted
+                // meanwhile. A workspace loaded by another document in the meantime is kept.
+                let entry = load_workspace(&config_path);
+                let mut workspaces = self.workspaces.lock().unwrap();
+                workspaces
+                    .entry(config_path.clone())
+                    .or_insert(entry)
+                    .clone()
+            }
         };
+        if let Some(file) = file {
+            workspace.add_file(file);
+        }
+        let config_diags = path_to_uri(&config_path).map(|uri| (uri, diags));
+        (workspace, config_diags)
+    }
+
+    /// Forget what depends on the saved file: the whole workspace if it is the configuration
+    /// file, or the summary of the file otherwise.
+    fn invalidate(&self, file: &Path) {
+        let Some(config_path) = Config::find(file) else {
+            return;
+        };
+        let mut workspaces = self.workspaces.lock().unwrap();
+        if std::path::absolute(&config_path).ok().as_deref() == Some(file) {
+            workspaces.remove(&config_path);
+        } else if let Some((workspace, _)) = workspaces.get(&config_path) {
+            workspace.invalidate(file);
+        }
+    }
+}
+
+/// Load the configuration file and the workspace of Lua files in its directory.
+fn load_workspace(config_path: &Path) -> WorkspaceEntry {
+    let (config, reports) = Config::load(config_path);
+    let workspace = Workspace::new(Arc::new(config));
+    if let Some(root) = config_path.parent() {
+        for file in lua_files(root) {
+            workspace.add_file(&file);
+        }
+    }
+    (
+        Arc::new(workspace),
+        reports.iter().map(report_to_diag).collect(),
+    )
+}
+
+/// Lua files in the directory honoring `.gitignore`.
+fn lua_files(dir: &Path) -> Vec<PathBuf> {
+    ignore::WalkBuilder::new(dir)
+        .require_git(false)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+        .collect()
+}
+
+/// Source of the document and its workspace, and errors in the configuration file of the
+/// workspace.
+fn source(
+    uri: &Url,
+    text: &str,
+    workspaces: &Workspaces,
+) -> (Arc<SourceInfo>, Arc<Workspace>, Option<ConfigDiagnostics>) {
+    let path = uri.to_file_path().ok();
+    let (workspace, config_diags) = workspaces.get(path.as_deref());
+
+    // Files are named by their paths so that the workspace finds them
+    let name = match &path {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => uri.to_string(),
+    };
+    let src = Arc::new(SourceInfo::new(name, text.to_owned()));
+    (src, workspace, config_diags)
+}
 
-        let mut diags = vec![];
-        for report in reports {
+/// Analyze the text of a document for navigation. Returns `None` if the text cannot be parsed.
+/// Unlike renaming, navigation uses the valid part of a configuration file with errors.
+fn analyze(uri: &Url, text: &str, workspaces: &Workspaces) -> Option<Context> {
+    let (src, workspace, _) = source(uri, text, workspaces);
+    let ast = parse(Arc::clone(&src)).ok()?;
+    let mut ctx = Context::with_workspace(src, workspace);
+    ctx.analyze(&ast);
+    Some(ctx)
+}
+
+fn lint(uri: &Url, text: &str, workspaces: &Workspaces) -> LintResult {
+    let (src, workspace, config_diags) = source(uri, text, workspaces);
+    let mut diags = vec![];
+    // Only syntactic passes can run on the parsable prefix of a file with a syntax error since
+    // names may be defined or used after the error
+    let (ast, syntactic_only) = match parse(Arc::clone(&src)) {
+        Ok(ast) => (Some(ast), false),
+        Err(report) => {
             diags.push(report_to_diag(&report));
+            (parse_prefix(&src, &report), true)
+        }
+    };
+
+    if let Some(ast) = ast {
+        let mut ctx = Context::with_workspace(src, workspace);
+        ctx.analyze(&ast);
+
+        let ctx = Arc::new(ctx);
+        let mut pass_manager = if syntactic_only {
+            pass::PassManager::with_syntactic_passes(Arc::clone(&ctx))
+        } else {
+            pass::PassManager::with_all_passes(Arc::clone(&ctx))
+        };
+        pass_manager.run(&ast);
+        diags.extend(ctx.reports().iter().map(|r| report_to_diag(r)));
+    }
+
+    LintResult {
+        diags,
+        config: config_diags,
+    }
+}
+
+/// Convert a path of [lunalint_core::location::SourceInfo] to a URI. Paths of opened documents are
+/// already URIs.
+fn path_to_uri(path: impl AsRef<std::path::Path>) -> Option<Url> {
+    let path = path.as_ref();
+    if let Ok(uri) = Url::parse(&path.to_string_lossy()) {
+        if uri.scheme() == "file" {
+            return Some(uri);
         }
-        self.client.publish_diagnostics(uri, diags, None).await;
     }
+    Url::from_file_path(std::path::absolute(path).ok()?).ok()
 }
 
 fn lintlevel_to_severity(level: &LintLevel) -> DiagnosticSeverity {
     match level {
         LintLevel::Error => DiagnosticSeverity::ERROR,
         LintLevel::Warning => DiagnosticSeverity::WARNING,
+        LintLevel::Information => DiagnosticSeverity::INFORMATION,
+        LintLevel::Hint => DiagnosticSeverity::HINT,
     }
 }
 
-fn lintloc_to_lsploc(loc: &lunalint_core::location::Location) -> Location {
-    Location {
-        uri: Url::from_file_path(loc.src().path()).unwrap(),
-        range: Range {
-            start: lintpos_to_lsppos(&loc.start()),
-            end: lintpos_to_lsppos(&loc.end()),
-        },
+fn linttag_to_tag(tag: &LintTag) -> DiagnosticTag {
+    match tag {
+        LintTag::Unnecessary => DiagnosticTag::UNNECESSARY,
     }
 }
 
-fn lintpos_to_lsppos(pos: &lunalint_core::location::Position) -> Position {
-    Position {
-        line: pos.line() as u32 - 1,
-        character: pos.character() as u32 - 1,
-    }
+fn lintloc_to_lsploc(loc: &lunalint_core::location::Location) -> Option<Location> {
+    Some(Location {
+        uri: path_to_uri(loc.src().path())?,
+        range: lintloc_to_lsprange(loc),
+    })
+}
+
+/// Range of the location counted in UTF-16 code units like [Document::range].
+fn lintloc_to_lsprange(loc: &lunalint_core::location::Location) -> Range {
+    document::range_of(loc.src().content(), loc.range())
 }
 
 fn report_to_diag(report: &LintReport) -> Diagnostic {
-    let loc = report.loc();
-    let start = lintpos_to_lsppos(&loc.start());
-    let end = lintpos_to_lsppos(&loc.end());
+    let range = lintloc_to_lsprange(&report.loc());
     let severity = lintlevel_to_severity(&report.level());
 
     let mut related_information = None;
     if !report.labels().is_empty() {
         let mut infos = vec![];
         for label in report.labels() {
+            let Some(location) = lintloc_to_lsploc(&label.loc()) else {
+                continue;
+            };
             let info = DiagnosticRelatedInformation {
-                location: lintloc_to_lsploc(&label.loc()),
+                location,
                 message: label.msg().to_owned(),
             };
             infos.push(info);
@@ -155,24 +572,133 @@ fn report_to_diag(report: &LintReport) -> Diagnostic {
         related_information = Some(infos);
     }
 
+    let tags = (!report.tags().is_empty())
+        .then(|| report.tags().iter().map(linttag_to_tag).collect::<Vec<_>>());
+
+    // Messages of parse errors are generic and details are in labels
+    let message = match (report.kind(), report.labels().first()) {
+        (LintKind::ParseError, Some(label)) => format!("{}: {}", report.msg(), label.msg()),
+        _ => report.msg().to_owned(),
+    };
+
     Diagnostic {
-        range: Range { start, end },
+        range,
         severity: Some(severity),
-        code: None,
+        code: Some(NumberOrString::String(report.name().to_owned())),
         code_description: None,
         source: Some("lunalint".to_owned()),
-        message: report.msg().to_owned(),
+        message,
         related_information,
-        tags: None,
+        tags,
         data: None,
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_stack_size(STACK_SIZE)
+        .enable_all()
+        .build()
+        .expect("failed to build tokio runtime");
+    runtime.block_on(async {
+        let stdin = tokio::io::stdin();
+        let stdout = tokio::io::stdout();
+
+        let (service, socket) = LspService::new(Backend::new);
+        Server::new(stdin, stdout, socket).serve(service).await;
+    });
+}
+
+#[test]
+fn test_report_range_utf16() {
+    // `𝄞` is two UTF-16 code units but one character
+    let src = Arc::new(SourceInfo::new(
+        "test.lua".to_owned(),
+        "s = '𝄞' .. x".to_owned(),
+    ));
+    let Ok(ast) = parse(Arc::clone(&src)) else {
+        panic!("failed to parse");
+    };
+    let mut ctx = Context::with_config(Arc::clone(&src), Arc::new(Config::default()));
+    ctx.analyze(&ast);
+    let ctx = Arc::new(ctx);
+    pass::PassManager::with_all_passes(Arc::clone(&ctx)).run(&ast);
+
+    let reports = ctx.reports();
+    let undefined = reports
+        .iter()
+        .find(|r| r.name() == "undefined-global")
+        .unwrap();
+    let diag = report_to_diag(undefined);
+    assert_eq!(
+        diag.range,
+        Range::new(Position::new(0, 12), Position::new(0, 13))
+    );
+}
+
+#[test]
+fn test_lint_workspace() {
+    let path = std::path::absolute("../lunalint-core/tests/lua/workspace/main.lua").unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    let result = lint(&uri, &text, &Workspaces::default());
+
+    // Globals of other files and fields of required modules are known
+    let mut messages = result
+        .diags
+        .iter()
+        .map(|diag| diag.message.as_str())
+        .collect::<Vec<_>>();
+    messages.sort();
+    assert_eq!(
+        messages,
+        [
+            "Indexing a possibly `nil` value",
+            "Module `missing.module` not found",
+            "Undefined field `trimm` of `util`",
+            "Undefined global `Logg`",
+        ]
+    );
+}
+
+#[test]
+fn test_analyze_with_invalid_config() {
+    let path = std::path::absolute("../lunalint-core/tests/lua/config-invalid/main.lua").unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+    let Some(ctx) = analyze(&uri, "local x = 1\nprint(x)", &Workspaces::default()) else {
+        panic!("failed to analyze");
+    };
+    let resolver = ctx.resolver();
+    let def_id = resolver.definition_at(18).unwrap();
+    assert_eq!(resolver.get_definition(def_id).unwrap().name(), "x");
+}
+
+#[test]
+fn test_invalidate_saved_file() {
+    let dir = std::env::temp_dir().join(format!("lunalintd-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lunalint.toml"), "").unwrap();
+    std::fs::write(dir.join("counter.lua"), "Counter = 0\n").unwrap();
+    let main = dir.join("main.lua");
+    let uri = Url::from_file_path(&main).unwrap();
+    let text = "print(Counter)\n";
+
+    let workspaces = Workspaces::default();
+    assert!(lint(&uri, text, &workspaces).diags.is_empty());
+    let (workspace, _) = workspaces.get(Some(&main));
+
+    std::fs::write(dir.join("counter.lua"), "Count = 0\n").unwrap();
+    workspaces.invalidate(&dir.join("counter.lua"));
+    let result = lint(&uri, text, &workspaces);
+    // Only the summary of the saved file is forgotten
+    let (latest, _) = workspaces.get(Some(&main));
+    std::fs::remove_dir_all(&dir).unwrap();
 
-    let (service, socket) = LspService::new(|client| Backend { client });
-    Server::new(stdin, stdout, socket).serve(service).await;
+    assert!(Arc::ptr_eq(&workspace, &latest));
+    let messages = result
+        .diags
+        .iter()
+        .map(|diag| diag.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(messages, ["Undefined global `Counter`"]);
 }